@@ -4,3 +4,4 @@ set -e -u
 cargo fmt --all
 cargo clippy --all-targets --all-features -- -D warnings
 cargo test
+cargo test --all-features
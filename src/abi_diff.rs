@@ -0,0 +1,270 @@
+use crate::imports::Export;
+use crate::types;
+use crate::{File, ParseError};
+use std::collections::{HashMap, HashSet};
+
+/// Whether a change keeps binaries built against the old version working.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compatibility {
+    /// Existing binaries keep working
+    Compatible,
+    /// Existing binaries may fail to load or misbehave
+    Breaking,
+}
+
+/// A single ABI difference between two versions of a shared library.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AbiChange {
+    /// An exported symbol is no longer exported
+    ExportRemoved {
+        name: String,
+        version: Option<String>,
+    },
+    /// A new symbol is exported
+    ExportAdded {
+        name: String,
+        version: Option<String>,
+    },
+    /// An exported data object changed size
+    SizeChanged {
+        name: String,
+        old_size: u64,
+        new_size: u64,
+    },
+    /// An exported symbol is only available under a different version
+    VersionChanged {
+        name: String,
+        old_version: Option<String>,
+        new_version: Option<String>,
+    },
+    /// The DT_SONAME changed
+    SonameChanged {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// A new DT_NEEDED entry was added
+    NeededAdded { name: String },
+}
+
+impl AbiChange {
+    /// Classify the change.
+    ///
+    /// Removing symbols or versions breaks binaries that bind to them and a
+    /// changed data object size breaks binaries with copy relocations. A new
+    /// SONAME means existing binaries no longer find the library.
+    pub fn compatibility(&self) -> Compatibility {
+        match self {
+            AbiChange::ExportAdded { .. } | AbiChange::NeededAdded { .. } => {
+                Compatibility::Compatible
+            }
+            AbiChange::ExportRemoved { .. }
+            | AbiChange::SizeChanged { .. }
+            | AbiChange::VersionChanged { .. }
+            | AbiChange::SonameChanged { .. } => Compatibility::Breaking,
+        }
+    }
+}
+
+impl std::fmt::Display for AbiChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fn versioned(name: &str, version: &Option<String>) -> String {
+            match version {
+                Some(version) => format!("{}@{}", name, version),
+                None => name.to_string(),
+            }
+        }
+        match self {
+            AbiChange::ExportRemoved { name, version } => {
+                write!(f, "removed export {}", versioned(name, version))
+            }
+            AbiChange::ExportAdded { name, version } => {
+                write!(f, "added export {}", versioned(name, version))
+            }
+            AbiChange::SizeChanged {
+                name,
+                old_size,
+                new_size,
+            } => write!(
+                f,
+                "size of {} changed from {} to {}",
+                name, old_size, new_size
+            ),
+            AbiChange::VersionChanged {
+                name,
+                old_version,
+                new_version,
+            } => write!(
+                f,
+                "version of {} changed from {} to {}",
+                name,
+                old_version.as_deref().unwrap_or("none"),
+                new_version.as_deref().unwrap_or("none")
+            ),
+            AbiChange::SonameChanged { old, new } => write!(
+                f,
+                "SONAME changed from {} to {}",
+                old.as_deref().unwrap_or("none"),
+                new.as_deref().unwrap_or("none")
+            ),
+            AbiChange::NeededAdded { name } => write!(f, "new dependency {}", name),
+        }
+    }
+}
+
+/// The ABI differences between two versions of a shared library.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbiDiff {
+    pub changes: Vec<AbiChange>,
+}
+
+impl AbiDiff {
+    /// Returns true if no change breaks existing binaries
+    pub fn is_compatible(&self) -> bool {
+        self.breaking().next().is_none()
+    }
+
+    /// The changes that break existing binaries
+    pub fn breaking(&self) -> impl Iterator<Item = &AbiChange> {
+        self.changes
+            .iter()
+            .filter(|change| change.compatibility() == Compatibility::Breaking)
+    }
+}
+
+/// Index exports by name and version, keeping the first of any duplicates.
+fn by_version(exports: &[Export]) -> HashMap<(&str, &Option<String>), &Export> {
+    let mut index = HashMap::new();
+    for export in exports {
+        index
+            .entry((export.symbol.name.as_str(), &export.version))
+            .or_insert(export);
+    }
+    index
+}
+
+/// Compare the exported ABI of two versions of a shared library.
+pub fn diff_abi(old: &File, new: &File) -> Result<AbiDiff, ParseError> {
+    let mut changes = Vec::new();
+
+    let old_soname = old.get_soname()?;
+    let new_soname = new.get_soname()?;
+    if old_soname != new_soname {
+        changes.push(AbiChange::SonameChanged {
+            old: old_soname,
+            new: new_soname,
+        });
+    }
+
+    let old_exports = old.get_exports()?;
+    let new_exports = new.get_exports()?;
+    let old_index = by_version(&old_exports);
+    let new_index = by_version(&new_exports);
+    let mut new_defaults = HashMap::new();
+    for export in new_exports.iter().filter(|export| export.default_version) {
+        new_defaults
+            .entry(export.symbol.name.as_str())
+            .or_insert(export);
+    }
+
+    // Name and new version of every export reported as a version change
+    let mut version_changes = HashSet::new();
+    for export in old_exports.iter() {
+        let name = &export.symbol.name;
+        match new_index.get(&(name.as_str(), &export.version)) {
+            Some(new_export) => {
+                if export.symbol.symtype == types::STT_OBJECT
+                    && export.symbol.size != new_export.symbol.size
+                {
+                    changes.push(AbiChange::SizeChanged {
+                        name: name.clone(),
+                        old_size: export.symbol.size,
+                        new_size: new_export.symbol.size,
+                    });
+                }
+            }
+            None => match new_defaults.get(name.as_str()) {
+                Some(new_export) => {
+                    version_changes.insert((name.as_str(), &new_export.version));
+                    changes.push(AbiChange::VersionChanged {
+                        name: name.clone(),
+                        old_version: export.version.clone(),
+                        new_version: new_export.version.clone(),
+                    })
+                }
+                None => changes.push(AbiChange::ExportRemoved {
+                    name: name.clone(),
+                    version: export.version.clone(),
+                }),
+            },
+        }
+    }
+
+    for export in new_exports.iter() {
+        let name = &export.symbol.name;
+        let key = (name.as_str(), &export.version);
+        if old_index.contains_key(&key) {
+            continue;
+        }
+        // Already reported as a version change of the old export
+        if !version_changes.contains(&key) {
+            changes.push(AbiChange::ExportAdded {
+                name: name.clone(),
+                version: export.version.clone(),
+            });
+        }
+    }
+
+    let old_needed = old.get_needed()?;
+    for name in new.get_needed()? {
+        if !old_needed.contains(&name) {
+            changes.push(AbiChange::NeededAdded { name });
+        }
+    }
+
+    Ok(AbiDiff { changes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_abi, AbiChange};
+    use crate::File;
+
+    #[test]
+    fn test_diff_abi() {
+        let old = File::open_path("tests/samples/libabi_v1.so").unwrap();
+        let new = File::open_path("tests/samples/libabi_v2.so").unwrap();
+        let diff = diff_abi(&old, &new).unwrap();
+        let v1 = Some("LIBABI_1.0".to_string());
+        let v2 = Some("LIBABI_2.0".to_string());
+
+        assert!(diff.changes.contains(&AbiChange::SonameChanged {
+            old: Some("libabi.so.1".to_string()),
+            new: Some("libabi.so.2".to_string()),
+        }));
+        assert!(diff.changes.contains(&AbiChange::ExportRemoved {
+            name: "abi_old".to_string(),
+            version: v1.clone(),
+        }));
+        assert!(diff.changes.contains(&AbiChange::ExportAdded {
+            name: "abi_new".to_string(),
+            version: v2.clone(),
+        }));
+        assert!(diff.changes.contains(&AbiChange::SizeChanged {
+            name: "abi_counter".to_string(),
+            old_size: 16,
+            new_size: 32,
+        }));
+        assert!(diff.changes.contains(&AbiChange::VersionChanged {
+            name: "abi_add".to_string(),
+            old_version: v1,
+            new_version: v2,
+        }));
+        assert!(diff.changes.contains(&AbiChange::NeededAdded {
+            name: "libm.so.6".to_string(),
+        }));
+        assert_eq!(diff.changes.len(), 6);
+        assert!(!diff.is_compatible());
+
+        assert!(diff_abi(&old, &old).unwrap().changes.is_empty());
+    }
+}
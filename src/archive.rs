@@ -0,0 +1,538 @@
+use crate::types;
+use crate::{File, ParseError};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Magic string of a regular archive
+const ARMAG: &[u8] = b"!<arch>\n";
+/// Magic string of a thin archive
+const ARMAG_THIN: &[u8] = b"!<thin>\n";
+/// Terminator of a member header
+const ARFMAG: &[u8] = b"`\n";
+/// Size of a member header
+const HEADER_SIZE: usize = 60;
+/// Longest member name stored directly in a System V header
+const MAX_SHORT_NAME: usize = 15;
+
+/// A file stored in an archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Member {
+    /// Member file name
+    pub name: String,
+    /// Modification time in seconds since the epoch
+    pub date: u64,
+    /// Owner user ID
+    pub uid: u32,
+    /// Owner group ID
+    pub gid: u32,
+    /// File mode
+    pub mode: u32,
+    /// Member contents. For thin archives these are read from the file the
+    /// member name refers to, relative to the archive, by [Archive::open_path]
+    /// and are empty after [Archive::parse].
+    pub data: Vec<u8>,
+}
+
+impl Member {
+    /// Create a member with default metadata, as `ar D` does.
+    pub fn new<T: Into<String>>(name: T, data: Vec<u8>) -> Member {
+        Member {
+            name: name.into(),
+            date: 0,
+            uid: 0,
+            gid: 0,
+            mode: 0o644,
+            data,
+        }
+    }
+
+    /// Parse the member contents as an ELF file.
+    pub fn parse(&self) -> Result<File, ParseError> {
+        File::open_stream(&mut io::Cursor::new(&self.data))
+    }
+}
+
+/// An entry of the archive symbol index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveSymbol {
+    /// Symbol name
+    pub name: String,
+    /// Index of the defining member in [Archive::members]
+    pub member: usize,
+}
+
+/// A static library in `ar` format.
+///
+/// Reading supports the System V/GNU variant with the `/` and `/SYM64/`
+/// symbol indexes and the `//` long name table, the BSD variant with `#1/`
+/// names and the `__.SYMDEF` symbol index, and GNU thin archives.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Archive {
+    /// Whether member contents are stored outside of the archive
+    pub thin: bool,
+    /// Archive members, excluding the symbol index and long name table
+    pub members: Vec<Member>,
+    /// Symbol index, mapping global symbols to the members defining them
+    pub symbols: Vec<ArchiveSymbol>,
+}
+
+fn field(header: &[u8], start: usize, len: usize) -> &str {
+    std::str::from_utf8(&header[start..start + len])
+        .unwrap_or("")
+        .trim_end()
+}
+
+fn number(header: &[u8], start: usize, len: usize, radix: u32) -> Result<u64, ParseError> {
+    let text = field(header, start, len);
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, radix).map_err(|_| {
+        ParseError::InvalidFormat(Some(format!("Invalid archive header field {:?}", text)))
+    })
+}
+
+fn c_string(data: &[u8], offset: usize) -> Result<String, ParseError> {
+    let data = data.get(offset..).unwrap_or_default();
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    Ok(String::from_utf8(data[..end].to_vec())?)
+}
+
+fn truncated() -> ParseError {
+    ParseError::InvalidFormat(Some("Truncated archive".to_string()))
+}
+
+impl Archive {
+    /// Create an empty archive.
+    pub fn new() -> Archive {
+        Archive::default()
+    }
+
+    /// Read an archive from a file, including the members of thin archives.
+    pub fn open_path<T: AsRef<Path>>(path: T) -> Result<Archive, ParseError> {
+        let path = path.as_ref();
+        let mut archive = Archive::parse(&fs::read(path)?)?;
+        if archive.thin {
+            let dir = path.parent().unwrap_or_else(|| Path::new(""));
+            for member in archive.members.iter_mut() {
+                member.data = fs::read(dir.join(&member.name))?;
+            }
+        }
+        Ok(archive)
+    }
+
+    /// Parse the contents of an archive file.
+    pub fn parse(data: &[u8]) -> Result<Archive, ParseError> {
+        let thin = if data.starts_with(ARMAG) {
+            false
+        } else if data.starts_with(ARMAG_THIN) {
+            true
+        } else {
+            return Err(ParseError::InvalidMagic);
+        };
+
+        let mut archive = Archive {
+            thin,
+            ..Default::default()
+        };
+        let mut long_names: &[u8] = &[];
+        // Symbol index entries as (name, member header offset)
+        let mut index: Vec<(String, u64)> = Vec::new();
+        // Header offset of each member in `archive.members`
+        let mut offsets = Vec::new();
+
+        let mut offset = ARMAG.len();
+        while offset < data.len() {
+            let header = data
+                .get(offset..offset + HEADER_SIZE)
+                .ok_or_else(truncated)?;
+            if &header[58..60] != ARFMAG {
+                return Err(ParseError::InvalidFormat(Some(format!(
+                    "Invalid archive member header at {:#x}",
+                    offset
+                ))));
+            }
+            let raw_name = field(header, 0, 16);
+            let size = number(header, 48, 10, 10)? as usize;
+            let start = offset + HEADER_SIZE;
+            let special = raw_name == "/" || raw_name == "/SYM64/" || raw_name == "//";
+            // Thin archives only store the special members
+            let stored = if thin && !special { 0 } else { size };
+            let mut contents = data.get(start..start + stored).ok_or_else(truncated)?;
+            let header_offset = offset as u64;
+            offset = start + stored + (stored & 1);
+
+            let name = if raw_name == "/" {
+                index.extend(parse_gnu_index(contents, 4)?);
+                continue;
+            } else if raw_name == "/SYM64/" {
+                index.extend(parse_gnu_index(contents, 8)?);
+                continue;
+            } else if raw_name == "//" {
+                long_names = contents;
+                continue;
+            } else if let Some(len) = raw_name.strip_prefix("#1/") {
+                let len: usize = len.parse().map_err(|_| {
+                    ParseError::InvalidFormat(Some(format!("Invalid BSD name {:?}", raw_name)))
+                })?;
+                let name = contents.get(..len).ok_or_else(truncated)?;
+                contents = &contents[len..];
+                let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+                String::from_utf8(name[..end].to_vec())?
+            } else if let Some(position) = raw_name.strip_prefix('/') {
+                let position: usize = position.parse().map_err(|_| {
+                    ParseError::InvalidFormat(Some(format!("Invalid long name {:?}", raw_name)))
+                })?;
+                let name = long_names.get(position..).ok_or_else(truncated)?;
+                let end = name
+                    .windows(2)
+                    .position(|w| w == b"/\n")
+                    .or_else(|| name.iter().position(|&b| b == b'\n'))
+                    .unwrap_or(name.len());
+                String::from_utf8(name[..end].to_vec())?
+            } else {
+                raw_name.strip_suffix('/').unwrap_or(raw_name).to_string()
+            };
+
+            if name == "__.SYMDEF" || name == "__.SYMDEF SORTED" {
+                index.extend(parse_bsd_index(contents)?);
+                continue;
+            }
+
+            archive.members.push(Member {
+                name,
+                date: number(header, 16, 12, 10)?,
+                uid: number(header, 28, 6, 10)? as u32,
+                gid: number(header, 34, 6, 10)? as u32,
+                mode: number(header, 40, 8, 8)? as u32,
+                data: if thin { Vec::new() } else { contents.to_vec() },
+            });
+            offsets.push(header_offset);
+        }
+
+        for (name, header_offset) in index {
+            let member = offsets
+                .iter()
+                .position(|&offset| offset == header_offset)
+                .ok_or(ParseError::InvalidFormat(Some(format!(
+                    "Symbol {} refers to no archive member",
+                    name
+                ))))?;
+            archive.symbols.push(ArchiveSymbol { name, member });
+        }
+        Ok(archive)
+    }
+
+    /// Get the member that defines a symbol according to the symbol index.
+    pub fn find_symbol(&self, name: &str) -> Option<&Member> {
+        self.symbols
+            .iter()
+            .find(|symbol| symbol.name == name)
+            .map(|symbol| &self.members[symbol.member])
+    }
+
+    /// Find the member that defines a symbol, matching either the raw or the
+    /// demangled symbol name.
+    #[cfg(feature = "demangle")]
+    pub fn find_demangled_symbol(
+        &self,
+        name: &str,
+        options: &crate::demangle::DemangleOptions,
+    ) -> Option<&Member> {
+        self.symbols
+            .iter()
+            .find(|symbol| {
+                symbol.name == name
+                    || crate::demangle::demangle(&symbol.name, options)
+                        .is_some_and(|demangled| demangled == name)
+            })
+            .map(|symbol| &self.members[symbol.member])
+    }
+
+    /// Rebuild the symbol index from the global and weak symbols defined by
+    /// the members that are ELF files. Other members are skipped.
+    pub fn update_symbols(&mut self) -> Result<(), ParseError> {
+        let mut symbols = Vec::new();
+        for (index, member) in self.members.iter().enumerate() {
+            if !member.data.starts_with(b"\x7fELF") {
+                continue;
+            }
+            let file = member.parse()?;
+            let symtab = match file
+                .sections
+                .iter()
+                .find(|section| section.shdr.shtype == types::SectionType::Symtab)
+            {
+                Some(symtab) => symtab,
+                None => continue,
+            };
+            for symbol in file.get_symbols(symtab)? {
+                let defined =
+                    symbol.shndx.is_defined() || symbol.shndx == types::SectionIndex::Common;
+                if symbol.bind != types::STB_LOCAL && defined && !symbol.name.is_empty() {
+                    symbols.push(ArchiveSymbol {
+                        name: symbol.name,
+                        member: index,
+                    });
+                }
+            }
+        }
+        self.symbols = symbols;
+        Ok(())
+    }
+
+    /// Write this archive to the given path.
+    pub fn write_path<T: AsRef<Path>>(&self, path: T) -> Result<(), ParseError> {
+        let mut file = fs::File::create(path)?;
+        self.write_stream(&mut file)
+    }
+
+    /// Serialize this archive in the System V/GNU format.
+    ///
+    /// The symbol index is regenerated from the members, see
+    /// [Archive::update_symbols]. Names longer than 15 characters go into a
+    /// `//` long name table. The 64-bit `/SYM64/` index is used if member
+    /// offsets do not fit in 32 bits. Thin archives are written with member
+    /// names only.
+    pub fn write_stream<T: io::Write>(&self, io_file: &mut T) -> Result<(), ParseError> {
+        let mut archive = self.clone();
+        archive.update_symbols()?;
+
+        // Long name table and the name field of each member
+        let mut long_names = Vec::new();
+        let mut names = Vec::new();
+        for member in archive.members.iter() {
+            if member.name.len() > MAX_SHORT_NAME || archive.thin {
+                names.push(format!("/{}", long_names.len()));
+                long_names.extend_from_slice(member.name.as_bytes());
+                long_names.extend_from_slice(b"/\n");
+            } else {
+                names.push(format!("{}/", member.name));
+            }
+        }
+
+        // GNU ar counts the padding of the long name table in its size
+        if long_names.len() % 2 == 1 {
+            long_names.push(b'\n');
+        }
+
+        let padded = |len: usize| len + (len & 1);
+        let strings: usize = archive
+            .symbols
+            .iter()
+            .map(|symbol| symbol.name.len() + 1)
+            .sum();
+        let members_size = |index_size: usize| -> u64 {
+            let mut offset = (ARMAG.len() + HEADER_SIZE + padded(index_size)) as u64;
+            if !long_names.is_empty() {
+                offset += (HEADER_SIZE + padded(long_names.len())) as u64;
+            }
+            offset
+        };
+        // Member header offsets, given the size of the symbol index
+        let layout = |index_size: usize| -> Vec<u64> {
+            let mut offset = members_size(index_size);
+            archive
+                .members
+                .iter()
+                .map(|member| {
+                    let here = offset;
+                    let stored = if archive.thin { 0 } else { member.data.len() };
+                    offset += (HEADER_SIZE + padded(stored)) as u64;
+                    here
+                })
+                .collect()
+        };
+        let mut width = 4;
+        let mut offsets = layout(width * (archive.symbols.len() + 1) + strings);
+        if offsets
+            .last()
+            .is_some_and(|&offset| offset > u32::MAX as u64)
+        {
+            width = 8;
+            offsets = layout(width * (archive.symbols.len() + 1) + strings);
+        }
+
+        let mut index = vec![0u8; width * (archive.symbols.len() + 1)];
+        let put = |buf: &mut [u8], value: u64| match width {
+            4 => BigEndian::write_u32(buf, value as u32),
+            _ => BigEndian::write_u64(buf, value),
+        };
+        put(&mut index[..width], archive.symbols.len() as u64);
+        for (i, symbol) in archive.symbols.iter().enumerate() {
+            let start = width * (i + 1);
+            put(&mut index[start..start + width], offsets[symbol.member]);
+        }
+        for symbol in archive.symbols.iter() {
+            index.extend_from_slice(symbol.name.as_bytes());
+            index.push(0);
+        }
+
+        io_file.write_all(if archive.thin { ARMAG_THIN } else { ARMAG })?;
+        let index_name = if width == 4 { "/" } else { "/SYM64/" };
+        write_member(io_file, index_name, &Member::new("", Vec::new()), &index, 0)?;
+        if !long_names.is_empty() {
+            write_member(io_file, "//", &Member::new("", Vec::new()), &long_names, 0)?;
+        }
+        for (member, name) in archive.members.iter().zip(names.iter()) {
+            if archive.thin {
+                write_member(io_file, name, member, &[], member.data.len())?;
+            } else {
+                write_member(io_file, name, member, &member.data, member.data.len())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write a member header followed by `contents`. `size` is the size recorded
+/// in the header, which differs from the contents for thin archive members.
+fn write_member<T: io::Write>(
+    io_file: &mut T,
+    name: &str,
+    member: &Member,
+    contents: &[u8],
+    size: usize,
+) -> Result<(), ParseError> {
+    let special = name.starts_with('/') && !name[1..].starts_with(|c: char| c.is_ascii_digit());
+    let size = if special { contents.len() } else { size };
+    let header = if name == "//" {
+        format!("{:<48}{:<10}", name, size)
+    } else if special {
+        format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}", name, 0, 0, 0, 0, size)
+    } else {
+        format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8o}{:<10}",
+            name, member.date, member.uid, member.gid, member.mode, size
+        )
+    };
+    if header.len() != HEADER_SIZE - ARFMAG.len() {
+        return Err(ParseError::InvalidFormat(Some(format!(
+            "Archive member header for {} does not fit",
+            name
+        ))));
+    }
+    io_file.write_all(header.as_bytes())?;
+    io_file.write_all(ARFMAG)?;
+    io_file.write_all(contents)?;
+    if contents.len() % 2 == 1 {
+        io_file.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Parse a System V symbol index with big-endian entries of the given width
+fn parse_gnu_index(data: &[u8], width: usize) -> Result<Vec<(String, u64)>, ParseError> {
+    let read = |offset: usize| -> Result<u64, ParseError> {
+        let bytes = data.get(offset..offset + width).ok_or_else(truncated)?;
+        Ok(match width {
+            4 => BigEndian::read_u32(bytes) as u64,
+            _ => BigEndian::read_u64(bytes),
+        })
+    };
+    // Every entry takes an offset of `width` bytes, so the count is bounded
+    // by the size of the index
+    let count = read(0)?;
+    if count > (data.len() / width) as u64 {
+        return Err(truncated());
+    }
+    let count = count as usize;
+    let mut strings = count
+        .checked_add(1)
+        .and_then(|entries| entries.checked_mul(width))
+        .ok_or_else(truncated)?;
+    let mut index = Vec::new();
+    for i in 0..count {
+        let name = c_string(data, strings)?;
+        strings += name.len() + 1;
+        index.push((name, read(width * (i + 1))?));
+    }
+    Ok(index)
+}
+
+/// Parse a BSD `__.SYMDEF` symbol index
+fn parse_bsd_index(data: &[u8]) -> Result<Vec<(String, u64)>, ParseError> {
+    let read = |offset: usize| -> Result<u32, ParseError> {
+        Ok(LittleEndian::read_u32(
+            data.get(offset..offset + 4).ok_or_else(truncated)?,
+        ))
+    };
+    let ranlib_size = read(0)? as usize;
+    let strings_start = 4 + ranlib_size + 4;
+    let mut index = Vec::with_capacity(ranlib_size / 8);
+    for i in 0..ranlib_size / 8 {
+        let name = read(4 + 8 * i)? as usize;
+        let offset = read(4 + 8 * i + 4)?;
+        index.push((c_string(data, strings_start + name)?, offset as u64));
+    }
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Archive, Member};
+    use std::io;
+
+    fn check(archive: &Archive) {
+        let names: Vec<&str> = archive.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names[..2], ["a.o", "a_member_with_a_long_name.o"]);
+        assert_eq!(archive.find_symbol("_start").unwrap().name, "a.o");
+        assert_eq!(
+            archive.find_symbol("helper").unwrap().name,
+            "a_member_with_a_long_name.o"
+        );
+        let file = archive.members[1].parse().unwrap();
+        assert!(file.get_section(".text.shared_inline").is_some());
+    }
+
+    #[test]
+    fn test_read_archives() {
+        let gnu = Archive::open_path("tests/samples/archive/libgnu.a").unwrap();
+        check(&gnu);
+        assert_eq!(gnu.members[2].name, "notes.txt");
+        assert_eq!(gnu.members[2].data, b"hello\n");
+        assert_eq!(gnu.members[0].mode, 0o644);
+        check(&Archive::open_path("tests/samples/archive/libbsd.a").unwrap());
+        let thin = Archive::open_path("tests/samples/archive/libthin.a").unwrap();
+        assert!(thin.thin);
+        check(&thin);
+        assert_eq!(thin.members[0].data, gnu.members[0].data);
+    }
+
+    #[test]
+    fn test_write_archive() {
+        let gnu = Archive::open_path("tests/samples/archive/libgnu.a").unwrap();
+        let mut buffer = Vec::new();
+        gnu.write_stream(&mut buffer).unwrap();
+        // Same layout as `ar rcsD`
+        assert_eq!(
+            buffer,
+            std::fs::read("tests/samples/archive/libgnu.a").unwrap()
+        );
+
+        let mut archive = Archive::new();
+        archive
+            .members
+            .push(Member::new("odd.txt", b"odd".to_vec()));
+        archive.members.push(gnu.members[1].clone());
+        let mut buffer = io::Cursor::new(Vec::new());
+        archive.write_stream(&mut buffer).unwrap();
+        let archive = Archive::parse(buffer.get_ref()).unwrap();
+        assert_eq!(archive.members[0].data, b"odd");
+        assert_eq!(
+            archive.find_symbol("zeroed").unwrap().name,
+            "a_member_with_a_long_name.o"
+        );
+    }
+
+    #[test]
+    fn test_malformed_index() {
+        // A /SYM64/ index claiming u64::MAX entries
+        let mut data = b"!<arch>\n".to_vec();
+        data.extend_from_slice(format!("{:<48}{:<10}`\n", "/SYM64/", 8).as_bytes());
+        data.extend_from_slice(&u64::MAX.to_be_bytes());
+        assert!(Archive::parse(&data).is_err());
+    }
+}
@@ -0,0 +1,554 @@
+use crate::dwarf::Reader;
+use crate::types;
+use crate::{File, ParseError};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Index table entry of a function that can not be unwound. Named EXIDX_CANTUNWIND in C code.
+pub const EXIDX_CANTUNWIND: u32 = 1;
+
+/// Personality routine index of the short compact model, with up to 3 opcodes
+pub const PERSONALITY_SU16: u8 = 0;
+/// Personality routine index of the long compact model with 16-bit scope descriptors
+pub const PERSONALITY_LU16: u8 = 1;
+/// Personality routine index of the long compact model with 32-bit scope descriptors
+pub const PERSONALITY_LU32: u8 = 2;
+
+/// How to unwind a function, from its `.ARM.exidx` entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArmUnwind {
+    /// The function can not be unwound
+    CantUnwind,
+    /// Compact model with one of the ARM-defined personality routines
+    Compact {
+        /// Personality routine index, e.g. PERSONALITY_SU16
+        personality: u8,
+        /// Unwind opcodes, without trailing finish opcodes
+        opcodes: Vec<u8>,
+        /// Address of the `.ARM.extab` entry, or None if the entry is inline in the index table
+        extab: Option<u64>,
+    },
+    /// Generic model with a personality routine address.
+    ///
+    /// The opcodes are decoded with the layout used by the GNU personality
+    /// routines, as the format is specific to the personality routine.
+    Generic {
+        /// Address of the personality routine
+        personality: u64,
+        /// Address of the `.ARM.extab` entry
+        extab: u64,
+        /// Unwind opcodes, without trailing finish opcodes
+        opcodes: Vec<u8>,
+    },
+}
+
+impl ArmUnwind {
+    /// Get the unwind opcodes. Functions that can not be unwound have none.
+    pub fn opcodes(&self) -> &[u8] {
+        match self {
+            ArmUnwind::CantUnwind => &[],
+            ArmUnwind::Compact { opcodes, .. } | ArmUnwind::Generic { opcodes, .. } => opcodes,
+        }
+    }
+}
+
+/// An entry of the `.ARM.exidx` section.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExidxEntry {
+    /// Address of the first function covered by the entry. The entry covers
+    /// addresses up to the next entry.
+    pub function: u64,
+    /// How to unwind the functions covered by the entry
+    pub unwind: ArmUnwind,
+}
+
+/// The entries of the `.ARM.exidx` section.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExidxTable {
+    /// Entries, sorted by function address
+    pub entries: Vec<ExidxEntry>,
+}
+
+impl ExidxTable {
+    /// Find the entry covering an address.
+    pub fn find(&self, address: u64) -> Option<&ExidxEntry> {
+        let index = self
+            .entries
+            .partition_point(|entry| entry.function <= address);
+        index.checked_sub(1).map(|index| &self.entries[index])
+    }
+}
+
+/// A decoded ARM unwind opcode.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArmUnwindOp {
+    /// Add to the virtual stack pointer
+    AdjustVsp(i64),
+    /// Pop the core registers in a mask, bit n standing for rn
+    Pop(u16),
+    /// Set the virtual stack pointer from a register
+    SetVsp(u8),
+    /// Pop VFP registers d[first] to d[first + count - 1], saved by FSTMFDX if `fstmfdx` is set or VPUSH otherwise
+    PopVfp { first: u8, count: u8, fstmfdx: bool },
+    /// Pop WMMX data registers wR[first] to wR[first + count - 1]
+    PopWmmxData { first: u8, count: u8 },
+    /// Pop WMMX control registers in a mask, bit n standing for wCGRn
+    PopWmmxControl(u8),
+    /// The function can not be unwound
+    Refuse,
+    /// End of the opcodes
+    Finish,
+}
+
+impl ArmUnwindOp {
+    /// Get the number of bytes the operation pops from the stack, besides core registers.
+    fn skipped(&self) -> u64 {
+        match *self {
+            ArmUnwindOp::PopVfp { count, fstmfdx, .. } => {
+                u64::from(count) * 8 + if fstmfdx { 4 } else { 0 }
+            }
+            ArmUnwindOp::PopWmmxData { count, .. } => u64::from(count) * 8,
+            ArmUnwindOp::PopWmmxControl(mask) => u64::from(mask.count_ones()) * 4,
+            _ => 0,
+        }
+    }
+}
+
+fn register_list(f: &mut fmt::Formatter, prefix: &str, first: u8, count: u8) -> fmt::Result {
+    if count == 1 {
+        write!(f, "{{{}{}}}", prefix, first)
+    } else {
+        write!(f, "{{{}{}-{}{}}}", prefix, first, prefix, first + count - 1)
+    }
+}
+
+impl fmt::Display for ArmUnwindOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArmUnwindOp::AdjustVsp(offset) if offset < 0 => {
+                write!(f, "vsp = vsp - {}", -offset)
+            }
+            ArmUnwindOp::AdjustVsp(offset) => write!(f, "vsp = vsp + {}", offset),
+            ArmUnwindOp::Pop(mask) => {
+                let registers: Vec<String> = (0..16)
+                    .filter(|bit| mask & (1 << bit) != 0)
+                    .map(|bit| format!("r{}", bit))
+                    .collect();
+                write!(f, "pop {{{}}}", registers.join(", "))
+            }
+            ArmUnwindOp::SetVsp(register) => write!(f, "vsp = r{}", register),
+            ArmUnwindOp::PopVfp {
+                first,
+                count,
+                fstmfdx,
+            } => {
+                write!(f, "pop ")?;
+                register_list(f, "d", first, count)?;
+                if fstmfdx {
+                    write!(f, " (FSTMFDX)")?;
+                }
+                Ok(())
+            }
+            ArmUnwindOp::PopWmmxData { first, count } => {
+                write!(f, "pop ")?;
+                register_list(f, "wR", first, count)
+            }
+            ArmUnwindOp::PopWmmxControl(mask) => {
+                let registers: Vec<String> = (0..4)
+                    .filter(|bit| mask & (1 << bit) != 0)
+                    .map(|bit| format!("wCGR{}", bit))
+                    .collect();
+                write!(f, "pop {{{}}}", registers.join(", "))
+            }
+            ArmUnwindOp::Refuse => write!(f, "refuse to unwind"),
+            ArmUnwindOp::Finish => write!(f, "finish"),
+        }
+    }
+}
+
+/// Decode ARM unwind opcodes. Decoding stops at the first finish opcode.
+pub fn decode_arm_unwind_opcodes(opcodes: &[u8]) -> Result<Vec<ArmUnwindOp>, ParseError> {
+    let mut ops = Vec::new();
+    let mut bytes = opcodes.iter().copied();
+    let spare = |opcode: &[u8]| {
+        ParseError::InvalidFormat(Some(format!("spare ARM unwind opcode {:02x?}", opcode)))
+    };
+    let truncated = || ParseError::InvalidFormat(Some("truncated ARM unwind opcode".to_string()));
+    while let Some(opcode) = bytes.next() {
+        let op = match opcode {
+            0x00..=0x3f => ArmUnwindOp::AdjustVsp((i64::from(opcode) << 2) + 4),
+            0x40..=0x7f => ArmUnwindOp::AdjustVsp(-((i64::from(opcode & 0x3f) << 2) + 4)),
+            0x80..=0x8f => {
+                let next = bytes.next().ok_or_else(truncated)?;
+                let mask = (u16::from(opcode & 0x0f) << 8) | u16::from(next);
+                if mask == 0 {
+                    ArmUnwindOp::Refuse
+                } else {
+                    ArmUnwindOp::Pop(mask << 4)
+                }
+            }
+            0x9d | 0x9f => return Err(spare(&[opcode])),
+            0x90..=0x9f => ArmUnwindOp::SetVsp(opcode & 0x0f),
+            0xa0..=0xaf => {
+                let mut mask = ((1u16 << ((opcode & 0x07) + 1)) - 1) << 4;
+                if opcode & 0x08 != 0 {
+                    mask |= 1 << 14;
+                }
+                ArmUnwindOp::Pop(mask)
+            }
+            0xb0 => ArmUnwindOp::Finish,
+            0xb1 => {
+                let next = bytes.next().ok_or_else(truncated)?;
+                if next == 0 || next & 0xf0 != 0 {
+                    return Err(spare(&[opcode, next]));
+                }
+                ArmUnwindOp::Pop(u16::from(next))
+            }
+            0xb2 => {
+                let mut reader = Reader::new(
+                    &opcodes[opcodes.len() - bytes.len()..],
+                    types::ElfEndianness::Lsb,
+                );
+                let value = reader.uleb128().map_err(|_| truncated())?;
+                bytes.nth(reader.offset - 1);
+                let adjust = value
+                    .checked_mul(4)
+                    .and_then(|value| value.checked_add(0x204))
+                    .and_then(|value| i64::try_from(value).ok())
+                    .ok_or_else(|| {
+                        ParseError::InvalidFormat(Some(format!(
+                            "vsp adjustment {:#x} out of range",
+                            value
+                        )))
+                    })?;
+                ArmUnwindOp::AdjustVsp(adjust)
+            }
+            0xb3 | 0xc8 | 0xc9 => {
+                let next = bytes.next().ok_or_else(truncated)?;
+                ArmUnwindOp::PopVfp {
+                    first: (next >> 4) + if opcode == 0xc8 { 16 } else { 0 },
+                    count: (next & 0x0f) + 1,
+                    fstmfdx: opcode == 0xb3,
+                }
+            }
+            0xb8..=0xbf | 0xd0..=0xd7 => ArmUnwindOp::PopVfp {
+                first: 8,
+                count: (opcode & 0x07) + 1,
+                fstmfdx: opcode < 0xc0,
+            },
+            0xc0..=0xc5 => ArmUnwindOp::PopWmmxData {
+                first: 10,
+                count: (opcode & 0x07) + 1,
+            },
+            0xc6 => {
+                let next = bytes.next().ok_or_else(truncated)?;
+                ArmUnwindOp::PopWmmxData {
+                    first: next >> 4,
+                    count: (next & 0x0f) + 1,
+                }
+            }
+            0xc7 => {
+                let next = bytes.next().ok_or_else(truncated)?;
+                if next == 0 || next & 0xf0 != 0 {
+                    return Err(spare(&[opcode, next]));
+                }
+                ArmUnwindOp::PopWmmxControl(next)
+            }
+            _ => return Err(spare(&[opcode])),
+        };
+        ops.push(op);
+        if op == ArmUnwindOp::Finish {
+            break;
+        }
+    }
+    Ok(ops)
+}
+
+/// The core registers of a 32-bit ARM frame, for unwinding with `.ARM.exidx`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ArmRegisters {
+    /// r0 to r15. r13 is the stack pointer, r14 the link register and r15 the program counter.
+    pub r: [u32; 16],
+}
+
+impl ArmRegisters {
+    /// Execute unwind opcodes to recover the caller's registers.
+    ///
+    /// `read` reads a word of the stack. If the opcodes do not pop the
+    /// program counter, it is set to the link register.
+    pub fn unwind<F: Fn(u32) -> Option<u32>>(
+        &mut self,
+        ops: &[ArmUnwindOp],
+        read: F,
+    ) -> Result<(), ParseError> {
+        let mut vsp = self.r[13];
+        let mut pc_popped = false;
+        for op in ops {
+            match *op {
+                ArmUnwindOp::AdjustVsp(offset) => vsp = vsp.wrapping_add(offset as u32),
+                ArmUnwindOp::Pop(mask) => {
+                    for register in (0..16).filter(|bit| mask & (1 << bit) != 0) {
+                        self.r[register] = read(vsp).ok_or_else(|| {
+                            ParseError::InvalidFormat(Some(format!(
+                                "stack at {:#x} is not available",
+                                vsp
+                            )))
+                        })?;
+                        vsp = vsp.wrapping_add(4);
+                    }
+                    // Popping sp sets vsp to the popped value
+                    if mask & (1 << 13) != 0 {
+                        vsp = self.r[13];
+                    }
+                    pc_popped |= mask & (1 << 15) != 0;
+                }
+                ArmUnwindOp::SetVsp(register) => vsp = self.r[register as usize],
+                ArmUnwindOp::Refuse => {
+                    return Err(ParseError::InvalidFormat(Some(
+                        "frame can not be unwound".to_string(),
+                    )))
+                }
+                ArmUnwindOp::Finish => break,
+                _ => vsp = vsp.wrapping_add(op.skipped() as u32),
+            }
+        }
+        self.r[13] = vsp;
+        if !pc_popped {
+            self.r[15] = self.r[14];
+        }
+        Ok(())
+    }
+}
+
+/// Decode a prel31 offset relative to the address of the word holding it.
+fn prel31(word: u32, address: u64) -> u64 {
+    let offset = ((word << 1) as i32 >> 1) as i64;
+    address.wrapping_add(offset as u64)
+}
+
+/// Split a word into its bytes, most significant first.
+fn word_bytes(word: u32) -> [u8; 4] {
+    word.to_be_bytes()
+}
+
+/// Remove trailing finish opcodes, which only pad the last word.
+fn trim_finish(mut opcodes: Vec<u8>) -> Vec<u8> {
+    while opcodes.last() == Some(&0xb0) {
+        opcodes.pop();
+    }
+    opcodes
+}
+
+impl File {
+    /// Read a word at an address in the sections of the file.
+    fn read_word_at(&self, address: u64) -> Result<u32, ParseError> {
+        let section = self
+            .sections
+            .iter()
+            .find(|section| {
+                section.shdr.shtype != types::SectionType::Nobits
+                    && section.shdr.addr <= address
+                    && address + 4 <= section.shdr.addr + section.data.len() as u64
+            })
+            .ok_or_else(|| {
+                ParseError::InvalidFormat(Some(format!(
+                    "address {:#x} is not in a section",
+                    address
+                )))
+            })?;
+        let data = &section.data[(address - section.shdr.addr) as usize..];
+        Ok(match self.header.endianness {
+            types::ElfEndianness::Lsb => LittleEndian::read_u32(data),
+            types::ElfEndianness::Msb => BigEndian::read_u32(data),
+        })
+    }
+
+    /// Decode an `.ARM.extab` entry.
+    fn parse_extab(&self, address: u64) -> Result<ArmUnwind, ParseError> {
+        let first = self.read_word_at(address)?;
+        let (opcodes, personality) = if first & 0x8000_0000 != 0 {
+            let personality = ((first >> 24) & 0x0f) as u8;
+            match personality {
+                PERSONALITY_SU16 => (word_bytes(first)[1..].to_vec(), None),
+                PERSONALITY_LU16 | PERSONALITY_LU32 => {
+                    let count = (first >> 16) & 0xff;
+                    let mut opcodes = word_bytes(first)[2..].to_vec();
+                    for index in 0..u64::from(count) {
+                        opcodes.extend(&word_bytes(self.read_word_at(address + 4 + index * 4)?));
+                    }
+                    (opcodes, None)
+                }
+                _ => {
+                    return Err(ParseError::InvalidFormat(Some(format!(
+                        "unknown ARM personality routine index {}",
+                        personality
+                    ))))
+                }
+            }
+        } else {
+            // The GNU personality routines put a word count and 3 opcodes in the first data word
+            let data = self.read_word_at(address + 4)?;
+            let count = data >> 24;
+            let mut opcodes = word_bytes(data)[1..].to_vec();
+            for index in 0..u64::from(count) {
+                opcodes.extend(&word_bytes(self.read_word_at(address + 8 + index * 4)?));
+            }
+            (opcodes, Some(prel31(first, address)))
+        };
+        let opcodes = trim_finish(opcodes);
+        Ok(match personality {
+            None => ArmUnwind::Compact {
+                personality: ((first >> 24) & 0x0f) as u8,
+                opcodes,
+                extab: Some(address),
+            },
+            Some(personality) => ArmUnwind::Generic {
+                personality,
+                extab: address,
+                opcodes,
+            },
+        })
+    }
+
+    /// Parse the `.ARM.exidx` section and the `.ARM.extab` entries it refers to.
+    ///
+    /// Returns None if the file has no SHT_ARM_EXIDX section. Offsets in
+    /// relocatable objects are decoded without applying relocations.
+    pub fn get_arm_exidx(&self) -> Result<Option<ExidxTable>, ParseError> {
+        let section = match self
+            .sections
+            .iter()
+            .find(|section| section.shdr.shtype == types::SectionType::ArmExidx)
+        {
+            Some(section) => section,
+            None => return Ok(None),
+        };
+        let read = |data: &[u8]| match self.header.endianness {
+            types::ElfEndianness::Lsb => LittleEndian::read_u32(data),
+            types::ElfEndianness::Msb => BigEndian::read_u32(data),
+        };
+        let mut entries = Vec::with_capacity(section.data.len() / 8);
+        for (index, entry) in section.data.chunks_exact(8).enumerate() {
+            let address = section.shdr.addr + index as u64 * 8;
+            let function = read(&entry[..4]);
+            if function & 0x8000_0000 != 0 {
+                return Err(ParseError::InvalidFormat(Some(format!(
+                    "invalid .ARM.exidx entry at {:#x}",
+                    address
+                ))));
+            }
+            let data = read(&entry[4..]);
+            let unwind = if data == EXIDX_CANTUNWIND {
+                ArmUnwind::CantUnwind
+            } else if data & 0x8000_0000 != 0 {
+                let personality = ((data >> 24) & 0x7f) as u8;
+                if personality != PERSONALITY_SU16 {
+                    return Err(ParseError::InvalidFormat(Some(format!(
+                        "invalid inline .ARM.exidx entry at {:#x}",
+                        address
+                    ))));
+                }
+                ArmUnwind::Compact {
+                    personality,
+                    opcodes: trim_finish(word_bytes(data)[1..].to_vec()),
+                    extab: None,
+                }
+            } else {
+                self.parse_extab(prel31(data, address + 4))?
+            };
+            entries.push(ExidxEntry {
+                function: prel31(function, address),
+                unwind,
+            });
+        }
+        entries.sort_by_key(|entry| entry.function);
+        Ok(Some(ExidxTable { entries }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arm_exidx() {
+        let file = File::open_path("tests/samples/android_arm_libncurses").unwrap();
+        let table = file.get_arm_exidx().unwrap().unwrap();
+        assert_eq!(table.entries.len(), 34);
+
+        // Inline compact entry
+        let entry = table.find(0x2db10).unwrap();
+        assert_eq!(entry.function, 0x2db04);
+        assert_eq!(entry.unwind.opcodes(), &[0xb1, 0x07, 0xaf]);
+        let ops = decode_arm_unwind_opcodes(entry.unwind.opcodes()).unwrap();
+        let text: Vec<String> = ops.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            text,
+            vec![
+                "pop {r0, r1, r2}",
+                "pop {r4, r5, r6, r7, r8, r9, r10, r11, r14}"
+            ]
+        );
+
+        // Compact entry in .ARM.extab
+        let entry = table.find(0x2dadc).unwrap();
+        assert_eq!(
+            entry.unwind,
+            ArmUnwind::Compact {
+                personality: PERSONALITY_LU16,
+                opcodes: vec![0xb1, 0x02, 0x84, 0x00],
+                extab: Some(0x39b50),
+            }
+        );
+
+        let entry = table.find(0x2e9c4).unwrap();
+        assert_eq!(entry.unwind, ArmUnwind::CantUnwind);
+    }
+
+    #[test]
+    fn test_arm_unwind() {
+        // __gnu_Unwind_RaiseException: vsp += 228, vsp += 256, pop {r4-r7, r14}
+        let file = File::open_path("tests/samples/android_arm_libncurses").unwrap();
+        let table = file.get_arm_exidx().unwrap().unwrap();
+        let entry = table.find(0x2df30).unwrap();
+        let ops = decode_arm_unwind_opcodes(entry.unwind.opcodes()).unwrap();
+        assert_eq!(
+            ops,
+            vec![
+                ArmUnwindOp::AdjustVsp(228),
+                ArmUnwindOp::AdjustVsp(256),
+                ArmUnwindOp::Pop(0x40f0)
+            ]
+        );
+
+        let mut registers = ArmRegisters { r: [0; 16] };
+        registers.r[13] = 0x1000;
+        registers.r[15] = 0x2df30;
+        registers.unwind(&ops, |address| Some(address * 2)).unwrap();
+        let saved = 0x1000 + 228 + 256;
+        assert_eq!(registers.r[4], saved * 2);
+        assert_eq!(registers.r[7], (saved + 12) * 2);
+        assert_eq!(registers.r[14], (saved + 16) * 2);
+        assert_eq!(registers.r[15], registers.r[14]);
+        assert_eq!(registers.r[13], saved + 20);
+
+        let cant_unwind = decode_arm_unwind_opcodes(&[0x80, 0x00]).unwrap();
+        assert!(registers.unwind(&cant_unwind, |_| Some(0)).is_err());
+
+        // vsp += 0x204 + (ULEB128 << 2), followed by another opcode
+        let ops = decode_arm_unwind_opcodes(&[0xb2, 0x81, 0x01, 0x00]).unwrap();
+        assert_eq!(
+            ops,
+            vec![
+                ArmUnwindOp::AdjustVsp(0x204 + (129 << 2)),
+                ArmUnwindOp::AdjustVsp(4)
+            ]
+        );
+        assert!(decode_arm_unwind_opcodes(&[0xb2, 0x81]).is_err());
+        let mut huge = vec![0xb2];
+        huge.extend_from_slice(&[0xff; 8]);
+        huge.push(0x1f);
+        assert!(decode_arm_unwind_opcodes(&huge).is_err());
+    }
+}
@@ -0,0 +1,348 @@
+use crate::types;
+use crate::{File, ParseError};
+
+/// Functions with a `__<name>_chk` counterpart under `_FORTIFY_SOURCE`.
+const FORTIFIABLE_FUNCTIONS: &[&str] = &[
+    "asprintf",
+    "confstr",
+    "dprintf",
+    "explicit_bzero",
+    "fdelt",
+    "fgets",
+    "fgets_unlocked",
+    "fgetws",
+    "fgetws_unlocked",
+    "fprintf",
+    "fread",
+    "fread_unlocked",
+    "fwprintf",
+    "getcwd",
+    "getdomainname",
+    "getgroups",
+    "gethostname",
+    "getlogin_r",
+    "gets",
+    "getwd",
+    "longjmp",
+    "mbsnrtowcs",
+    "mbsrtowcs",
+    "mbstowcs",
+    "memcpy",
+    "memmove",
+    "mempcpy",
+    "memset",
+    "obstack_printf",
+    "obstack_vprintf",
+    "poll",
+    "ppoll",
+    "pread",
+    "pread64",
+    "printf",
+    "ptsname_r",
+    "read",
+    "readlink",
+    "readlinkat",
+    "realpath",
+    "recv",
+    "recvfrom",
+    "snprintf",
+    "sprintf",
+    "stpcpy",
+    "stpncpy",
+    "strcat",
+    "strcpy",
+    "strlcat",
+    "strlcpy",
+    "strncat",
+    "strncpy",
+    "swprintf",
+    "syslog",
+    "ttyname_r",
+    "vasprintf",
+    "vdprintf",
+    "vfprintf",
+    "vfwprintf",
+    "vprintf",
+    "vsnprintf",
+    "vsprintf",
+    "vswprintf",
+    "vsyslog",
+    "vwprintf",
+    "wcpcpy",
+    "wcpncpy",
+    "wcrtomb",
+    "wcscat",
+    "wcscpy",
+    "wcsncat",
+    "wcsncpy",
+    "wcsnrtombs",
+    "wcsrtombs",
+    "wcstombs",
+    "wctomb",
+    "wmemcpy",
+    "wmemmove",
+    "wmempcpy",
+    "wmemset",
+    "wprintf",
+];
+
+/// Symbols whose presence indicates stack protector instrumentation.
+const STACK_CHK_SYMBOLS: &[&str] = &[
+    "__stack_chk_fail",
+    "__stack_chk_guard",
+    "__intel_security_cookie",
+];
+
+/// Relocation read-only (RELRO) protection level.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Relro {
+    /// No PT_GNU_RELRO segment
+    None,
+    /// PT_GNU_RELRO without immediate binding, so the GOT stays writable
+    Partial,
+    /// PT_GNU_RELRO with immediate binding
+    Full,
+}
+
+/// Position independence of the file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pie {
+    /// Executable linked at a fixed address
+    None,
+    /// Position independent executable
+    Pie,
+    /// Shared object
+    Dso,
+    /// Relocatable object
+    Rel,
+}
+
+/// Result of a checksec-style hardening analysis of a file.
+///
+/// All fields are public so that callers can express their own policies
+/// over them; see [File::security_report].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecurityReport {
+    /// Relocation read-only protection
+    pub relro: Relro,
+    /// Position independence
+    pub pie: Pie,
+    /// Non-executable stack, from the PT_GNU_STACK flags
+    pub nx: bool,
+    /// Stack protector instrumentation
+    pub canary: bool,
+    /// Fortified `__*_chk` functions that are used
+    pub fortified: Vec<String>,
+    /// Fortifiable functions that are used without their `__*_chk` variant
+    pub fortifiable: Vec<String>,
+    /// DT_RPATH, if present
+    pub rpath: Option<String>,
+    /// DT_RUNPATH, if present
+    pub runpath: Option<String>,
+    /// Object contains text relocations
+    pub textrel: bool,
+    /// x86 CET features from the GNU property note
+    pub x86_features: Option<types::X86Feature1Flag>,
+    /// AArch64 BTI/PAC features from the GNU property note
+    pub aarch64_features: Option<types::Aarch64Feature1Flag>,
+}
+
+impl SecurityReport {
+    /// Returns true if FORTIFY_SOURCE appears to be in effect
+    pub fn is_fortified(&self) -> bool {
+        !self.fortified.is_empty()
+    }
+
+    /// Returns true if both Indirect Branch Tracking and Shadow Stack are enabled
+    pub fn has_cet(&self) -> bool {
+        self.x86_features.is_some_and(|flags| {
+            flags.contains(types::GNU_PROPERTY_X86_FEATURE_1_IBT)
+                && flags.contains(types::GNU_PROPERTY_X86_FEATURE_1_SHSTK)
+        })
+    }
+
+    /// Returns true if both Branch Target Identification and Pointer Authentication are enabled
+    pub fn has_bti_pac(&self) -> bool {
+        self.aarch64_features.is_some_and(|flags| {
+            flags.contains(types::GNU_PROPERTY_AARCH64_FEATURE_1_BTI)
+                && flags.contains(types::GNU_PROPERTY_AARCH64_FEATURE_1_PAC)
+        })
+    }
+}
+
+impl std::fmt::Display for SecurityReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "RELRO: {:?} PIE: {:?} NX: {} Canary: {} Fortified: {}/{} RPATH: {} RUNPATH: {} TEXTREL: {}",
+            self.relro,
+            self.pie,
+            self.nx,
+            self.canary,
+            self.fortified.len(),
+            self.fortified.len() + self.fortifiable.len(),
+            self.rpath.is_some(),
+            self.runpath.is_some(),
+            self.textrel
+        )
+    }
+}
+
+impl File {
+    /// Produce a checksec-style hardening report for this file.
+    pub fn security_report(&self) -> Result<SecurityReport, ParseError> {
+        let dynamic = self.get_dynamic()?;
+        let has_tag = |tag: types::DynTag| dynamic.iter().any(|entry| entry.d_tag == tag);
+        let has_flag = |tag: types::DynTag, flag: u64| {
+            dynamic
+                .iter()
+                .any(|entry| entry.d_tag == tag && entry.d_val & flag != 0)
+        };
+
+        let bind_now = has_tag(types::DT_BIND_NOW)
+            || has_flag(types::DT_FLAGS, types::DF_BIND_NOW)
+            || has_flag(types::DT_FLAGS_1, types::DF_1_NOW);
+        let relro = if !self
+            .phdrs
+            .iter()
+            .any(|phdr| phdr.progtype == types::PT_GNU_RELRO)
+        {
+            Relro::None
+        } else if bind_now {
+            Relro::Full
+        } else {
+            Relro::Partial
+        };
+
+        let pie = match self.header.elftype {
+            types::ElfFileType::RelocatableObject => Pie::Rel,
+            types::ElfFileType::SharedLibrary => {
+                // Linkers before DF_1_PIE left an interpreter as the only sign of
+                // a PIE, but shared objects like libc.so.6 can have one too
+                let interp = self
+                    .phdrs
+                    .iter()
+                    .any(|phdr| phdr.progtype == types::PT_INTERP);
+                if has_flag(types::DT_FLAGS_1, types::DF_1_PIE)
+                    || (interp && !has_tag(types::DT_SONAME))
+                {
+                    Pie::Pie
+                } else {
+                    Pie::Dso
+                }
+            }
+            _ => Pie::None,
+        };
+
+        let nx = self
+            .phdrs
+            .iter()
+            .find(|phdr| phdr.progtype == types::PT_GNU_STACK)
+            .is_some_and(|phdr| phdr.flags.0 & types::PF_X.0 == 0);
+
+        let names = self.referenced_symbol_names()?;
+        let canary = names
+            .iter()
+            .any(|name| STACK_CHK_SYMBOLS.contains(&name.as_str()));
+
+        let mut fortified = Vec::new();
+        let mut fortifiable = Vec::new();
+        for name in names.iter() {
+            if name.starts_with("__") && name.ends_with("_chk") {
+                let base = &name[2..name.len() - 4];
+                if FORTIFIABLE_FUNCTIONS.contains(&base) {
+                    fortified.push(name.clone());
+                }
+            } else if FORTIFIABLE_FUNCTIONS.contains(&name.as_str()) {
+                fortifiable.push(name.clone());
+            }
+        }
+
+        Ok(SecurityReport {
+            relro,
+            pie,
+            nx,
+            canary,
+            fortified,
+            fortifiable,
+            rpath: self.get_rpath()?,
+            runpath: self.get_runpath()?,
+            textrel: has_tag(types::DT_TEXTREL) || has_flag(types::DT_FLAGS, types::DF_TEXTREL),
+            x86_features: self.get_x86_features()?,
+            aarch64_features: self.get_aarch64_features()?,
+        })
+    }
+
+    /// Names of the functions a file calls into: the undefined dynamic symbols
+    /// of a dynamically linked file, or every symbol of a static one.
+    fn referenced_symbol_names(&self) -> Result<Vec<String>, ParseError> {
+        let (section, undefined_only) = match self
+            .sections
+            .iter()
+            .find(|section| section.shdr.shtype == types::SectionType::Dynsym)
+        {
+            Some(section) => (section, true),
+            None => match self
+                .sections
+                .iter()
+                .find(|section| section.shdr.shtype == types::SectionType::Symtab)
+            {
+                Some(section) => (section, false),
+                None => return Ok(Vec::new()),
+            },
+        };
+
+        let mut names: Vec<String> = self
+            .get_symbols(section)?
+            .into_iter()
+            .filter(|sym| !sym.name.is_empty())
+            .filter(|sym| !undefined_only || sym.shndx == types::SectionIndex::Undefined)
+            .map(|sym| sym.name)
+            .collect();
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pie, Relro};
+    use crate::types;
+    use crate::File;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_security_report() {
+        let file = File::open_path(PathBuf::from("tests/samples/x86_64_hardened")).unwrap();
+        let report = file.security_report().unwrap();
+        assert_eq!(report.relro, Relro::Full);
+        assert_eq!(report.pie, Pie::Pie);
+        assert!(report.nx);
+        assert!(report.canary);
+        assert_eq!(report.fortified, vec!["__printf_chk", "__strcpy_chk"]);
+        assert!(report.fortifiable.is_empty());
+        assert!(!report.textrel);
+        assert!(report.has_cet());
+
+        let file = File::open_path(PathBuf::from("tests/samples/x86_64_plain")).unwrap();
+        let report = file.security_report().unwrap();
+        assert_eq!(report.relro, Relro::None);
+        assert_eq!(report.pie, Pie::None);
+        assert!(!report.nx);
+        assert!(!report.canary);
+        assert!(!report.is_fortified());
+        assert_eq!(report.fortifiable, vec!["printf", "strcpy"]);
+        assert_eq!(report.runpath.as_deref(), Some("/opt/lib"));
+        assert!(!report.has_cet());
+
+        // A shared object with an interpreter, like libc.so.6, is not a PIE
+        let mut file = File::open_path(PathBuf::from("tests/samples/libabi_v1.so")).unwrap();
+        assert_eq!(file.security_report().unwrap().pie, Pie::Dso);
+        let mut interp = file.phdrs[0];
+        interp.progtype = types::PT_INTERP;
+        file.phdrs.push(interp);
+        assert!(file.get_soname().unwrap().is_some());
+        assert_eq!(file.security_report().unwrap().pie, Pie::Dso);
+    }
+}
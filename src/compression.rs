@@ -0,0 +1,141 @@
+use crate::types;
+use crate::{File, ParseError, Section};
+use byteorder::ByteOrder;
+use std::borrow::Cow;
+use std::io;
+
+/// Magic at the start of the data of legacy `.zdebug_*` sections
+const ZDEBUG_MAGIC: &[u8] = b"ZLIB";
+
+impl File {
+    /// Get the compression header of an SHF_COMPRESSED section.
+    ///
+    /// Returns None for sections that are not compressed this way. Legacy
+    /// GNU `.zdebug_*` sections do not have a compression header.
+    pub fn get_compression_header(
+        &self,
+        section: &Section,
+    ) -> Result<Option<types::CompressionHeader>, ParseError> {
+        if section.shdr.flags.0 & types::SHF_COMPRESSED.0 == 0 {
+            return Ok(None);
+        }
+        let mut io_section = io::Cursor::new(&section.data);
+        let chtype = types::CompressionType(read_u32!(self, io_section)?);
+        let (size, addralign) = match self.header.class {
+            types::ElfClass::Format32 => (
+                read_u32!(self, io_section)? as u64,
+                read_u32!(self, io_section)? as u64,
+            ),
+            types::ElfClass::Format64 => {
+                // ch_reserved
+                read_u32!(self, io_section)?;
+                (read_u64!(self, io_section)?, read_u64!(self, io_section)?)
+            }
+        };
+        Ok(Some(types::CompressionHeader {
+            chtype,
+            size,
+            addralign,
+        }))
+    }
+
+    /// Get the contents of a section, decompressing them if needed.
+    ///
+    /// Both SHF_COMPRESSED sections and legacy GNU `.zdebug_*` sections are
+    /// supported. Uncompressed sections are returned as is, without copying.
+    pub fn get_section_data<'a>(&self, section: &'a Section) -> Result<Cow<'a, [u8]>, ParseError> {
+        if let Some(chdr) = self.get_compression_header(section)? {
+            let offset = match self.header.class {
+                types::ElfClass::Format32 => 12,
+                types::ElfClass::Format64 => 24,
+            };
+            let data = section.data.get(offset..).ok_or_else(|| {
+                ParseError::InvalidFormat(Some("truncated compression header".to_string()))
+            })?;
+            return match chdr.chtype {
+                types::ELFCOMPRESS_ZLIB => inflate(data, chdr.size).map(Cow::Owned),
+                _ => Err(ParseError::NotImplemented),
+            };
+        }
+        if section.shdr.name.starts_with(".zdebug") && section.data.starts_with(ZDEBUG_MAGIC) {
+            // The magic is followed by the uncompressed size in big endian
+            if section.data.len() < 12 {
+                return Err(ParseError::InvalidFormat(Some(
+                    "truncated .zdebug header".to_string(),
+                )));
+            }
+            let size = byteorder::BigEndian::read_u64(&section.data[4..12]);
+            return inflate(&section.data[12..], size).map(Cow::Owned);
+        }
+        Ok(Cow::Borrowed(&section.data))
+    }
+
+    /// Get the contents of a debug section by its name, such as `.debug_line`.
+    ///
+    /// The legacy `.zdebug_*` variant of the section is used if the file has
+    /// no section with the exact name. The contents are decompressed if needed.
+    pub fn get_debug_section_data(&self, name: &str) -> Result<Option<Cow<'_, [u8]>>, ParseError> {
+        let section = match self.get_section(name) {
+            Some(section) => section,
+            None => match name.strip_prefix(".debug") {
+                Some(suffix) => match self.get_section(format!(".zdebug{}", suffix)) {
+                    Some(section) => section,
+                    None => return Ok(None),
+                },
+                None => return Ok(None),
+            },
+        };
+        self.get_section_data(section).map(Some)
+    }
+}
+
+#[cfg(feature = "zlib")]
+fn inflate(data: &[u8], size: u64) -> Result<Vec<u8>, ParseError> {
+    let inflated = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(data, size as usize)
+        .map_err(|err| ParseError::InvalidFormat(Some(format!("zlib: {}", err))))?;
+    if inflated.len() as u64 != size {
+        return Err(ParseError::InvalidFormat(Some(
+            "decompressed size does not match the header".to_string(),
+        )));
+    }
+    Ok(inflated)
+}
+
+#[cfg(not(feature = "zlib"))]
+fn inflate(_data: &[u8], _size: u64) -> Result<Vec<u8>, ParseError> {
+    Err(ParseError::NotImplemented)
+}
+
+#[cfg(all(test, feature = "zlib"))]
+mod tests {
+    use crate::types;
+    use crate::File;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_compressed_sections() {
+        let plain = File::open_path(PathBuf::from("tests/samples/dwarf/lines_dwarf5")).unwrap();
+        let file = File::open_path(PathBuf::from("tests/samples/dwarf/lines_zlib")).unwrap();
+        let line = file.get_section(".debug_line").unwrap();
+        let chdr = file.get_compression_header(line).unwrap().unwrap();
+        assert_eq!(chdr.chtype, types::ELFCOMPRESS_ZLIB);
+        assert_eq!(
+            &*file.get_section_data(line).unwrap(),
+            &plain.get_section(".debug_line").unwrap().data[..]
+        );
+
+        let str_section = file.get_section(".debug_line_str").unwrap();
+        assert!(file.get_compression_header(str_section).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_zdebug_sections() {
+        let plain = File::open_path(PathBuf::from("tests/samples/dwarf/lines_dwarf4")).unwrap();
+        let file = File::open_path(PathBuf::from("tests/samples/dwarf/lines_zgnu")).unwrap();
+        assert!(file.get_section(".debug_line").is_none());
+        assert_eq!(
+            &*file.get_debug_section_data(".debug_line").unwrap().unwrap(),
+            &plain.get_section(".debug_line").unwrap().data[..]
+        );
+    }
+}
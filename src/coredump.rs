@@ -0,0 +1,308 @@
+use crate::note::Note;
+use crate::types;
+use crate::{File, ParseError};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Process status of a thread. Named NT_PRSTATUS in C code.
+pub const NT_PRSTATUS: u32 = 1;
+/// Files mapped into the process. Named NT_FILE in C code.
+pub const NT_FILE: u32 = 0x4649_4c45;
+
+/// Offset of pr_pid in struct elf_prstatus on 64-bit targets
+const PRSTATUS_PID_64: usize = 32;
+/// Offset of pr_reg in struct elf_prstatus on 64-bit targets
+const PRSTATUS_REG_64: usize = 112;
+/// Offset of pr_cursig in struct elf_prstatus
+const PRSTATUS_CURSIG: usize = 12;
+
+/// A thread of the dumped process, from an NT_PRSTATUS note.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Thread {
+    /// Thread id
+    pub pid: u32,
+    /// Signal that caused the dump, or 0
+    pub signal: u16,
+    /// General purpose registers, in the order of the target's `struct user_regs_struct`
+    pub registers: Vec<u64>,
+}
+
+impl Thread {
+    /// Get the program counter, for the architectures whose register layout is known.
+    pub fn pc(&self, arch: types::ElfCpuArchitecture) -> Option<u64> {
+        match arch {
+            types::ElfCpuArchitecture::EM_X86_64 => self.registers.get(16).copied(),
+            types::ElfCpuArchitecture::EM_AARCH64 => self.registers.get(32).copied(),
+            _ => None,
+        }
+    }
+
+    /// Get the stack pointer, for the architectures whose register layout is known.
+    pub fn sp(&self, arch: types::ElfCpuArchitecture) -> Option<u64> {
+        match arch {
+            types::ElfCpuArchitecture::EM_X86_64 => self.registers.get(19).copied(),
+            types::ElfCpuArchitecture::EM_AARCH64 => self.registers.get(31).copied(),
+            _ => None,
+        }
+    }
+}
+
+/// A file mapped into the dumped process, from the NT_FILE note.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MappedFile {
+    /// First address of the mapping
+    pub start: u64,
+    /// First address past the end of the mapping
+    pub end: u64,
+    /// Offset of the mapping in the file, in bytes
+    pub file_offset: u64,
+    /// Path of the file in the dumped process
+    pub path: String,
+}
+
+/// A core dump, with the contents of its segments.
+///
+/// Core dumps usually have no section headers, so the process memory and
+/// notes are only reachable through the program headers, and are read from
+/// the file contents kept by `File`.
+pub struct CoreDump {
+    /// The parsed ELF headers
+    pub file: File,
+}
+
+impl CoreDump {
+    pub fn open_path<T: AsRef<Path>>(path: T) -> Result<CoreDump, ParseError> {
+        CoreDump::parse(fs::read(path)?)
+    }
+
+    /// Parse a core dump from its contents.
+    pub fn parse(data: Vec<u8>) -> Result<CoreDump, ParseError> {
+        let file = File::open_stream(&mut io::Cursor::new(&data))?;
+        if file.header.elftype != types::ElfFileType::Core {
+            return Err(ParseError::InvalidFormat(Some(
+                "not a core file".to_string(),
+            )));
+        }
+        Ok(CoreDump { file })
+    }
+
+    fn address_size(&self) -> usize {
+        match self.file.header.class {
+            types::ElfClass::Format32 => 4,
+            types::ElfClass::Format64 => 8,
+        }
+    }
+
+    fn read_word(&self, data: &[u8]) -> u64 {
+        match (self.file.header.endianness, self.address_size()) {
+            (types::ElfEndianness::Lsb, 4) => u64::from(LittleEndian::read_u32(data)),
+            (types::ElfEndianness::Lsb, _) => LittleEndian::read_u64(data),
+            (types::ElfEndianness::Msb, 4) => u64::from(BigEndian::read_u32(data)),
+            (types::ElfEndianness::Msb, _) => BigEndian::read_u64(data),
+        }
+    }
+
+    /// Get the file contents of a segment.
+    ///
+    /// Returns None if the segment extends past the end of the file, as
+    /// happens with truncated dumps.
+    pub fn segment_data(&self, phdr: &types::ProgramHeader) -> Option<&[u8]> {
+        let data = self.file.get_segment_bytes(phdr)?;
+        if data.len() as u64 != phdr.filesz {
+            return None;
+        }
+        Some(data)
+    }
+
+    /// Parse the notes of all PT_NOTE segments.
+    pub fn notes(&self) -> Result<Vec<Note>, ParseError> {
+        let mut notes = Vec::new();
+        for phdr in self
+            .file
+            .phdrs
+            .iter()
+            .filter(|phdr| phdr.progtype == types::PT_NOTE)
+        {
+            let data = self.segment_data(phdr).ok_or_else(|| {
+                ParseError::InvalidFormat(Some("note segment past end of file".to_string()))
+            })?;
+            notes.extend(self.file.parse_notes(data, phdr.align)?);
+        }
+        Ok(notes)
+    }
+
+    /// Get the threads of the dumped process, with their registers.
+    ///
+    /// Only 64-bit dumps are supported, as the layout of the status note
+    /// differs for 32-bit targets.
+    pub fn threads(&self) -> Result<Vec<Thread>, ParseError> {
+        if self.address_size() != 8 {
+            return Err(ParseError::NotImplemented);
+        }
+        let mut threads = Vec::new();
+        for note in self.notes()? {
+            if note.name != "CORE" || note.ntype != NT_PRSTATUS {
+                continue;
+            }
+            if note.desc.len() < PRSTATUS_REG_64 {
+                return Err(ParseError::InvalidFormat(Some(
+                    "truncated NT_PRSTATUS note".to_string(),
+                )));
+            }
+            let (pid, signal) = match self.file.header.endianness {
+                types::ElfEndianness::Lsb => (
+                    LittleEndian::read_u32(&note.desc[PRSTATUS_PID_64..]),
+                    LittleEndian::read_u16(&note.desc[PRSTATUS_CURSIG..]),
+                ),
+                types::ElfEndianness::Msb => (
+                    BigEndian::read_u32(&note.desc[PRSTATUS_PID_64..]),
+                    BigEndian::read_u16(&note.desc[PRSTATUS_CURSIG..]),
+                ),
+            };
+            // pr_reg is followed by the 4 byte pr_fpvalid and padding
+            let count = match self.file.header.cpu_architecture {
+                types::ElfCpuArchitecture::EM_X86_64 => 27,
+                types::ElfCpuArchitecture::EM_AARCH64 => 34,
+                _ => note.desc.len().saturating_sub(PRSTATUS_REG_64 + 8) / 8,
+            };
+            let registers = note.desc[PRSTATUS_REG_64..]
+                .chunks_exact(8)
+                .take(count)
+                .map(|word| self.read_word(word))
+                .collect();
+            threads.push(Thread {
+                pid,
+                signal,
+                registers,
+            });
+        }
+        Ok(threads)
+    }
+
+    /// Get the files mapped into the dumped process.
+    pub fn mapped_files(&self) -> Result<Vec<MappedFile>, ParseError> {
+        let note = match self
+            .notes()?
+            .into_iter()
+            .find(|note| note.name == "CORE" && note.ntype == NT_FILE)
+        {
+            Some(note) => note,
+            None => return Ok(Vec::new()),
+        };
+        let size = self.address_size();
+        let truncated = || ParseError::InvalidFormat(Some("truncated NT_FILE note".to_string()));
+        let word = |index: usize| -> Result<u64, ParseError> {
+            let start = index.checked_mul(size).ok_or_else(truncated)?;
+            let data = note.desc.get(start..start + size).ok_or_else(truncated)?;
+            Ok(self.read_word(data))
+        };
+        // Each file takes three words, so count is bounded by the note size
+        let count = word(0)?;
+        if count > (note.desc.len() / (3 * size)) as u64 {
+            return Err(truncated());
+        }
+        let count = count as usize;
+        let page_size = word(1)?;
+        let mut names = note
+            .desc
+            .get((2 + count * 3) * size..)
+            .ok_or_else(truncated)?
+            .split(|&b| b == 0);
+        let mut files = Vec::with_capacity(count);
+        for index in 0..count {
+            let name = names.next().ok_or_else(truncated)?;
+            files.push(MappedFile {
+                start: word(2 + index * 3)?,
+                end: word(3 + index * 3)?,
+                file_offset: word(4 + index * 3)?.checked_mul(page_size).ok_or_else(|| {
+                    ParseError::InvalidFormat(Some("NT_FILE offset overflow".to_string()))
+                })?,
+                path: String::from_utf8_lossy(name).into_owned(),
+            });
+        }
+        Ok(files)
+    }
+
+    /// Read process memory saved in the dump.
+    ///
+    /// Returns None if any part of the range was not saved. Memory that is
+    /// part of a segment's memory size but not of its file size reads as zeroes.
+    pub fn read_memory(&self, address: u64, len: usize) -> Option<Vec<u8>> {
+        let end = address.checked_add(len as u64)?;
+        let phdr = self.file.phdrs.iter().find(|phdr| {
+            phdr.progtype == types::PT_LOAD
+                && address >= phdr.vaddr
+                && end <= phdr.vaddr.saturating_add(phdr.memsz)
+        })?;
+        // Segments of file mappings that were not dumped have no file contents
+        if phdr.filesz == 0 {
+            return None;
+        }
+        let data = self.segment_data(phdr)?;
+        let mut memory = vec![0u8; len];
+        let start = (address - phdr.vaddr) as usize;
+        if start < data.len() {
+            let available = (data.len() - start).min(len);
+            memory[..available].copy_from_slice(&data[start..start + available]);
+        }
+        Some(memory)
+    }
+
+    /// Read a pointer-sized value from process memory saved in the dump.
+    pub fn read_pointer(&self, address: u64) -> Option<u64> {
+        let data = self.read_memory(address, self.address_size())?;
+        Some(self.read_word(&data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_core_threads_and_files() {
+        let core = CoreDump::open_path("tests/samples/core/core").unwrap();
+        let arch = core.file.header.cpu_architecture;
+        let threads = core.threads().unwrap();
+        assert_eq!(threads.len(), 1);
+        // SIGSEGV
+        assert_eq!(threads[0].signal, 11);
+        let pc = threads[0].pc(arch).unwrap();
+        let sp = threads[0].sp(arch).unwrap();
+        assert_eq!(pc & 0xfff, 0x134);
+        assert!(core.read_pointer(sp).is_some());
+
+        let files = core.mapped_files().unwrap();
+        let crash: Vec<&MappedFile> = files
+            .iter()
+            .filter(|file| file.path.ends_with("/crash"))
+            .collect();
+        assert_eq!(crash.len(), 5);
+        assert_eq!(crash[0].file_offset, 0);
+        assert!(crash[0].start <= pc && pc < crash[4].end);
+        assert!(files.iter().any(|file| file.path.contains("libc.so")));
+    }
+
+    #[test]
+    fn test_core_aarch64() {
+        let core = CoreDump::open_path("tests/samples/core/aarch64/core").unwrap();
+        let arch = core.file.header.cpu_architecture;
+        assert_eq!(arch, types::ElfCpuArchitecture::EM_AARCH64);
+        let threads = core.threads().unwrap();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].pid, 4242);
+        assert_eq!(threads[0].signal, 11);
+        assert_eq!(threads[0].registers.len(), 34);
+        assert_eq!(threads[0].pc(arch), Some(0x55_0000_0214));
+        assert_eq!(threads[0].sp(arch), Some(0x7f_ffff_ff00));
+
+        let files = core.mapped_files().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[1].start, 0x55_0000_1000);
+        assert_eq!(files[1].file_offset, 0);
+        // File mappings were not dumped
+        assert_eq!(core.read_memory(0x55_0000_0214, 4), None);
+    }
+}
@@ -0,0 +1,1102 @@
+use crate::debug_line::{self, LineLocation, LineTable};
+use crate::dwarf::{self, AttributeValue, Encoding, Reader};
+use crate::types;
+use crate::{File, ParseError};
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Compilation unit. Named DW_TAG_compile_unit in C code.
+const DW_TAG_COMPILE_UNIT: u64 = 0x11;
+/// Inlined copy of a subprogram. Named DW_TAG_inlined_subroutine in C code.
+const DW_TAG_INLINED_SUBROUTINE: u64 = 0x1d;
+/// Function or subroutine. Named DW_TAG_subprogram in C code.
+const DW_TAG_SUBPROGRAM: u64 = 0x2e;
+/// Partial unit. Named DW_TAG_partial_unit in C code.
+const DW_TAG_PARTIAL_UNIT: u64 = 0x3c;
+/// Skeleton unit of a split unit. Named DW_TAG_skeleton_unit in C code.
+const DW_TAG_SKELETON_UNIT: u64 = 0x4a;
+
+/// Named DW_AT_name in C code.
+const DW_AT_NAME: u64 = 0x03;
+/// Named DW_AT_stmt_list in C code.
+const DW_AT_STMT_LIST: u64 = 0x10;
+/// Named DW_AT_low_pc in C code.
+const DW_AT_LOW_PC: u64 = 0x11;
+/// Named DW_AT_high_pc in C code.
+const DW_AT_HIGH_PC: u64 = 0x12;
+/// Named DW_AT_language in C code.
+const DW_AT_LANGUAGE: u64 = 0x13;
+/// Named DW_AT_comp_dir in C code.
+const DW_AT_COMP_DIR: u64 = 0x1b;
+/// Named DW_AT_producer in C code.
+const DW_AT_PRODUCER: u64 = 0x25;
+/// Named DW_AT_abstract_origin in C code.
+const DW_AT_ABSTRACT_ORIGIN: u64 = 0x31;
+/// Named DW_AT_specification in C code.
+const DW_AT_SPECIFICATION: u64 = 0x47;
+/// Named DW_AT_ranges in C code.
+const DW_AT_RANGES: u64 = 0x55;
+/// Named DW_AT_call_column in C code.
+const DW_AT_CALL_COLUMN: u64 = 0x57;
+/// Named DW_AT_call_file in C code.
+const DW_AT_CALL_FILE: u64 = 0x58;
+/// Named DW_AT_call_line in C code.
+const DW_AT_CALL_LINE: u64 = 0x59;
+/// Named DW_AT_linkage_name in C code.
+const DW_AT_LINKAGE_NAME: u64 = 0x6e;
+/// Named DW_AT_str_offsets_base in C code.
+const DW_AT_STR_OFFSETS_BASE: u64 = 0x72;
+/// Named DW_AT_addr_base in C code.
+const DW_AT_ADDR_BASE: u64 = 0x73;
+/// Named DW_AT_rnglists_base in C code.
+const DW_AT_RNGLISTS_BASE: u64 = 0x74;
+/// Named DW_AT_MIPS_linkage_name in C code.
+const DW_AT_MIPS_LINKAGE_NAME: u64 = 0x2007;
+
+/// Full compilation unit. Named DW_UT_compile in C code.
+const DW_UT_COMPILE: u8 = 0x01;
+/// Type unit. Named DW_UT_type in C code.
+const DW_UT_TYPE: u8 = 0x02;
+/// Partial unit. Named DW_UT_partial in C code.
+const DW_UT_PARTIAL: u8 = 0x03;
+/// Skeleton of a split unit. Named DW_UT_skeleton in C code.
+const DW_UT_SKELETON: u8 = 0x04;
+/// Split compilation unit. Named DW_UT_split_compile in C code.
+const DW_UT_SPLIT_COMPILE: u8 = 0x05;
+/// Split type unit. Named DW_UT_split_type in C code.
+const DW_UT_SPLIT_TYPE: u8 = 0x06;
+
+/// End of a range list. Named DW_RLE_end_of_list in C code.
+const DW_RLE_END_OF_LIST: u8 = 0x00;
+/// Base address from .debug_addr. Named DW_RLE_base_addressx in C code.
+const DW_RLE_BASE_ADDRESSX: u8 = 0x01;
+/// Start and end from .debug_addr. Named DW_RLE_startx_endx in C code.
+const DW_RLE_STARTX_ENDX: u8 = 0x02;
+/// Start from .debug_addr and a length. Named DW_RLE_startx_length in C code.
+const DW_RLE_STARTX_LENGTH: u8 = 0x03;
+/// Offsets from the base address. Named DW_RLE_offset_pair in C code.
+const DW_RLE_OFFSET_PAIR: u8 = 0x04;
+/// Base address. Named DW_RLE_base_address in C code.
+const DW_RLE_BASE_ADDRESS: u8 = 0x05;
+/// Start and end addresses. Named DW_RLE_start_end in C code.
+const DW_RLE_START_END: u8 = 0x06;
+/// Start address and a length. Named DW_RLE_start_length in C code.
+const DW_RLE_START_LENGTH: u8 = 0x07;
+
+/// Follow at most this many abstract origins and specifications to find a name
+const MAX_NAME_INDIRECTIONS: usize = 16;
+
+/// A half-open range of addresses.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Range {
+    /// First address in the range
+    pub begin: u64,
+    /// First address past the end of the range
+    pub end: u64,
+}
+
+impl Range {
+    /// Returns true if the range contains an address.
+    pub fn contains(&self, address: u64) -> bool {
+        self.begin <= address && address < self.end
+    }
+}
+
+/// A compilation unit from the `.debug_info` section.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompileUnit {
+    /// Offset of the unit header in the `.debug_info` section
+    pub offset: u64,
+    /// DWARF version of the unit, from 2 to 5
+    pub version: u16,
+    /// Name of the primary source file
+    pub name: Option<String>,
+    /// Compiler that produced the unit
+    pub producer: Option<String>,
+    /// Source language, one of the DW_LANG_* constants in [crate::dwarf]
+    pub language: Option<u64>,
+    /// Working directory of the compilation
+    pub comp_dir: Option<String>,
+    /// Addresses covered by the unit
+    pub ranges: Vec<Range>,
+    /// Offset of the unit's line number program in the `.debug_line` section
+    pub line_offset: Option<u64>,
+}
+
+/// A function with code, described by a DW_TAG_subprogram entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subprogram {
+    /// Offset of the entry in the `.debug_info` section
+    pub offset: u64,
+    /// Index of the unit in [DebugInfo::units]
+    pub unit: usize,
+    /// Source name
+    pub name: Option<String>,
+    /// Mangled name
+    pub linkage_name: Option<String>,
+    /// Addresses of the function's code
+    pub ranges: Vec<Range>,
+    /// Functions inlined directly into this one
+    pub inlined: Vec<InlinedSubroutine>,
+}
+
+/// A copy of a function that was inlined into another one, described by a
+/// DW_TAG_inlined_subroutine entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InlinedSubroutine {
+    /// Offset of the entry in the `.debug_info` section
+    pub offset: u64,
+    /// Source name of the inlined function
+    pub name: Option<String>,
+    /// Mangled name of the inlined function
+    pub linkage_name: Option<String>,
+    /// Addresses of the inlined code
+    pub ranges: Vec<Range>,
+    /// Source file of the call that was inlined
+    pub call_file: Option<String>,
+    /// Source line of the call that was inlined, or 0 if unknown
+    pub call_line: u64,
+    /// Source column of the call that was inlined, or 0 if unknown
+    pub call_column: u64,
+    /// Functions inlined directly into this one
+    pub inlined: Vec<InlinedSubroutine>,
+}
+
+/// One function in the chain of inlined calls at an address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// Source name of the function
+    pub function: Option<String>,
+    /// Mangled name of the function
+    pub linkage_name: Option<String>,
+    /// Source location within the function
+    pub location: Option<LineLocation>,
+}
+
+/// The compilation units and functions of a file, indexed for address lookups.
+#[derive(Clone, Debug)]
+pub struct DebugInfo {
+    /// Compilation units, in section order
+    pub units: Vec<CompileUnit>,
+    /// Functions with code, in section order
+    pub subprograms: Vec<Subprogram>,
+    lines: LineTable,
+    /// (begin, end, index into subprograms), sorted by begin
+    index: Vec<(u64, u64, usize)>,
+}
+
+impl DebugInfo {
+    /// Get the line table of the file.
+    pub fn line_table(&self) -> &LineTable {
+        &self.lines
+    }
+
+    /// Find the compilation unit that covers an address.
+    pub fn find_unit(&self, address: u64) -> Option<&CompileUnit> {
+        self.units
+            .iter()
+            .find(|unit| unit.ranges.iter().any(|range| range.contains(address)))
+    }
+
+    /// Find the function whose code contains an address.
+    pub fn find_subprogram(&self, address: u64) -> Option<&Subprogram> {
+        let end = self
+            .index
+            .partition_point(|&(begin, _, _)| begin <= address);
+        self.index[..end]
+            .iter()
+            .rev()
+            .find(|&&(_, end, _)| address < end)
+            .map(|&(_, _, subprogram)| &self.subprograms[subprogram])
+    }
+
+    /// Find the chain of inlined functions at an address, outermost first.
+    pub fn find_inlined(&self, address: u64) -> Vec<&InlinedSubroutine> {
+        let mut chain = Vec::new();
+        if let Some(subprogram) = self.find_subprogram(address) {
+            let mut inlined = &subprogram.inlined;
+            while let Some(inner) = inlined
+                .iter()
+                .find(|inner| inner.ranges.iter().any(|range| range.contains(address)))
+            {
+                chain.push(inner);
+                inlined = &inner.inlined;
+            }
+        }
+        chain
+    }
+
+    /// Find the frames at an address, innermost first, like `addr2line -i`.
+    ///
+    /// The innermost frame is located with the line table, and each outer
+    /// frame at the call site of the function inlined into it. An address
+    /// with line information but no known function yields a single frame
+    /// without a name.
+    pub fn find_frames(&self, address: u64) -> Vec<Frame> {
+        let mut location = self.lines.lookup(address).map(|mut location| {
+            if let Some(comp_dir) = self
+                .find_unit(address)
+                .and_then(|unit| unit.comp_dir.as_ref())
+            {
+                location.file = debug_line::join_path(comp_dir, &location.file);
+            }
+            location
+        });
+        let subprogram = match self.find_subprogram(address) {
+            Some(subprogram) => subprogram,
+            None => {
+                return location
+                    .map(|location| Frame {
+                        function: None,
+                        linkage_name: None,
+                        location: Some(location),
+                    })
+                    .into_iter()
+                    .collect()
+            }
+        };
+        let mut frames = Vec::new();
+        for inlined in self.find_inlined(address).into_iter().rev() {
+            frames.push(Frame {
+                function: inlined.name.clone(),
+                linkage_name: inlined.linkage_name.clone(),
+                location: location.take(),
+            });
+            location = inlined.call_file.as_ref().map(|file| LineLocation {
+                file: file.clone(),
+                line: inlined.call_line,
+                column: inlined.call_column,
+            });
+        }
+        frames.push(Frame {
+            function: subprogram.name.clone(),
+            linkage_name: subprogram.linkage_name.clone(),
+            location,
+        });
+        frames
+    }
+}
+
+/// An abbreviation declaration from the `.debug_abbrev` section.
+struct Abbrev {
+    tag: u64,
+    has_children: bool,
+    /// (attribute, form, implicit constant)
+    attributes: Vec<(u64, u64, i64)>,
+}
+
+fn parse_abbrevs(
+    data: &[u8],
+    offset: u64,
+    endianness: types::ElfEndianness,
+) -> Result<HashMap<u64, Abbrev>, ParseError> {
+    let mut reader = Reader::new(data, endianness);
+    reader.bytes(offset as usize)?;
+    let mut abbrevs = HashMap::new();
+    loop {
+        let code = reader.uleb128()?;
+        if code == 0 {
+            break;
+        }
+        let tag = reader.uleb128()?;
+        let has_children = reader.u8()? != 0;
+        let mut attributes = Vec::new();
+        loop {
+            let name = reader.uleb128()?;
+            let form = reader.uleb128()?;
+            if name == 0 && form == 0 {
+                break;
+            }
+            let implicit_const = if form == dwarf::DW_FORM_IMPLICIT_CONST {
+                reader.sleb128()?
+            } else {
+                0
+            };
+            attributes.push((name, form, implicit_const));
+        }
+        abbrevs.insert(
+            code,
+            Abbrev {
+                tag,
+                has_children,
+                attributes,
+            },
+        );
+    }
+    Ok(abbrevs)
+}
+
+/// A debugging information entry.
+struct Die {
+    offset: u64,
+    tag: u64,
+    depth: usize,
+    attributes: Vec<(u64, AttributeValue)>,
+}
+
+impl Die {
+    fn attribute(&self, name: u64) -> Option<&AttributeValue> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| *attribute == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// A unit with its entries and the bases of its indexed attributes.
+struct Unit {
+    offset: u64,
+    encoding: Encoding,
+    dies: Vec<Die>,
+    str_offsets_base: u64,
+    addr_base: u64,
+    rnglists_base: u64,
+    base_address: u64,
+}
+
+fn reader_at(
+    data: &[u8],
+    endianness: types::ElfEndianness,
+    offset: u64,
+) -> Result<Reader<'_>, ParseError> {
+    let mut reader = Reader::new(data, endianness);
+    reader.bytes(offset as usize)?;
+    Ok(reader)
+}
+
+/// Get the offset of entry `index` of `size` bytes in a table starting at `base`.
+fn table_offset(base: u64, index: u64, size: u64) -> Result<u64, ParseError> {
+    index
+        .checked_mul(size)
+        .and_then(|offset| offset.checked_add(base))
+        .ok_or_else(|| ParseError::InvalidFormat(Some(format!("index {} out of range", index))))
+}
+
+/// The debug sections needed to resolve attribute values.
+struct Sections<'a> {
+    endianness: types::ElfEndianness,
+    debug_str: Cow<'a, [u8]>,
+    line_str: Cow<'a, [u8]>,
+    str_offsets: Cow<'a, [u8]>,
+    addr: Cow<'a, [u8]>,
+    ranges: Cow<'a, [u8]>,
+    rnglists: Cow<'a, [u8]>,
+}
+
+impl<'a> Sections<'a> {
+    fn load(file: &'a File) -> Result<Sections<'a>, ParseError> {
+        let load = |name| -> Result<Cow<'a, [u8]>, ParseError> {
+            Ok(file
+                .get_debug_section_data(name)?
+                .unwrap_or(Cow::Borrowed(&[])))
+        };
+        Ok(Sections {
+            endianness: file.header.endianness,
+            debug_str: load(".debug_str")?,
+            line_str: load(".debug_line_str")?,
+            str_offsets: load(".debug_str_offsets")?,
+            addr: load(".debug_addr")?,
+            ranges: load(".debug_ranges")?,
+            rnglists: load(".debug_rnglists")?,
+        })
+    }
+
+    fn string(&self, unit: &Unit, value: &AttributeValue) -> Result<Option<String>, ParseError> {
+        Ok(Some(match *value {
+            AttributeValue::String(ref string) => string.clone(),
+            AttributeValue::StrOffset(offset) => dwarf::string_at(&self.debug_str, offset)?,
+            AttributeValue::LineStrOffset(offset) => dwarf::string_at(&self.line_str, offset)?,
+            AttributeValue::StrIndex(index) => {
+                let size = match unit.encoding.format {
+                    dwarf::DwarfFormat::Dwarf32 => 4,
+                    dwarf::DwarfFormat::Dwarf64 => 8,
+                };
+                let mut reader = reader_at(
+                    &self.str_offsets,
+                    self.endianness,
+                    table_offset(unit.str_offsets_base, index, size)?,
+                )?;
+                let offset = reader.offset(unit.encoding.format)?;
+                dwarf::string_at(&self.debug_str, offset)?
+            }
+            // Strings in supplementary files are not available
+            _ => return Ok(None),
+        }))
+    }
+
+    fn address_index(&self, unit: &Unit, index: u64) -> Result<u64, ParseError> {
+        let offset = table_offset(unit.addr_base, index, u64::from(unit.encoding.address_size))?;
+        let mut reader = reader_at(&self.addr, self.endianness, offset)?;
+        reader.sized(unit.encoding.address_size)
+    }
+
+    fn address(&self, unit: &Unit, value: &AttributeValue) -> Result<Option<u64>, ParseError> {
+        match *value {
+            AttributeValue::Address(address) => Ok(Some(address)),
+            AttributeValue::AddressIndex(index) => self.address_index(unit, index).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Get the address ranges of an entry, from either its DW_AT_low_pc and
+    /// DW_AT_high_pc or its DW_AT_ranges attributes.
+    fn die_ranges(&self, unit: &Unit, die: &Die) -> Result<Vec<Range>, ParseError> {
+        let mut ranges = Vec::new();
+        if let Some(value) = die.attribute(DW_AT_RANGES) {
+            let offset = match *value {
+                AttributeValue::SecOffset(offset) | AttributeValue::Data(offset) => {
+                    if unit.encoding.version >= 5 {
+                        offset
+                    } else {
+                        return self.debug_ranges(unit, offset);
+                    }
+                }
+                AttributeValue::RangeListIndex(index) => {
+                    let size = match unit.encoding.format {
+                        dwarf::DwarfFormat::Dwarf32 => 4,
+                        dwarf::DwarfFormat::Dwarf64 => 8,
+                    };
+                    let mut reader = reader_at(
+                        &self.rnglists,
+                        self.endianness,
+                        table_offset(unit.rnglists_base, index, size)?,
+                    )?;
+                    table_offset(unit.rnglists_base, reader.offset(unit.encoding.format)?, 1)?
+                }
+                _ => return Ok(ranges),
+            };
+            return self.debug_rnglists(unit, offset);
+        }
+        let low_pc = match die.attribute(DW_AT_LOW_PC) {
+            Some(value) => self.address(unit, value)?,
+            None => None,
+        };
+        if let (Some(low_pc), Some(high_pc)) = (low_pc, die.attribute(DW_AT_HIGH_PC)) {
+            let high_pc = match *high_pc {
+                AttributeValue::Data(length) => low_pc.wrapping_add(length),
+                AttributeValue::Sdata(length) => low_pc.wrapping_add(length as u64),
+                ref value => self.address(unit, value)?.unwrap_or(low_pc),
+            };
+            ranges.push(Range {
+                begin: low_pc,
+                end: high_pc,
+            });
+        }
+        Ok(ranges)
+    }
+
+    /// Read a DWARF 2 to 4 range list from `.debug_ranges`.
+    fn debug_ranges(&self, unit: &Unit, offset: u64) -> Result<Vec<Range>, ParseError> {
+        let mut reader = reader_at(&self.ranges, self.endianness, offset)?;
+        let size = unit.encoding.address_size;
+        let max = if size == 8 {
+            u64::MAX
+        } else {
+            (1u64 << (size * 8)) - 1
+        };
+        let mut base = unit.base_address;
+        let mut ranges = Vec::new();
+        loop {
+            let begin = reader.sized(size)?;
+            let end = reader.sized(size)?;
+            if begin == 0 && end == 0 {
+                break;
+            }
+            if begin == max {
+                base = end;
+                continue;
+            }
+            ranges.push(Range {
+                begin: base.wrapping_add(begin),
+                end: base.wrapping_add(end),
+            });
+        }
+        Ok(ranges)
+    }
+
+    /// Read a DWARF 5 range list from `.debug_rnglists`.
+    fn debug_rnglists(&self, unit: &Unit, offset: u64) -> Result<Vec<Range>, ParseError> {
+        let mut reader = reader_at(&self.rnglists, self.endianness, offset)?;
+        let size = unit.encoding.address_size;
+        let mut base = unit.base_address;
+        let mut ranges = Vec::new();
+        loop {
+            let (begin, end) = match reader.u8()? {
+                DW_RLE_END_OF_LIST => break,
+                DW_RLE_BASE_ADDRESSX => {
+                    base = self.address_index(unit, reader.uleb128()?)?;
+                    continue;
+                }
+                DW_RLE_STARTX_ENDX => (
+                    self.address_index(unit, reader.uleb128()?)?,
+                    self.address_index(unit, reader.uleb128()?)?,
+                ),
+                DW_RLE_STARTX_LENGTH => {
+                    let begin = self.address_index(unit, reader.uleb128()?)?;
+                    (begin, begin.wrapping_add(reader.uleb128()?))
+                }
+                DW_RLE_OFFSET_PAIR => (
+                    base.wrapping_add(reader.uleb128()?),
+                    base.wrapping_add(reader.uleb128()?),
+                ),
+                DW_RLE_BASE_ADDRESS => {
+                    base = reader.sized(size)?;
+                    continue;
+                }
+                DW_RLE_START_END => (reader.sized(size)?, reader.sized(size)?),
+                DW_RLE_START_LENGTH => {
+                    let begin = reader.sized(size)?;
+                    (begin, begin.wrapping_add(reader.uleb128()?))
+                }
+                kind => {
+                    return Err(ParseError::InvalidFormat(Some(format!(
+                        "unknown range list entry kind {:#x}",
+                        kind
+                    ))))
+                }
+            };
+            ranges.push(Range { begin, end });
+        }
+        Ok(ranges)
+    }
+}
+
+fn parse_units(
+    data: &[u8],
+    abbrev_data: &[u8],
+    endianness: types::ElfEndianness,
+) -> Result<Vec<Unit>, ParseError> {
+    let mut abbrev_cache: HashMap<u64, HashMap<u64, Abbrev>> = HashMap::new();
+    let mut units = Vec::new();
+    let mut reader = Reader::new(data, endianness);
+    while !reader.is_empty() {
+        let offset = reader.offset as u64;
+        let (unit_length, format) = reader.initial_length()?;
+        let mut unit = reader.sub(unit_length as usize)?;
+        let version = unit.u16()?;
+        let (unit_type, abbrev_offset, address_size) = if version >= 5 {
+            let unit_type = unit.u8()?;
+            let address_size = unit.u8()?;
+            let abbrev_offset = unit.offset(format)?;
+            match unit_type {
+                DW_UT_SKELETON | DW_UT_SPLIT_COMPILE => {
+                    // dwo_id
+                    unit.u64()?;
+                }
+                DW_UT_TYPE | DW_UT_SPLIT_TYPE => {
+                    // type_signature and type_offset
+                    unit.u64()?;
+                    unit.offset(format)?;
+                }
+                _ => {}
+            }
+            (unit_type, abbrev_offset, address_size)
+        } else if version >= 2 {
+            let abbrev_offset = unit.offset(format)?;
+            (DW_UT_COMPILE, abbrev_offset, unit.u8()?)
+        } else {
+            return Err(ParseError::InvalidFormat(Some(format!(
+                "unsupported DWARF version {}",
+                version
+            ))));
+        };
+        if unit_type != DW_UT_COMPILE && unit_type != DW_UT_PARTIAL && unit_type != DW_UT_SKELETON {
+            continue;
+        }
+        let abbrevs = match abbrev_cache.entry(abbrev_offset) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(parse_abbrevs(abbrev_data, abbrev_offset, endianness)?)
+            }
+        };
+        let encoding = Encoding {
+            format,
+            version,
+            address_size,
+        };
+
+        let mut dies = Vec::new();
+        let mut depth = 0usize;
+        while !unit.is_empty() {
+            let die_offset = unit.offset as u64;
+            let code = unit.uleb128()?;
+            if code == 0 {
+                depth = depth.saturating_sub(1);
+                continue;
+            }
+            let abbrev = abbrevs.get(&code).ok_or_else(|| {
+                ParseError::InvalidFormat(Some(format!(
+                    "unknown abbreviation code {} at {:#x}",
+                    code, die_offset
+                )))
+            })?;
+            let mut attributes = Vec::with_capacity(abbrev.attributes.len());
+            for &(name, form, implicit_const) in abbrev.attributes.iter() {
+                attributes.push((name, unit.attribute(form, implicit_const, encoding)?));
+            }
+            dies.push(Die {
+                offset: die_offset,
+                tag: abbrev.tag,
+                depth,
+                attributes,
+            });
+            if abbrev.has_children {
+                depth += 1;
+            }
+        }
+
+        let root = dies.first();
+        let base = |name| {
+            root.and_then(|root| root.attribute(name))
+                .and_then(|value| match *value {
+                    AttributeValue::SecOffset(offset) | AttributeValue::Data(offset) => {
+                        Some(offset)
+                    }
+                    _ => None,
+                })
+        };
+        // Units without the attributes use the first table after the section header
+        let header_size = match format {
+            dwarf::DwarfFormat::Dwarf32 => 8,
+            dwarf::DwarfFormat::Dwarf64 => 16,
+        };
+        let str_offsets_base = base(DW_AT_STR_OFFSETS_BASE).unwrap_or(header_size);
+        let addr_base = base(DW_AT_ADDR_BASE).unwrap_or(header_size);
+        let rnglists_base = base(DW_AT_RNGLISTS_BASE).unwrap_or(header_size + 4);
+        // Indexed base addresses are resolved once .debug_addr is loaded
+        let base_address = match root.and_then(|root| root.attribute(DW_AT_LOW_PC)) {
+            Some(&AttributeValue::Address(address)) => address,
+            _ => 0,
+        };
+        units.push(Unit {
+            offset,
+            encoding,
+            dies,
+            str_offsets_base,
+            addr_base,
+            rnglists_base,
+            base_address,
+        });
+    }
+    Ok(units)
+}
+
+/// Builds the public view of the units, resolving names across entries.
+struct Builder<'a> {
+    sections: Sections<'a>,
+    units: Vec<Unit>,
+    /// Entry offset to (unit index, entry index)
+    offsets: HashMap<u64, (usize, usize)>,
+    lines: LineTable,
+    /// Code of relocatable objects legitimately starts at address 0
+    relocatable: bool,
+}
+
+impl<'a> Builder<'a> {
+    fn die(&self, unit: usize, value: &AttributeValue) -> Option<(usize, usize)> {
+        let offset = match *value {
+            AttributeValue::UnitRef(offset) => self.units[unit].offset.checked_add(offset)?,
+            AttributeValue::DebugInfoRef(offset) => offset,
+            _ => return None,
+        };
+        self.offsets.get(&offset).copied()
+    }
+
+    /// Get the name and linkage name of an entry, following abstract origins
+    /// and specifications for the ones it lacks.
+    fn names(
+        &self,
+        unit: usize,
+        die: usize,
+    ) -> Result<(Option<String>, Option<String>), ParseError> {
+        let (mut name, mut linkage_name) = (None, None);
+        let mut current = Some((unit, die));
+        for _ in 0..MAX_NAME_INDIRECTIONS {
+            let (unit_index, die_index) = match current {
+                Some(current) => current,
+                None => break,
+            };
+            let unit = &self.units[unit_index];
+            let die = &unit.dies[die_index];
+            if name.is_none() {
+                if let Some(value) = die.attribute(DW_AT_NAME) {
+                    name = self.sections.string(unit, value)?;
+                }
+            }
+            if linkage_name.is_none() {
+                if let Some(value) = die
+                    .attribute(DW_AT_LINKAGE_NAME)
+                    .or_else(|| die.attribute(DW_AT_MIPS_LINKAGE_NAME))
+                {
+                    linkage_name = self.sections.string(unit, value)?;
+                }
+            }
+            if name.is_some() && linkage_name.is_some() {
+                break;
+            }
+            current = die
+                .attribute(DW_AT_ABSTRACT_ORIGIN)
+                .or_else(|| die.attribute(DW_AT_SPECIFICATION))
+                .and_then(|value| self.die(unit_index, value));
+        }
+        Ok((name, linkage_name))
+    }
+
+    fn compile_unit(&self, index: usize) -> Result<Option<CompileUnit>, ParseError> {
+        let unit = &self.units[index];
+        let root = match unit.dies.first() {
+            Some(root) => root,
+            None => return Ok(None),
+        };
+        if root.tag != DW_TAG_COMPILE_UNIT
+            && root.tag != DW_TAG_PARTIAL_UNIT
+            && root.tag != DW_TAG_SKELETON_UNIT
+        {
+            return Ok(None);
+        }
+        let string = |name| -> Result<Option<String>, ParseError> {
+            match root.attribute(name) {
+                Some(value) => self.sections.string(unit, value),
+                None => Ok(None),
+            }
+        };
+        Ok(Some(CompileUnit {
+            offset: unit.offset,
+            version: unit.encoding.version,
+            name: string(DW_AT_NAME)?,
+            producer: string(DW_AT_PRODUCER)?,
+            language: root
+                .attribute(DW_AT_LANGUAGE)
+                .and_then(AttributeValue::udata),
+            comp_dir: string(DW_AT_COMP_DIR)?,
+            ranges: self.valid_ranges(self.sections.die_ranges(unit, root)?),
+            line_offset: match root.attribute(DW_AT_STMT_LIST) {
+                Some(&AttributeValue::SecOffset(offset)) | Some(&AttributeValue::Data(offset)) => {
+                    Some(offset)
+                }
+                _ => None,
+            },
+        }))
+    }
+
+    /// Drop empty ranges, and ranges of code that the linker discarded and
+    /// moved to address 0 or an all-ones tombstone.
+    fn valid_ranges(&self, ranges: Vec<Range>) -> Vec<Range> {
+        ranges
+            .into_iter()
+            .filter(|range| range.begin < range.end)
+            .filter(|range| self.relocatable || range.begin != 0)
+            .filter(|range| range.begin != u64::MAX && range.begin != u64::from(u32::MAX))
+            .collect()
+    }
+
+    /// Get the path of a file of a unit's line number program.
+    fn file_path(&self, unit: &CompileUnit, file: u64) -> Option<String> {
+        let program = self
+            .lines
+            .programs()
+            .iter()
+            .find(|program| Some(program.offset) == unit.line_offset)?;
+        let path = program.file_path(file)?;
+        Some(match unit.comp_dir {
+            Some(ref comp_dir) => debug_line::join_path(comp_dir, &path),
+            None => path,
+        })
+    }
+
+    /// Collect the subprograms of a unit with the functions inlined into them.
+    fn subprograms(
+        &self,
+        index: usize,
+        compile_unit: &CompileUnit,
+        subprograms: &mut Vec<Subprogram>,
+    ) -> Result<(), ParseError> {
+        let unit = &self.units[index];
+        for (die_index, die) in unit.dies.iter().enumerate() {
+            if die.tag != DW_TAG_SUBPROGRAM {
+                continue;
+            }
+            let ranges = self.valid_ranges(self.sections.die_ranges(unit, die)?);
+            if ranges.is_empty() {
+                continue;
+            }
+            let (name, linkage_name) = self.names(index, die_index)?;
+            subprograms.push(Subprogram {
+                offset: die.offset,
+                unit: index,
+                name,
+                linkage_name,
+                ranges,
+                inlined: self.inlined(index, compile_unit, die_index)?,
+            });
+        }
+        Ok(())
+    }
+
+    /// Build the tree of inlined subroutines below an entry.
+    fn inlined(
+        &self,
+        index: usize,
+        compile_unit: &CompileUnit,
+        parent: usize,
+    ) -> Result<Vec<InlinedSubroutine>, ParseError> {
+        let unit = &self.units[index];
+        let parent_depth = unit.dies[parent].depth;
+        let mut roots = Vec::new();
+        // Inlined subroutines that may still get children, with their depths
+        let mut stack: Vec<(usize, InlinedSubroutine)> = Vec::new();
+        let mut skip_below = None;
+        for (die_index, die) in unit.dies.iter().enumerate().skip(parent + 1) {
+            if die.depth <= parent_depth {
+                break;
+            }
+            while stack.last().is_some_and(|&(depth, _)| depth >= die.depth) {
+                let (_, done) = stack.pop().unwrap();
+                match stack.last_mut() {
+                    Some((_, outer)) => outer.inlined.push(done),
+                    None => roots.push(done),
+                }
+            }
+            if skip_below.is_some_and(|depth| die.depth > depth) {
+                continue;
+            }
+            skip_below = None;
+            match die.tag {
+                // Nested functions are collected on their own
+                DW_TAG_SUBPROGRAM => skip_below = Some(die.depth),
+                DW_TAG_INLINED_SUBROUTINE => {
+                    let (name, linkage_name) = self.names(index, die_index)?;
+                    let call_file = die
+                        .attribute(DW_AT_CALL_FILE)
+                        .and_then(AttributeValue::udata)
+                        .and_then(|file| self.file_path(compile_unit, file));
+                    let constant = |name| {
+                        die.attribute(name)
+                            .and_then(AttributeValue::udata)
+                            .unwrap_or(0)
+                    };
+                    stack.push((
+                        die.depth,
+                        InlinedSubroutine {
+                            offset: die.offset,
+                            name,
+                            linkage_name,
+                            ranges: self.valid_ranges(self.sections.die_ranges(unit, die)?),
+                            call_file,
+                            call_line: constant(DW_AT_CALL_LINE),
+                            call_column: constant(DW_AT_CALL_COLUMN),
+                            inlined: Vec::new(),
+                        },
+                    ));
+                }
+                _ => {}
+            }
+        }
+        while let Some((_, done)) = stack.pop() {
+            match stack.last_mut() {
+                Some((_, outer)) => outer.inlined.push(done),
+                None => roots.push(done),
+            }
+        }
+        Ok(roots)
+    }
+}
+
+impl File {
+    /// Parse the compilation units and functions in the `.debug_info` section.
+    ///
+    /// DWARF versions 2 to 5 are supported. Type units and entries that
+    /// refer to supplementary or split DWARF files are skipped.
+    pub fn get_debug_info(&self) -> Result<DebugInfo, ParseError> {
+        let info = self.get_debug_section_data(".debug_info")?;
+        let abbrev = self.get_debug_section_data(".debug_abbrev")?;
+        let mut units = match (info, abbrev) {
+            (Some(info), Some(abbrev)) => parse_units(&info, &abbrev, self.header.endianness)?,
+            _ => Vec::new(),
+        };
+        let sections = Sections::load(self)?;
+        // An indexed base address can only be read once DW_AT_addr_base is known
+        for unit in units.iter_mut() {
+            let base_address = match unit
+                .dies
+                .first()
+                .and_then(|root| root.attribute(DW_AT_LOW_PC))
+            {
+                Some(value @ AttributeValue::AddressIndex(_)) => sections.address(unit, value)?,
+                _ => None,
+            };
+            if let Some(base_address) = base_address {
+                unit.base_address = base_address;
+            }
+        }
+        let mut offsets = HashMap::new();
+        for (unit_index, unit) in units.iter().enumerate() {
+            for (die_index, die) in unit.dies.iter().enumerate() {
+                offsets.insert(die.offset, (unit_index, die_index));
+            }
+        }
+        let builder = Builder {
+            sections,
+            units,
+            offsets,
+            lines: self.get_line_table()?,
+            relocatable: self.header.elftype == types::ElfFileType::RelocatableObject,
+        };
+
+        let mut units = Vec::new();
+        let mut subprograms = Vec::new();
+        for index in 0..builder.units.len() {
+            if let Some(unit) = builder.compile_unit(index)? {
+                builder.subprograms(index, &unit, &mut subprograms)?;
+                units.push(unit);
+            }
+        }
+        // Subprograms refer to units by their index in the public list
+        for subprogram in subprograms.iter_mut() {
+            let offset = builder.units[subprogram.unit].offset;
+            subprogram.unit = units.iter().position(|unit| unit.offset == offset).unwrap();
+        }
+
+        let mut index = Vec::new();
+        for (position, subprogram) in subprograms.iter().enumerate() {
+            for range in subprogram.ranges.iter() {
+                index.push((range.begin, range.end, position));
+            }
+        }
+        index.sort_unstable();
+        Ok(DebugInfo {
+            units,
+            subprograms,
+            lines: builder.lines,
+            index,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn frames(info: &DebugInfo, address: u64) -> Vec<(String, String, u64)> {
+        info.find_frames(address)
+            .into_iter()
+            .map(|frame| {
+                let location = frame.location.unwrap();
+                (frame.function.unwrap(), location.file, location.line)
+            })
+            .collect()
+    }
+
+    fn frame(function: &str, line: u64) -> (String, String, u64) {
+        (function.to_string(), "/src/inline.c".to_string(), line)
+    }
+
+    #[test]
+    fn test_compile_units() {
+        // DWARF 4 has no language code for C11
+        for (path, version, language) in [
+            ("tests/samples/dwarf/inline_dwarf4", 4, dwarf::DW_LANG_C99),
+            ("tests/samples/dwarf/inline_dwarf5", 5, dwarf::DW_LANG_C11),
+        ] {
+            let file = File::open_path(PathBuf::from(path)).unwrap();
+            let info = file.get_debug_info().unwrap();
+            assert_eq!(info.units.len(), 1);
+            let unit = &info.units[0];
+            assert_eq!(unit.version, version);
+            assert_eq!(unit.name.as_deref(), Some("inline.c"));
+            assert_eq!(unit.comp_dir.as_deref(), Some("/src"));
+            assert_eq!(unit.language, Some(language));
+            assert!(unit.producer.as_ref().unwrap().starts_with("GNU C17"));
+            assert_eq!(
+                unit.ranges,
+                vec![
+                    Range {
+                        begin: 0x1160,
+                        end: 0x1177
+                    },
+                    Range {
+                        begin: 0x1050,
+                        end: 0x1056
+                    },
+                    Range {
+                        begin: 0x1060,
+                        end: 0x1068
+                    },
+                ]
+            );
+            let compute = info.find_subprogram(0x1052).unwrap();
+            assert_eq!(compute.name.as_deref(), Some("compute"));
+            assert_eq!(compute.ranges.len(), 2);
+            assert!(info.find_subprogram(0x1000).is_none());
+        }
+    }
+
+    #[test]
+    fn test_indexed_base_address() {
+        // The unit low_pc is a .debug_addr index, as emitted by clang, and
+        // the range list of compute is relative to it
+        let file = File::open_path(PathBuf::from("tests/samples/dwarf/addrx_dwarf5")).unwrap();
+        let info = file.get_debug_info().unwrap();
+        assert_eq!(
+            info.units[0].ranges,
+            vec![Range {
+                begin: 0x401000,
+                end: 0x401022
+            }]
+        );
+        let compute = info.find_subprogram(0x40101e).unwrap();
+        assert_eq!(compute.name.as_deref(), Some("compute"));
+        assert_eq!(
+            compute.ranges,
+            vec![
+                Range {
+                    begin: 0x40100e,
+                    end: 0x401016
+                },
+                Range {
+                    begin: 0x40101c,
+                    end: 0x401022
+                },
+            ]
+        );
+
+        // Indexes from the input must not overflow the table offsets
+        assert_eq!(table_offset(0x10, 3, 8).unwrap(), 0x28);
+        assert!(table_offset(0x10, u64::MAX, 8).is_err());
+        assert!(table_offset(u64::MAX, 1, 1).is_err());
+    }
+
+    #[test]
+    fn test_inlined_frames() {
+        for path in [
+            "tests/samples/dwarf/inline_dwarf4",
+            "tests/samples/dwarf/inline_dwarf5",
+        ] {
+            let file = File::open_path(PathBuf::from(path)).unwrap();
+            let info = file.get_debug_info().unwrap();
+            assert_eq!(
+                frames(&info, 0x1170),
+                vec![
+                    frame("square", 5),
+                    frame("sum_squares", 11),
+                    frame("compute", 19)
+                ]
+            );
+            assert_eq!(
+                frames(&info, 0x116d),
+                vec![
+                    frame("square", 5),
+                    frame("sum_squares", 10),
+                    frame("compute", 19)
+                ]
+            );
+            assert_eq!(frames(&info, 0x1052), vec![frame("compute", 18)]);
+            assert_eq!(frames(&info, 0x1062), vec![frame("main", 25)]);
+            let chain = info.find_inlined(0x1170);
+            assert_eq!(chain[0].call_column, 12);
+            assert_eq!(chain[1].call_column, 10);
+        }
+    }
+}
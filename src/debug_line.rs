@@ -0,0 +1,678 @@
+use crate::dwarf::{self, AttributeValue, Encoding, Reader};
+use crate::types;
+use crate::{File, ParseError};
+
+/// Copy the registers to a new row. Named DW_LNS_copy in C code.
+const DW_LNS_COPY: u8 = 0x01;
+/// Advance the address. Named DW_LNS_advance_pc in C code.
+const DW_LNS_ADVANCE_PC: u8 = 0x02;
+/// Advance the line. Named DW_LNS_advance_line in C code.
+const DW_LNS_ADVANCE_LINE: u8 = 0x03;
+/// Set the file. Named DW_LNS_set_file in C code.
+const DW_LNS_SET_FILE: u8 = 0x04;
+/// Set the column. Named DW_LNS_set_column in C code.
+const DW_LNS_SET_COLUMN: u8 = 0x05;
+/// Toggle is_stmt. Named DW_LNS_negate_stmt in C code.
+const DW_LNS_NEGATE_STMT: u8 = 0x06;
+/// Mark the start of a basic block. Named DW_LNS_set_basic_block in C code.
+const DW_LNS_SET_BASIC_BLOCK: u8 = 0x07;
+/// Advance the address like special opcode 255. Named DW_LNS_const_add_pc in C code.
+const DW_LNS_CONST_ADD_PC: u8 = 0x08;
+/// Advance the address by a fixed size operand. Named DW_LNS_fixed_advance_pc in C code.
+const DW_LNS_FIXED_ADVANCE_PC: u8 = 0x09;
+/// Mark the end of a function prologue. Named DW_LNS_set_prologue_end in C code.
+const DW_LNS_SET_PROLOGUE_END: u8 = 0x0a;
+/// Mark the start of a function epilogue. Named DW_LNS_set_epilogue_begin in C code.
+const DW_LNS_SET_EPILOGUE_BEGIN: u8 = 0x0b;
+/// Set the instruction set architecture. Named DW_LNS_set_isa in C code.
+const DW_LNS_SET_ISA: u8 = 0x0c;
+
+/// End a sequence of rows. Named DW_LNE_end_sequence in C code.
+const DW_LNE_END_SEQUENCE: u8 = 0x01;
+/// Set the address. Named DW_LNE_set_address in C code.
+const DW_LNE_SET_ADDRESS: u8 = 0x02;
+/// Add a file entry, before DWARF 5. Named DW_LNE_define_file in C code.
+const DW_LNE_DEFINE_FILE: u8 = 0x03;
+/// Set the discriminator. Named DW_LNE_set_discriminator in C code.
+const DW_LNE_SET_DISCRIMINATOR: u8 = 0x04;
+
+/// Path of a directory or file entry. Named DW_LNCT_path in C code.
+const DW_LNCT_PATH: u64 = 0x1;
+/// Directory index of a file entry. Named DW_LNCT_directory_index in C code.
+const DW_LNCT_DIRECTORY_INDEX: u64 = 0x2;
+/// Modification time of a file entry. Named DW_LNCT_timestamp in C code.
+const DW_LNCT_TIMESTAMP: u64 = 0x3;
+/// Size of a file entry. Named DW_LNCT_size in C code.
+const DW_LNCT_SIZE: u64 = 0x4;
+/// MD5 digest of a file entry. Named DW_LNCT_MD5 in C code.
+const DW_LNCT_MD5: u64 = 0x5;
+
+/// A source file referenced by a line number program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileEntry {
+    /// File name, as recorded by the compiler
+    pub name: String,
+    /// Index into [LineProgram::include_directories]
+    pub directory_index: u64,
+    /// Modification time, or 0 if unknown
+    pub mtime: u64,
+    /// File size in bytes, or 0 if unknown
+    pub size: u64,
+    /// MD5 digest of the file contents, if recorded
+    pub md5: Option<[u8; 16]>,
+}
+
+/// A row of the line number matrix.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LineRow {
+    /// Address of the first instruction of the row
+    pub address: u64,
+    /// Index of the instruction within a VLIW bundle
+    pub op_index: u64,
+    /// Index of the source file, see [LineProgram::file]
+    pub file: u64,
+    /// Source line, starting from 1, or 0 if the instruction has no line
+    pub line: u64,
+    /// Source column, starting from 1, or 0 for the whole line
+    pub column: u64,
+    /// Recommended breakpoint location
+    pub is_stmt: bool,
+    /// Start of a basic block
+    pub basic_block: bool,
+    /// First address past the end of a sequence of instructions
+    pub end_sequence: bool,
+    /// End of a function prologue
+    pub prologue_end: bool,
+    /// Start of a function epilogue
+    pub epilogue_begin: bool,
+    /// Instruction set architecture
+    pub isa: u64,
+    /// Block the instruction belongs to
+    pub discriminator: u64,
+}
+
+/// A line number program from the `.debug_line` section, with its decoded rows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineProgram {
+    /// Offset of the program in the `.debug_line` section
+    pub offset: u64,
+    /// Line table format version, from 2 to 5
+    pub version: u16,
+    /// Size of an address in bytes
+    pub address_size: u8,
+    /// Size of the smallest instruction in bytes
+    pub minimum_instruction_length: u8,
+    /// Maximum number of operations in a VLIW bundle
+    pub maximum_operations_per_instruction: u8,
+    /// Initial value of the is_stmt register
+    pub default_is_stmt: bool,
+    /// Smallest line advance of a special opcode
+    pub line_base: i8,
+    /// Number of line advances of special opcodes
+    pub line_range: u8,
+    /// Number of the first special opcode
+    pub opcode_base: u8,
+    /// Include directories. Before DWARF 5 the compilation directory is
+    /// implicit, so directory index 0 refers to it and index 1 to the first
+    /// entry.
+    pub include_directories: Vec<String>,
+    /// Source files. Before DWARF 5 file index 1 refers to the first entry.
+    pub files: Vec<FileEntry>,
+    /// Rows of the line number matrix, in program order
+    pub rows: Vec<LineRow>,
+}
+
+impl LineProgram {
+    /// Get a file entry by the index used in [LineRow::file].
+    pub fn file(&self, index: u64) -> Option<&FileEntry> {
+        if self.version >= 5 {
+            self.files.get(index as usize)
+        } else {
+            index
+                .checked_sub(1)
+                .and_then(|index| self.files.get(index as usize))
+        }
+    }
+
+    /// Get an include directory by the index used in [FileEntry::directory_index].
+    ///
+    /// Before DWARF 5 index 0 is the compilation directory, which is not
+    /// recorded in the line program, so an empty string is returned for it.
+    pub fn directory(&self, index: u64) -> Option<&str> {
+        if self.version >= 5 {
+            self.include_directories
+                .get(index as usize)
+                .map(String::as_str)
+        } else if index == 0 {
+            Some("")
+        } else {
+            self.include_directories
+                .get(index as usize - 1)
+                .map(String::as_str)
+        }
+    }
+
+    /// Get the path of a file by the index used in [LineRow::file], joined
+    /// with its include directory.
+    ///
+    /// Relative include directories are relative to the compilation
+    /// directory, which is only known to the line program from DWARF 5 on.
+    pub fn file_path(&self, index: u64) -> Option<String> {
+        let file = self.file(index)?;
+        let mut path = join_path(
+            self.directory(file.directory_index).unwrap_or(""),
+            &file.name,
+        );
+        if self.version >= 5 && file.directory_index != 0 {
+            path = join_path(self.directory(0).unwrap_or(""), &path);
+        }
+        Some(path)
+    }
+}
+
+pub(crate) fn join_path(directory: &str, name: &str) -> String {
+    if directory.is_empty() || name.starts_with('/') {
+        name.to_string()
+    } else if directory.ends_with('/') {
+        format!("{}{}", directory, name)
+    } else {
+        format!("{}/{}", directory, name)
+    }
+}
+
+/// A source location found for an address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineLocation {
+    /// Source file path
+    pub file: String,
+    /// Source line, or 0 if the instruction has no line
+    pub line: u64,
+    /// Source column, or 0 for the whole line
+    pub column: u64,
+}
+
+/// A range of addresses covered by a sequence of rows of one program.
+#[derive(Copy, Clone, Debug)]
+struct Sequence {
+    start: u64,
+    end: u64,
+    program: usize,
+    rows: (usize, usize),
+}
+
+/// All line number programs of a file, indexed for address lookups.
+#[derive(Clone, Debug)]
+pub struct LineTable {
+    programs: Vec<LineProgram>,
+    sequences: Vec<Sequence>,
+}
+
+impl LineTable {
+    /// Index the sequences of a set of line number programs.
+    ///
+    /// Code of relocatable objects legitimately starts at address 0, so
+    /// sequences there are only dropped if `relocatable` is unset.
+    pub fn new(programs: Vec<LineProgram>, relocatable: bool) -> LineTable {
+        let mut sequences = Vec::new();
+        for (index, program) in programs.iter().enumerate() {
+            let mut start = 0;
+            for (row_index, row) in program.rows.iter().enumerate() {
+                if !row.end_sequence {
+                    continue;
+                }
+                let first = program.rows[start].address;
+                // Sequences of discarded functions are moved to address 0 or
+                // an all-ones tombstone by the linker
+                if row_index > start && (relocatable || first != 0) && first < row.address {
+                    sequences.push(Sequence {
+                        start: first,
+                        end: row.address,
+                        program: index,
+                        rows: (start, row_index),
+                    });
+                }
+                start = row_index + 1;
+            }
+        }
+        sequences.sort_by_key(|sequence| sequence.start);
+        LineTable {
+            programs,
+            sequences,
+        }
+    }
+
+    /// Get the indexed line number programs.
+    pub fn programs(&self) -> &[LineProgram] {
+        &self.programs
+    }
+
+    /// Find the row describing the instruction at an address, and its program.
+    pub fn find_row(&self, address: u64) -> Option<(&LineProgram, &LineRow)> {
+        let index = self
+            .sequences
+            .partition_point(|sequence| sequence.start <= address);
+        let sequence = self.sequences[..index]
+            .iter()
+            .rev()
+            .find(|sequence| address < sequence.end)?;
+        let program = &self.programs[sequence.program];
+        let rows = &program.rows[sequence.rows.0..sequence.rows.1];
+        let row = rows.partition_point(|row| row.address <= address);
+        Some((program, &rows[row - 1]))
+    }
+
+    /// Find the source file, line and column of the instruction at an address.
+    pub fn lookup(&self, address: u64) -> Option<LineLocation> {
+        let (program, row) = self.find_row(address)?;
+        Some(LineLocation {
+            file: program.file_path(row.file)?,
+            line: row.line,
+            column: row.column,
+        })
+    }
+}
+
+/// Read the DWARF 5 entry format description and entries of a directory or file table.
+fn read_entries(
+    reader: &mut Reader,
+    encoding: Encoding,
+    line_str: &[u8],
+    debug_str: &[u8],
+) -> Result<Vec<FileEntry>, ParseError> {
+    let format_count = reader.u8()?;
+    let mut formats = Vec::with_capacity(format_count as usize);
+    for _ in 0..format_count {
+        formats.push((reader.uleb128()?, reader.uleb128()?));
+    }
+    let count = reader.uleb128()?;
+    // Entries take at least one byte each, except with an empty format
+    if count > reader.remaining() as u64 {
+        return Err(ParseError::InvalidFormat(Some(format!(
+            "{} line table entries do not fit in the header",
+            count
+        ))));
+    }
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let mut entry = FileEntry {
+            name: String::new(),
+            directory_index: 0,
+            mtime: 0,
+            size: 0,
+            md5: None,
+        };
+        for &(content, form) in formats.iter() {
+            let value = reader.attribute(form, 0, encoding)?;
+            match (content, value) {
+                (DW_LNCT_PATH, AttributeValue::String(name)) => entry.name = name,
+                (DW_LNCT_PATH, AttributeValue::LineStrOffset(offset)) => {
+                    entry.name = dwarf::string_at(line_str, offset)?
+                }
+                (DW_LNCT_PATH, AttributeValue::StrOffset(offset)) => {
+                    entry.name = dwarf::string_at(debug_str, offset)?
+                }
+                (DW_LNCT_DIRECTORY_INDEX, AttributeValue::Data(index)) => {
+                    entry.directory_index = index
+                }
+                (DW_LNCT_TIMESTAMP, AttributeValue::Data(mtime)) => entry.mtime = mtime,
+                (DW_LNCT_SIZE, AttributeValue::Data(size)) => entry.size = size,
+                (DW_LNCT_MD5, AttributeValue::Data16(md5)) => entry.md5 = Some(md5),
+                // Vendor defined content is skipped
+                _ => {}
+            }
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Registers of the line number state machine.
+struct State {
+    row: LineRow,
+    default_is_stmt: bool,
+}
+
+impl State {
+    fn new(default_is_stmt: bool) -> State {
+        State {
+            row: LineRow {
+                address: 0,
+                op_index: 0,
+                file: 1,
+                line: 1,
+                column: 0,
+                is_stmt: default_is_stmt,
+                basic_block: false,
+                end_sequence: false,
+                prologue_end: false,
+                epilogue_begin: false,
+                isa: 0,
+                discriminator: 0,
+            },
+            default_is_stmt,
+        }
+    }
+
+    fn emit(&mut self, rows: &mut Vec<LineRow>) {
+        rows.push(self.row);
+        self.row.basic_block = false;
+        self.row.prologue_end = false;
+        self.row.epilogue_begin = false;
+        self.row.discriminator = 0;
+    }
+}
+
+fn parse_line_program(
+    reader: &mut Reader,
+    default_address_size: u8,
+    line_str: &[u8],
+    debug_str: &[u8],
+) -> Result<LineProgram, ParseError> {
+    let offset = reader.offset as u64;
+    let (unit_length, format) = reader.initial_length()?;
+    let mut unit = reader.sub(unit_length as usize)?;
+    let version = unit.u16()?;
+    if !(2..=5).contains(&version) {
+        return Err(ParseError::InvalidFormat(Some(format!(
+            "unsupported line table version {}",
+            version
+        ))));
+    }
+    let mut address_size = default_address_size;
+    if version >= 5 {
+        address_size = unit.u8()?;
+        // segment_selector_size
+        unit.u8()?;
+    }
+    let header_length = unit.offset(format)?;
+    let program_start = unit
+        .offset
+        .checked_add(header_length as usize)
+        .ok_or_else(|| ParseError::InvalidFormat(Some("invalid header_length".to_string())))?;
+    let minimum_instruction_length = unit.u8()?;
+    let maximum_operations_per_instruction = if version >= 4 { unit.u8()? } else { 1 };
+    let default_is_stmt = unit.u8()? != 0;
+    let line_base = unit.u8()? as i8;
+    let line_range = unit.u8()?;
+    let opcode_base = unit.u8()?;
+    if line_range == 0 {
+        return Err(ParseError::InvalidFormat(Some(
+            "line table with a line_range of 0".to_string(),
+        )));
+    }
+    let standard_opcode_lengths = unit.bytes(opcode_base.saturating_sub(1) as usize)?;
+
+    let mut include_directories = Vec::new();
+    let mut files = Vec::new();
+    if version >= 5 {
+        let encoding = Encoding {
+            format,
+            version,
+            address_size,
+        };
+        include_directories = read_entries(&mut unit, encoding, line_str, debug_str)?
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        files = read_entries(&mut unit, encoding, line_str, debug_str)?;
+    } else {
+        loop {
+            let directory = unit.cstr()?;
+            if directory.is_empty() {
+                break;
+            }
+            include_directories.push(String::from_utf8_lossy(directory).into_owned());
+        }
+        loop {
+            let name = unit.cstr()?;
+            if name.is_empty() {
+                break;
+            }
+            files.push(FileEntry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                directory_index: unit.uleb128()?,
+                mtime: unit.uleb128()?,
+                size: unit.uleb128()?,
+                md5: None,
+            });
+        }
+    }
+
+    let mut program = LineProgram {
+        offset,
+        version,
+        address_size,
+        minimum_instruction_length,
+        maximum_operations_per_instruction: maximum_operations_per_instruction.max(1),
+        default_is_stmt,
+        line_base,
+        line_range,
+        opcode_base,
+        include_directories,
+        files,
+        rows: Vec::new(),
+    };
+
+    unit.offset = program_start;
+    let mut state = State::new(default_is_stmt);
+    let mut rows = Vec::new();
+    while !unit.is_empty() {
+        let opcode = unit.u8()?;
+        if opcode >= opcode_base {
+            let adjusted = opcode - opcode_base;
+            program.advance(&mut state.row, u64::from(adjusted / line_range));
+            state.row.line = state
+                .row
+                .line
+                .wrapping_add((i64::from(line_base) + i64::from(adjusted % line_range)) as u64);
+            state.emit(&mut rows);
+            continue;
+        }
+        match opcode {
+            0 => {
+                let len = unit.uleb128()? as usize;
+                let mut extended = unit.sub(len)?;
+                if len == 0 {
+                    continue;
+                }
+                match extended.u8()? {
+                    DW_LNE_END_SEQUENCE => {
+                        state.row.end_sequence = true;
+                        state.emit(&mut rows);
+                        state = State::new(state.default_is_stmt);
+                    }
+                    DW_LNE_SET_ADDRESS => {
+                        state.row.address = extended.sized((len - 1) as u8)?;
+                        state.row.op_index = 0;
+                    }
+                    DW_LNE_DEFINE_FILE => {
+                        let name = extended.cstr()?;
+                        program.files.push(FileEntry {
+                            name: String::from_utf8_lossy(name).into_owned(),
+                            directory_index: extended.uleb128()?,
+                            mtime: extended.uleb128()?,
+                            size: extended.uleb128()?,
+                            md5: None,
+                        });
+                    }
+                    DW_LNE_SET_DISCRIMINATOR => state.row.discriminator = extended.uleb128()?,
+                    // Unknown extended opcodes are skipped using their length
+                    _ => {}
+                }
+            }
+            DW_LNS_COPY => state.emit(&mut rows),
+            DW_LNS_ADVANCE_PC => {
+                let advance = unit.uleb128()?;
+                program.advance(&mut state.row, advance);
+            }
+            DW_LNS_ADVANCE_LINE => {
+                state.row.line = state.row.line.wrapping_add(unit.sleb128()? as u64)
+            }
+            DW_LNS_SET_FILE => state.row.file = unit.uleb128()?,
+            DW_LNS_SET_COLUMN => state.row.column = unit.uleb128()?,
+            DW_LNS_NEGATE_STMT => state.row.is_stmt = !state.row.is_stmt,
+            DW_LNS_SET_BASIC_BLOCK => state.row.basic_block = true,
+            DW_LNS_CONST_ADD_PC => {
+                let advance = u64::from((255 - opcode_base) / line_range);
+                program.advance(&mut state.row, advance);
+            }
+            DW_LNS_FIXED_ADVANCE_PC => {
+                state.row.address = state.row.address.wrapping_add(u64::from(unit.u16()?));
+                state.row.op_index = 0;
+            }
+            DW_LNS_SET_PROLOGUE_END => state.row.prologue_end = true,
+            DW_LNS_SET_EPILOGUE_BEGIN => state.row.epilogue_begin = true,
+            DW_LNS_SET_ISA => state.row.isa = unit.uleb128()?,
+            _ => {
+                // Unknown standard opcodes are skipped using their operand count
+                for _ in 0..standard_opcode_lengths[opcode as usize - 1] {
+                    unit.uleb128()?;
+                }
+            }
+        }
+    }
+    program.rows = rows;
+    Ok(program)
+}
+
+impl LineProgram {
+    /// Apply an operation advance to the address and op_index registers.
+    fn advance(&self, row: &mut LineRow, operation_advance: u64) {
+        let min_length = u64::from(self.minimum_instruction_length);
+        let max_ops = u64::from(self.maximum_operations_per_instruction);
+        if max_ops == 1 {
+            row.address = row
+                .address
+                .wrapping_add(min_length.wrapping_mul(operation_advance));
+        } else {
+            let ops = row.op_index.wrapping_add(operation_advance);
+            row.address = row
+                .address
+                .wrapping_add(min_length.wrapping_mul(ops / max_ops));
+            row.op_index = ops % max_ops;
+        }
+    }
+}
+
+impl File {
+    /// Parse all line number programs in the `.debug_line` section.
+    ///
+    /// Compressed debug sections are decompressed. Addresses in relocatable
+    /// objects are returned as stored, without applying relocations.
+    pub fn get_line_programs(&self) -> Result<Vec<LineProgram>, ParseError> {
+        let data = match self.get_debug_section_data(".debug_line")? {
+            Some(data) => data,
+            None => return Ok(Vec::new()),
+        };
+        let line_str = self.get_debug_section_data(".debug_line_str")?;
+        let debug_str = self.get_debug_section_data(".debug_str")?;
+        let line_str = line_str.as_deref().unwrap_or(&[]);
+        let debug_str = debug_str.as_deref().unwrap_or(&[]);
+        let address_size = match self.header.class {
+            types::ElfClass::Format32 => 4,
+            types::ElfClass::Format64 => 8,
+        };
+
+        let mut reader = Reader::new(&data, self.header.endianness);
+        let mut programs = Vec::new();
+        while !reader.is_empty() {
+            programs.push(parse_line_program(
+                &mut reader,
+                address_size,
+                line_str,
+                debug_str,
+            )?);
+        }
+        Ok(programs)
+    }
+
+    /// Parse the `.debug_line` section into a table for address to line lookups.
+    pub fn get_line_table(&self) -> Result<LineTable, ParseError> {
+        Ok(LineTable::new(
+            self.get_line_programs()?,
+            self.header.elftype == types::ElfFileType::RelocatableObject,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn line_table(path: &str) -> LineTable {
+        File::open_path(PathBuf::from(path))
+            .unwrap()
+            .get_line_table()
+            .unwrap()
+    }
+
+    fn location(file: &str, line: u64, column: u64) -> Option<LineLocation> {
+        Some(LineLocation {
+            file: file.to_string(),
+            line,
+            column,
+        })
+    }
+
+    #[test]
+    fn test_line_table_dwarf5() {
+        let table = line_table("tests/samples/dwarf/lines_dwarf5");
+        let program = &table.programs()[0];
+        assert_eq!(program.version, 5);
+        assert_eq!(program.include_directories, vec!["/src", "inc"]);
+        assert_eq!(program.file_path(0).unwrap(), "/src/lines.c");
+        assert_eq!(program.file_path(1).unwrap(), "/src/inc/helper.h");
+
+        // helper() from the header, then compute() and main()
+        assert_eq!(table.lookup(0x1130), location("/src/inc/helper.h", 3, 14));
+        assert_eq!(table.lookup(0x113e), location("/src/lines.c", 4, 1));
+        assert_eq!(table.lookup(0x1150), location("/src/lines.c", 5, 9));
+        assert_eq!(table.lookup(0x117a), location("/src/lines.c", 13, 12));
+        assert_eq!(table.lookup(0x1000), None);
+    }
+
+    #[test]
+    fn test_entry_count_bound() {
+        let encoding = Encoding {
+            format: dwarf::DwarfFormat::Dwarf32,
+            version: 5,
+            address_size: 8,
+        };
+        // No entry formats and u64::MAX entries
+        let data = [
+            0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01,
+        ];
+        let mut reader = Reader::new(&data, types::ElfEndianness::Lsb);
+        assert!(read_entries(&mut reader, encoding, &[], &[]).is_err());
+    }
+
+    #[test]
+    fn test_line_table_dwarf3() {
+        let table = line_table("tests/samples/dwarf/lines_dwarf3");
+        let program = &table.programs()[0];
+        assert_eq!(program.version, 3);
+        assert_eq!(program.file_path(1).unwrap(), "lines.c");
+        assert_eq!(table.lookup(0x1130), location("inc/helper.h", 3, 14));
+        assert_eq!(table.lookup(0x1150), location("lines.c", 5, 9));
+    }
+
+    #[test]
+    fn test_line_table_relocatable() {
+        // square() starts at address 0 of .text
+        let table = line_table("tests/samples/dwarf/lines.o");
+        assert_eq!(table.lookup(0), location("lines.c", 2, 1));
+        assert_eq!(table.lookup(0x13), location("lines.c", 8, 9));
+    }
+
+    #[cfg(feature = "zlib")]
+    #[test]
+    fn test_line_table_compressed() {
+        let plain = line_table("tests/samples/dwarf/lines_dwarf5");
+        let zlib = line_table("tests/samples/dwarf/lines_zlib");
+        assert_eq!(plain.programs(), zlib.programs());
+        let zgnu = line_table("tests/samples/dwarf/lines_zgnu");
+        assert_eq!(zgnu.programs()[0].version, 4);
+        assert_eq!(zgnu.lookup(0x1150), location("lines.c", 5, 9));
+    }
+}
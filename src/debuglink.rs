@@ -0,0 +1,360 @@
+use crate::types;
+use crate::{File, ParseError};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::path::{Component, Path, PathBuf};
+
+/// Compute the CRC32 checksum used by `.gnu_debuglink`, continuing from `crc`.
+///
+/// This is the usual CRC-32 (IEEE 802.3) checksum; pass 0 to start.
+pub fn gnu_debuglink_crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (!(crc & 1)).wrapping_add(1));
+        }
+    }
+    !crc
+}
+
+/// The contents of a `.gnu_debuglink` section.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DebugLink {
+    /// File name of the separate debug file
+    pub filename: String,
+    /// CRC32 checksum of the whole debug file
+    pub crc: u32,
+}
+
+/// The contents of a `.gnu_debugaltlink` section, referring to a file of
+/// debug information shared between several debug files, e.g. by dwz.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DebugAltLink {
+    /// Path of the shared file, relative to the directory of the file holding the link
+    pub filename: String,
+    /// Build ID of the shared file
+    pub build_id: Vec<u8>,
+}
+
+/// How a separate debug file was matched to a binary.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DebugFileMatch {
+    /// The build IDs are equal
+    BuildId,
+    /// The checksum of the debug file matches the `.gnu_debuglink` section
+    DebugLink,
+}
+
+/// A separate debug file found for a binary.
+#[derive(Debug)]
+pub struct DebugFile {
+    /// Path of the debug file
+    pub path: PathBuf,
+    /// The parsed debug file
+    pub file: File,
+    /// How the debug file was matched
+    pub matched_by: DebugFileMatch,
+}
+
+/// Where to look for separate debug files, following gdb.
+#[derive(Clone, Debug)]
+pub struct DebugFileSearch {
+    /// Global debug directories, e.g. `/usr/lib/debug`
+    pub debug_dirs: Vec<PathBuf>,
+    /// Look up files by build ID under `.build-id` in the debug directories
+    pub use_build_id: bool,
+    /// Look up files by `.gnu_debuglink` next to the binary and in the debug directories
+    pub use_debuglink: bool,
+}
+
+impl Default for DebugFileSearch {
+    fn default() -> Self {
+        DebugFileSearch {
+            debug_dirs: vec![PathBuf::from("/usr/lib/debug")],
+            use_build_id: true,
+            use_debuglink: true,
+        }
+    }
+}
+
+/// Read and parse a candidate debug file. Missing or invalid files are not candidates.
+fn read_candidate(path: &Path) -> Option<File> {
+    File::open_path(path).ok()
+}
+
+/// Get the file name of the `.build-id` link for a build ID.
+fn build_id_path(build_id: &[u8]) -> Option<PathBuf> {
+    let (first, rest) = build_id.split_first()?;
+    if rest.is_empty() {
+        return None;
+    }
+    let rest: String = rest.iter().map(|b| format!("{:02x}", b)).collect();
+    Some(
+        Path::new(".build-id")
+            .join(format!("{:02x}", first))
+            .join(format!("{}.debug", rest)),
+    )
+}
+
+impl DebugFileSearch {
+    /// Get the paths where the debug file of a build ID may be, in search order.
+    pub fn build_id_candidates(&self, build_id: &[u8]) -> Vec<PathBuf> {
+        match build_id_path(build_id) {
+            Some(path) => self.debug_dirs.iter().map(|dir| dir.join(&path)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Get the paths where the debug file named by a `.gnu_debuglink` may
+    /// be, in search order: the binary's directory, its `.debug`
+    /// subdirectory, and the binary's directory under each debug directory.
+    pub fn debuglink_candidates(&self, path: &Path, link: &DebugLink) -> Vec<PathBuf> {
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut candidates = vec![
+            dir.join(&link.filename),
+            dir.join(".debug").join(&link.filename),
+        ];
+        // Join the binary's directory below the debug directory, even if it is absolute
+        let relative: PathBuf = dir
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect();
+        candidates.extend(
+            self.debug_dirs
+                .iter()
+                .map(|debug_dir| debug_dir.join(&relative).join(&link.filename)),
+        );
+        candidates
+    }
+
+    /// Find the separate debug file of a binary.
+    ///
+    /// `path` is where the binary was read from. Candidates are checked by
+    /// build ID first, then by `.gnu_debuglink`. A candidate is only
+    /// returned if its build ID or checksum matches.
+    pub fn find(&self, path: &Path, file: &File) -> Result<Option<DebugFile>, ParseError> {
+        if self.use_build_id {
+            if let Some(build_id) = file.build_id()? {
+                if let Some(found) = self.find_by_build_id(&build_id)? {
+                    return Ok(Some(found));
+                }
+            }
+        }
+        if self.use_debuglink {
+            if let Some(link) = file.get_debuglink()? {
+                for candidate in self.debuglink_candidates(path, &link) {
+                    // Do not match the binary itself if the link names it
+                    if candidate == path {
+                        continue;
+                    }
+                    if let Some(debug_file) = read_candidate(&candidate) {
+                        // The CRC covers the whole file, which the File keeps
+                        let crc = debug_file
+                            .raw_data()
+                            .map(|data| gnu_debuglink_crc32(0, data));
+                        if crc == Some(link.crc) {
+                            return Ok(Some(DebugFile {
+                                path: candidate,
+                                file: debug_file,
+                                matched_by: DebugFileMatch::DebugLink,
+                            }));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find the file of debug information shared through `.gnu_debugaltlink`.
+    ///
+    /// `path` is where the file holding the link was read from, usually a
+    /// separate debug file. The link's path is tried first, then the build ID
+    /// in the debug directories. A candidate is only returned if its build
+    /// ID matches.
+    pub fn find_alt(&self, path: &Path, file: &File) -> Result<Option<DebugFile>, ParseError> {
+        let link = match file.get_debugaltlink()? {
+            Some(link) => link,
+            None => return Ok(None),
+        };
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let candidate = dir.join(&link.filename);
+        if let Some(alt_file) = read_candidate(&candidate) {
+            if alt_file.build_id().ok().flatten().as_deref() == Some(&link.build_id[..]) {
+                return Ok(Some(DebugFile {
+                    path: candidate,
+                    file: alt_file,
+                    matched_by: DebugFileMatch::BuildId,
+                }));
+            }
+        }
+        self.find_by_build_id(&link.build_id)
+    }
+
+    fn find_by_build_id(&self, build_id: &[u8]) -> Result<Option<DebugFile>, ParseError> {
+        for candidate in self.build_id_candidates(build_id) {
+            if let Some(debug_file) = read_candidate(&candidate) {
+                if debug_file.build_id().ok().flatten().as_deref() == Some(build_id) {
+                    return Ok(Some(DebugFile {
+                        path: candidate,
+                        file: debug_file,
+                        matched_by: DebugFileMatch::BuildId,
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl File {
+    /// Get the build ID from the NT_GNU_BUILD_ID note.
+    ///
+    /// Notes are read from SHT_NOTE sections, or from PT_NOTE segments if
+    /// there are no sections.
+    pub fn build_id(&self) -> Result<Option<Vec<u8>>, ParseError> {
+        let mut notes = Vec::new();
+        for section in &self.sections {
+            notes.extend(self.get_notes(section)?);
+        }
+        if self.sections.is_empty() {
+            for phdr in self
+                .phdrs
+                .iter()
+                .filter(|phdr| phdr.progtype == types::PT_NOTE)
+            {
+                notes.extend(self.get_segment_notes(phdr)?);
+            }
+        }
+        Ok(notes
+            .into_iter()
+            .find(|note| note.name == "GNU" && note.ntype == types::NT_GNU_BUILD_ID)
+            .map(|note| note.desc))
+    }
+
+    /// Parse the `.gnu_debuglink` section. Returns None if there is none.
+    pub fn get_debuglink(&self) -> Result<Option<DebugLink>, ParseError> {
+        let section = match self.get_section(".gnu_debuglink") {
+            Some(section) => section,
+            None => return Ok(None),
+        };
+        let invalid =
+            || ParseError::InvalidFormat(Some("invalid .gnu_debuglink section".to_string()));
+        let len = section
+            .data
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(invalid)?;
+        // The checksum follows the name, aligned to 4 bytes
+        let crc_offset = (len + 4) & !3;
+        let crc = section
+            .data
+            .get(crc_offset..crc_offset + 4)
+            .ok_or_else(invalid)?;
+        Ok(Some(DebugLink {
+            filename: String::from_utf8(section.data[..len].to_vec())?,
+            crc: match self.header.endianness {
+                types::ElfEndianness::Lsb => LittleEndian::read_u32(crc),
+                types::ElfEndianness::Msb => BigEndian::read_u32(crc),
+            },
+        }))
+    }
+
+    /// Parse the `.gnu_debugaltlink` section. Returns None if there is none.
+    pub fn get_debugaltlink(&self) -> Result<Option<DebugAltLink>, ParseError> {
+        let section = match self.get_section(".gnu_debugaltlink") {
+            Some(section) => section,
+            None => return Ok(None),
+        };
+        let len = section.data.iter().position(|&b| b == 0).ok_or_else(|| {
+            ParseError::InvalidFormat(Some("invalid .gnu_debugaltlink section".to_string()))
+        })?;
+        Ok(Some(DebugAltLink {
+            filename: String::from_utf8(section.data[..len].to_vec())?,
+            build_id: section.data[len + 1..].to_vec(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: &str = "tests/samples/debuglink";
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(gnu_debuglink_crc32(0, b"123456789"), 0xcbf4_3926);
+        assert_eq!(
+            gnu_debuglink_crc32(gnu_debuglink_crc32(0, b"1234"), b"56789"),
+            0xcbf4_3926
+        );
+    }
+
+    #[test]
+    fn test_links() {
+        let file = File::open_path(Path::new(SAMPLES).join("prog")).unwrap();
+        let link = file.get_debuglink().unwrap().unwrap();
+        assert_eq!(link.filename, "prog.debug");
+        assert_eq!(link.crc, 0xeeca_9780);
+        assert_eq!(
+            file.build_id().unwrap().unwrap()[..4],
+            [0xc6, 0x69, 0x40, 0x37]
+        );
+        assert!(file.get_debugaltlink().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_build_id_without_sections() {
+        // The same binary as the debuglink sample, with the section headers removed
+        let file = File::open_path("tests/samples/x86_64_nosections").unwrap();
+        assert!(file.sections.is_empty());
+        let stripped = file.build_id().unwrap().unwrap();
+        let file = File::open_path(Path::new(SAMPLES).join("prog")).unwrap();
+        assert_eq!(Some(stripped), file.build_id().unwrap());
+    }
+
+    #[test]
+    fn test_find_by_debuglink() {
+        let path = Path::new(SAMPLES).join("prog");
+        let file = File::open_path(&path).unwrap();
+        let search = DebugFileSearch {
+            debug_dirs: Vec::new(),
+            ..Default::default()
+        };
+        // prog.debug next to the binary has the wrong checksum, so the one in .debug is found
+        let found = search.find(&path, &file).unwrap().unwrap();
+        assert_eq!(found.path, Path::new(SAMPLES).join(".debug/prog.debug"));
+        assert_eq!(found.matched_by, DebugFileMatch::DebugLink);
+        assert!(found.file.get_section(".debug_info").is_some());
+
+        let alt = search.find_alt(&found.path, &found.file).unwrap().unwrap();
+        assert_eq!(alt.path, Path::new(SAMPLES).join(".debug/../alt.debug"));
+        assert_eq!(alt.matched_by, DebugFileMatch::BuildId);
+    }
+
+    #[test]
+    fn test_find_by_build_id() {
+        let path = Path::new(SAMPLES).join("prog");
+        let file = File::open_path(&path).unwrap();
+        let search = DebugFileSearch {
+            debug_dirs: vec![Path::new(SAMPLES).join("root")],
+            use_debuglink: false,
+            ..Default::default()
+        };
+        let found = search.find(&path, &file).unwrap().unwrap();
+        assert_eq!(
+            found.path,
+            Path::new(SAMPLES)
+                .join("root/.build-id/c6/6940378b96311d52e5a2be3edaee1623fbe1d0.debug")
+        );
+        assert_eq!(found.matched_by, DebugFileMatch::BuildId);
+
+        let search = DebugFileSearch {
+            debug_dirs: Vec::new(),
+            use_debuglink: false,
+            ..Default::default()
+        };
+        assert!(search.find(&path, &file).unwrap().is_none());
+    }
+}
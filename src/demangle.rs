@@ -0,0 +1,185 @@
+use crate::types;
+use crate::{File, ParseError};
+
+/// Name mangling scheme of a symbol.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ManglingScheme {
+    /// Itanium C++ ABI, used by GCC and Clang
+    Itanium,
+    /// Rust legacy scheme, an Itanium-like path ending in a hash
+    RustLegacy,
+    /// Rust v0 scheme
+    RustV0,
+}
+
+/// Options controlling how names are demangled.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DemangleOptions {
+    /// Drop the hash suffix of Rust legacy names and the crate
+    /// disambiguators of Rust v0 names
+    pub strip_rust_hash: bool,
+}
+
+/// Determine the mangling scheme of a symbol name, if it is mangled.
+///
+/// Names are only classified if they demangle, as C identifiers may start
+/// with the same prefixes, like `_Reset_Handler`.
+pub fn mangling_scheme(name: &str) -> Option<ManglingScheme> {
+    let scheme = scheme_of_prefix(name)?;
+    demangle_as(name, scheme, &DemangleOptions::default())?;
+    Some(scheme)
+}
+
+/// The mangling scheme a name would use, from its prefix alone.
+fn scheme_of_prefix(name: &str) -> Option<ManglingScheme> {
+    if name.starts_with("_R") {
+        Some(ManglingScheme::RustV0)
+    } else if is_rust_legacy(name) {
+        Some(ManglingScheme::RustLegacy)
+    } else if name.starts_with("_Z") {
+        Some(ManglingScheme::Itanium)
+    } else {
+        None
+    }
+}
+
+/// Rust legacy names are nested names whose last component is `17h` followed by 16 hex digits
+fn is_rust_legacy(name: &str) -> bool {
+    // Clones made by optimizations add suffixes like ".llvm.1234"
+    let name = name.split('.').next().unwrap_or(name);
+    if !name.starts_with("_ZN") || !name.ends_with('E') || name.len() < 24 {
+        return false;
+    }
+    // Work on bytes, as the name may not be ASCII
+    let hash = &name.as_bytes()[name.len() - 20..name.len() - 1];
+    hash.starts_with(b"17h") && hash[3..].iter().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Demangle a symbol name.
+///
+/// Returns None if the name is not mangled or cannot be demangled.
+pub fn demangle(name: &str, options: &DemangleOptions) -> Option<String> {
+    demangle_as(name, scheme_of_prefix(name)?, options)
+}
+
+fn demangle_as(name: &str, scheme: ManglingScheme, options: &DemangleOptions) -> Option<String> {
+    match scheme {
+        ManglingScheme::RustLegacy | ManglingScheme::RustV0 => {
+            let demangled = rustc_demangle::try_demangle(name).ok()?;
+            Some(if options.strip_rust_hash {
+                format!("{:#}", demangled)
+            } else {
+                demangled.to_string()
+            })
+        }
+        ManglingScheme::Itanium => cpp_demangle::Symbol::new(name)
+            .ok()?
+            .demangle(&cpp_demangle::DemangleOptions::default())
+            .ok(),
+    }
+}
+
+impl types::Symbol {
+    /// Get the demangled symbol name, or the raw name if it is not mangled.
+    pub fn demangled(&self, options: &DemangleOptions) -> String {
+        demangle(&self.name, options).unwrap_or_else(|| self.name.clone())
+    }
+
+    /// Returns true if `name` is the raw or the demangled name of this symbol.
+    pub fn matches_name(&self, name: &str, options: &DemangleOptions) -> bool {
+        self.name == name
+            || demangle(&self.name, options).is_some_and(|demangled| demangled == name)
+    }
+}
+
+impl File {
+    /// Find symbols by raw or demangled name.
+    ///
+    /// Both the static and the dynamic symbol tables are searched, so a
+    /// symbol may be returned twice.
+    pub fn find_symbols(
+        &self,
+        name: &str,
+        options: &DemangleOptions,
+    ) -> Result<Vec<types::Symbol>, ParseError> {
+        let mut found = Vec::new();
+        for section in self.sections.iter().filter(|section| {
+            section.shdr.shtype == types::SectionType::Symtab
+                || section.shdr.shtype == types::SectionType::Dynsym
+        }) {
+            found.extend(
+                self.get_symbols(section)?
+                    .into_iter()
+                    .filter(|symbol| symbol.matches_name(name, options)),
+            );
+        }
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_mangling_scheme() {
+        assert_eq!(mangling_scheme("main"), None);
+        assert_eq!(mangling_scheme("_Z6answerv"), Some(ManglingScheme::Itanium));
+        assert_eq!(
+            mangling_scheme("_ZN3foo3bar17h05af221e174051e9E"),
+            Some(ManglingScheme::RustLegacy)
+        );
+        assert_eq!(
+            mangling_scheme("_RNvCs15kBYyAo9fc_7mycrate7example"),
+            Some(ManglingScheme::RustV0)
+        );
+        // The hash position falls inside a multi-byte character
+        let name = format!("_ZN{}E", "\u{e9}".repeat(20));
+        assert_ne!(mangling_scheme(&name), Some(ManglingScheme::RustLegacy));
+        // C names with mangling prefixes
+        assert_eq!(mangling_scheme("_Reset_Handler"), None);
+        assert_eq!(mangling_scheme("_Zero"), None);
+    }
+
+    #[test]
+    fn test_demangle() {
+        let keep = DemangleOptions::default();
+        let strip = DemangleOptions {
+            strip_rust_hash: true,
+        };
+        assert_eq!(demangle("main", &keep), None);
+        assert_eq!(
+            demangle("_Z5twiceIiET_S0_", &keep).unwrap(),
+            "int twice<int>(int)"
+        );
+        assert_eq!(
+            demangle("_ZN3foo3bar17h05af221e174051e9E", &keep).unwrap(),
+            "foo::bar::h05af221e174051e9"
+        );
+        assert_eq!(
+            demangle("_ZN3foo3bar17h05af221e174051e9E", &strip).unwrap(),
+            "foo::bar"
+        );
+        assert_eq!(
+            demangle("_RNvCs15kBYyAo9fc_7mycrate7example", &keep).unwrap(),
+            "mycrate[ca63f166dbe9294]::example"
+        );
+        assert_eq!(
+            demangle("_RNvCs15kBYyAo9fc_7mycrate7example", &strip).unwrap(),
+            "mycrate::example"
+        );
+    }
+
+    #[test]
+    fn test_find_symbols() {
+        let file = File::open_path(PathBuf::from("tests/samples/comdat.o")).unwrap();
+        let options = DemangleOptions::default();
+        let found = file.find_symbols("answer()", &options).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "_Z6answerv");
+        assert_eq!(found[0].demangled(&options), "answer()");
+        let found = file.find_symbols("_Z6use_iti", &options).unwrap();
+        assert_eq!(found[0].demangled(&options), "use_it(int)");
+    }
+}
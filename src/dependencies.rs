@@ -0,0 +1,501 @@
+use crate::ld_so_cache::LdSoCache;
+use crate::types;
+use crate::{File, ParseError};
+use std::collections::{HashSet, VecDeque};
+use std::path::{Component, Path, PathBuf};
+
+/// Maximum number of symbolic links followed while resolving a path in the sysroot.
+const MAX_SYMLINKS: usize = 40;
+
+/// Where a library search directory came from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SearchSource {
+    /// The DT_NEEDED entry contains a slash and is used as a path, relative
+    /// to the working directory unless it is absolute
+    Direct,
+    /// DT_RPATH of the loading object or one of its loaders
+    Rpath,
+    /// The `library_path` of the resolver, equivalent to LD_LIBRARY_PATH
+    LibraryPath,
+    /// DT_RUNPATH of the loading object
+    Runpath,
+    /// The loader cache, see [DependencyResolver::cache]
+    Cache,
+    /// The default system library directories
+    Default,
+}
+
+/// A single candidate path that was tried while resolving a library.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchAttempt {
+    /// Where the directory of this candidate came from
+    pub source: SearchSource,
+    /// Candidate path, as seen from inside the sysroot
+    pub path: PathBuf,
+}
+
+/// A DT_NEEDED entry and the library it resolved to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    /// Library name from DT_NEEDED
+    pub name: String,
+    /// Path of the object that needs the library, as seen from inside the sysroot
+    pub needed_by: PathBuf,
+    /// Resolved path as seen from inside the sysroot, or None if the library is missing
+    pub path: Option<PathBuf>,
+    /// Candidates in the order they were tried
+    pub search_order: Vec<SearchAttempt>,
+}
+
+/// The dependency closure of an object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DependencyClosure {
+    /// Dependencies in the breadth-first order the dynamic loader would load them.
+    ///
+    /// Libraries needed by several objects are only searched for the first time.
+    pub dependencies: Vec<Dependency>,
+}
+
+impl DependencyClosure {
+    /// Dependencies that could not be resolved
+    pub fn missing(&self) -> impl Iterator<Item = &Dependency> {
+        self.dependencies.iter().filter(|dep| dep.path.is_none())
+    }
+
+    /// Paths of all resolved libraries, as seen from inside the sysroot
+    pub fn libraries(&self) -> impl Iterator<Item = &Path> {
+        self.dependencies
+            .iter()
+            .filter_map(|dep| dep.path.as_deref())
+    }
+}
+
+/// Emulates the search the dynamic loader performs for DT_NEEDED entries,
+/// without executing anything.
+///
+/// All paths are interpreted relative to `sysroot`, including absolute
+/// paths in DT_RPATH/DT_RUNPATH and absolute symbolic links.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DependencyResolver {
+    /// Directory that stands in for `/`
+    pub sysroot: PathBuf,
+    /// Directories searched like LD_LIBRARY_PATH
+    pub library_path: Vec<String>,
+    /// Working directory of the process, used for DT_NEEDED entries containing a slash
+    pub cwd: PathBuf,
+    /// Loader cache consulted after DT_RUNPATH, like `/etc/ld.so.cache`
+    pub cache: Option<LdSoCache>,
+    /// glibc-hwcaps subdirectories accepted from the cache, in order of preference
+    pub hwcaps: Vec<String>,
+    /// Directories searched after everything else
+    pub default_dirs: Vec<String>,
+    /// Expansion of `$PLATFORM`; derived from the machine type if None
+    pub platform: Option<String>,
+    /// Expansion of `$LIB`; derived from the file class if None
+    pub lib: Option<String>,
+}
+
+struct PendingObject {
+    path: PathBuf,
+    file: File,
+    // DT_RPATH directories of this object's loaders that have no
+    // DT_RUNPATH, nearest first
+    rpath_chain: Vec<Vec<String>>,
+}
+
+impl DependencyResolver {
+    pub fn new<T: AsRef<Path>>(sysroot: T) -> DependencyResolver {
+        DependencyResolver {
+            sysroot: sysroot.as_ref().to_path_buf(),
+            library_path: Vec::new(),
+            cwd: PathBuf::from("/"),
+            cache: None,
+            hwcaps: Vec::new(),
+            default_dirs: ["/lib64", "/usr/lib64", "/lib", "/usr/lib"]
+                .iter()
+                .map(|dir| dir.to_string())
+                .collect(),
+            platform: None,
+            lib: None,
+        }
+    }
+
+    /// Load `/etc/ld.so.cache` from the sysroot as the loader cache.
+    pub fn load_cache(&mut self) -> Result<(), ParseError> {
+        self.cache = Some(LdSoCache::open_path(self.host_path("/etc/ld.so.cache"))?);
+        Ok(())
+    }
+
+    /// Compute the dependency closure of the object at `path` inside the sysroot.
+    pub fn resolve<T: AsRef<Path>>(&self, path: T) -> Result<DependencyClosure, ParseError> {
+        let path = Path::new("/").join(path.as_ref());
+        let root = File::open_path(self.host_path(&path))?;
+        let header = root.header;
+        // $ORIGIN in LD_LIBRARY_PATH refers to the directory of the executable
+        let library_path: Vec<String> = self
+            .library_path
+            .iter()
+            .flat_map(|dir| self.expand_search_path(dir, &path, &header))
+            .collect();
+
+        let mut dependencies = Vec::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut loaded: HashSet<PathBuf> = HashSet::new();
+        loaded.insert(path.clone());
+        if let Some(soname) = root.get_soname()? {
+            seen.insert(soname);
+        }
+
+        let mut queue = VecDeque::new();
+        queue.push_back(PendingObject {
+            path,
+            file: root,
+            rpath_chain: Vec::new(),
+        });
+
+        while let Some(object) = queue.pop_front() {
+            let runpath = object.file.get_runpath()?;
+            let mut rpath_chain = object.rpath_chain.clone();
+            // The DT_RPATH of an object with a DT_RUNPATH is ignored, also
+            // when searching for the dependencies of its dependencies
+            if runpath.is_none() {
+                if let Some(rpath) = object.file.get_rpath()? {
+                    rpath_chain.insert(0, self.expand_search_path(&rpath, &object.path, &header));
+                }
+            }
+
+            for name in object.file.get_needed()? {
+                if !seen.insert(name.clone()) {
+                    continue;
+                }
+
+                let mut search_order = Vec::new();
+                let mut found = None;
+                let candidates = self.candidates(
+                    &name,
+                    &object,
+                    &rpath_chain,
+                    &library_path,
+                    &runpath,
+                    &header,
+                );
+                for attempt in candidates {
+                    let matched = self.open_matching(&attempt.path, &header);
+                    search_order.push(attempt.clone());
+                    if let Some(file) = matched {
+                        found = Some((attempt.path, file));
+                        break;
+                    }
+                }
+
+                let path = found.map(|(path, file)| {
+                    if loaded.insert(path.clone()) {
+                        queue.push_back(PendingObject {
+                            path: path.clone(),
+                            file,
+                            rpath_chain: rpath_chain.clone(),
+                        });
+                    }
+                    path
+                });
+                dependencies.push(Dependency {
+                    name,
+                    needed_by: object.path.clone(),
+                    path,
+                    search_order,
+                });
+            }
+        }
+
+        Ok(DependencyClosure { dependencies })
+    }
+
+    /// Candidate paths for a needed library, in search order.
+    fn candidates(
+        &self,
+        name: &str,
+        object: &PendingObject,
+        rpath_chain: &[Vec<String>],
+        library_path: &[String],
+        runpath: &Option<String>,
+        header: &types::FileHeader,
+    ) -> Vec<SearchAttempt> {
+        if name.contains('/') {
+            return vec![SearchAttempt {
+                source: SearchSource::Direct,
+                path: Path::new("/").join(&self.cwd).join(name),
+            }];
+        }
+
+        let mut dirs: Vec<(SearchSource, String)> = Vec::new();
+        // DT_RPATH is ignored when the loading object has a DT_RUNPATH
+        if runpath.is_none() {
+            for rpath in rpath_chain {
+                dirs.extend(rpath.iter().map(|dir| (SearchSource::Rpath, dir.clone())));
+            }
+        }
+        dirs.extend(
+            library_path
+                .iter()
+                .map(|dir| (SearchSource::LibraryPath, dir.clone())),
+        );
+        if let Some(runpath) = runpath {
+            dirs.extend(
+                self.expand_search_path(runpath, &object.path, header)
+                    .into_iter()
+                    .map(|dir| (SearchSource::Runpath, dir)),
+            );
+        }
+        let mut cached = None;
+        if let Some(entry) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.lookup(name, header, &self.hwcaps))
+        {
+            cached = Some(SearchAttempt {
+                source: SearchSource::Cache,
+                path: PathBuf::from(&entry.path),
+            });
+        }
+        dirs.extend(
+            self.default_dirs
+                .iter()
+                .map(|dir| (SearchSource::Default, dir.clone())),
+        );
+
+        let default_start = dirs.len() - self.default_dirs.len();
+        let mut attempts: Vec<SearchAttempt> = dirs
+            .into_iter()
+            .map(|(source, dir)| SearchAttempt {
+                source,
+                path: Path::new("/").join(dir).join(name),
+            })
+            .collect();
+        if let Some(cached) = cached {
+            attempts.insert(default_start, cached);
+        }
+
+        let mut seen = HashSet::new();
+        attempts
+            .into_iter()
+            .filter(|attempt| seen.insert(attempt.path.clone()))
+            .collect()
+    }
+
+    /// Split a DT_RPATH/DT_RUNPATH value and expand the dynamic string tokens.
+    fn expand_search_path(
+        &self,
+        value: &str,
+        origin: &Path,
+        header: &types::FileHeader,
+    ) -> Vec<String> {
+        let origin = origin
+            .parent()
+            .unwrap_or_else(|| Path::new("/"))
+            .to_string_lossy()
+            .into_owned();
+        let lib = self.lib.clone().unwrap_or_else(|| default_lib(header));
+        let platform = self
+            .platform
+            .clone()
+            .unwrap_or_else(|| default_platform(header));
+
+        value
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| {
+                dir.replace("${ORIGIN}", &origin)
+                    .replace("$ORIGIN", &origin)
+                    .replace("${LIB}", &lib)
+                    .replace("$LIB", &lib)
+                    .replace("${PLATFORM}", &platform)
+                    .replace("$PLATFORM", &platform)
+            })
+            .collect()
+    }
+
+    /// Open a candidate and check that it is compatible with the root object.
+    fn open_matching(&self, path: &Path, header: &types::FileHeader) -> Option<File> {
+        let file = File::open_path(self.host_path(path)).ok()?;
+        if file.header.class == header.class
+            && file.header.endianness == header.endianness
+            && file.header.cpu_architecture == header.cpu_architecture
+        {
+            Some(file)
+        } else {
+            None
+        }
+    }
+
+    /// Translate a path inside the sysroot to a path on the host, following
+    /// symbolic links without escaping the sysroot.
+    pub fn host_path<T: AsRef<Path>>(&self, path: T) -> PathBuf {
+        let mut resolved = PathBuf::from("/");
+        let mut links = 0;
+        let mut components: VecDeque<PathBuf> = path
+            .as_ref()
+            .components()
+            .map(|component| PathBuf::from(component.as_os_str()))
+            .collect();
+
+        while let Some(component) = components.pop_front() {
+            match component.components().next() {
+                Some(Component::RootDir) | Some(Component::CurDir) | None => continue,
+                Some(Component::ParentDir) => {
+                    resolved.pop();
+                    continue;
+                }
+                _ => {}
+            }
+            let candidate = resolved.join(&component);
+            let host = self.join_sysroot(&candidate);
+            match std::fs::read_link(&host) {
+                Ok(target) if links < MAX_SYMLINKS => {
+                    links += 1;
+                    if target.is_absolute() {
+                        resolved = PathBuf::from("/");
+                    }
+                    for component in target.components().rev() {
+                        components.push_front(PathBuf::from(component.as_os_str()));
+                    }
+                }
+                _ => resolved = candidate,
+            }
+        }
+        self.join_sysroot(&resolved)
+    }
+
+    fn join_sysroot(&self, path: &Path) -> PathBuf {
+        self.sysroot.join(path.strip_prefix("/").unwrap_or(path))
+    }
+}
+
+fn default_lib(header: &types::FileHeader) -> String {
+    match header.class {
+        types::ElfClass::Format32 => "lib".to_string(),
+        types::ElfClass::Format64 => "lib64".to_string(),
+    }
+}
+
+fn default_platform(header: &types::FileHeader) -> String {
+    match header.cpu_architecture {
+        types::ElfCpuArchitecture::EM_X86_64 => "x86_64",
+        types::ElfCpuArchitecture::EM_386 => "i686",
+        types::ElfCpuArchitecture::EM_AARCH64 => "aarch64",
+        types::ElfCpuArchitecture::EM_ARM => "v7l",
+        types::ElfCpuArchitecture::EM_PPC64 => "ppc64",
+        types::ElfCpuArchitecture::EM_S390 => "s390x",
+        _ => "unknown",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DependencyResolver, PendingObject, SearchSource};
+    use crate::File;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_resolve_in_sysroot() {
+        let sysroot = std::env::temp_dir().join(format!("elf-sysroot-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&sysroot);
+        fs::create_dir_all(sysroot.join("usr/bin")).unwrap();
+        fs::create_dir_all(sysroot.join("usr/lib64")).unwrap();
+        fs::create_dir_all(sysroot.join("opt/lib")).unwrap();
+        std::os::unix::fs::symlink("usr/lib64", sysroot.join("lib64")).unwrap();
+        fs::copy("tests/samples/x86_64_plain", sysroot.join("usr/bin/plain")).unwrap();
+        // Stand-in libraries; any object of the right class and machine will do
+        fs::copy(
+            "tests/samples/x86_64_hardened",
+            sysroot.join("usr/lib64/libc.so.6"),
+        )
+        .unwrap();
+        fs::copy(
+            "tests/samples/android_arm_libncurses",
+            sysroot.join("opt/lib/libm.so.6"),
+        )
+        .unwrap();
+
+        let resolver = DependencyResolver::new(&sysroot);
+        let closure = resolver.resolve("/usr/bin/plain").unwrap();
+        let _ = fs::remove_dir_all(&sysroot);
+
+        let libc = closure
+            .dependencies
+            .iter()
+            .find(|dep| dep.name == "libc.so.6")
+            .unwrap();
+        assert_eq!(libc.path.as_deref(), Some(Path::new("/lib64/libc.so.6")));
+
+        // The ARM libm in the RUNPATH does not match the x86_64 executable
+        let missing: Vec<_> = closure.missing().collect();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].name, "libm.so.6");
+        assert_eq!(missing[0].search_order[0].source, SearchSource::Runpath);
+        assert_eq!(
+            missing[0].search_order[0].path,
+            Path::new("/opt/lib/libm.so.6")
+        );
+    }
+
+    #[test]
+    fn test_search_order() {
+        let sysroot = std::env::temp_dir().join(format!("elf-search-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&sysroot);
+        for (dir, lib) in [
+            ("usr/lib", "libroot.so"),
+            ("opt/root", "libtop.so"),
+            ("opt/top", "libmid.so"),
+            ("opt/root", "libleaf.so"),
+        ] {
+            fs::create_dir_all(sysroot.join(dir)).unwrap();
+            fs::copy(
+                Path::new("tests/samples/deps").join(lib),
+                sysroot.join(dir).join(lib),
+            )
+            .unwrap();
+        }
+
+        // libroot has DT_RPATH /opt/root and needs libtop, which has
+        // DT_RUNPATH /opt/top and needs libmid, which needs libleaf
+        let mut resolver = DependencyResolver::new(&sysroot);
+        resolver.library_path = vec!["$ORIGIN/plugins".to_string()];
+        let closure = resolver.resolve("/usr/lib/libroot.so").unwrap();
+        let _ = fs::remove_dir_all(&sysroot);
+        assert_eq!(closure.missing().count(), 0);
+
+        let deps = &closure.dependencies;
+        assert_eq!(deps[0].name, "libtop.so");
+        assert_eq!(deps[0].search_order[0].source, SearchSource::Rpath);
+        // The DT_RUNPATH of libtop disables all DT_RPATH lookups
+        assert_eq!(deps[1].name, "libmid.so");
+        assert_eq!(deps[1].search_order[0].source, SearchSource::LibraryPath);
+        assert_eq!(
+            deps[1].search_order[0].path,
+            Path::new("/usr/lib/plugins/libmid.so")
+        );
+        assert_eq!(deps[1].search_order[1].source, SearchSource::Runpath);
+        assert_eq!(deps[1].search_order.len(), 2);
+        // libmid has no DT_RUNPATH, so the DT_RPATH of libroot applies again
+        assert_eq!(deps[2].name, "libleaf.so");
+        assert_eq!(
+            deps[2].path.as_deref(),
+            Some(Path::new("/opt/root/libleaf.so"))
+        );
+        assert_eq!(deps[2].search_order[0].source, SearchSource::Rpath);
+
+        resolver.cwd = PathBuf::from("/work");
+        let object = PendingObject {
+            path: PathBuf::from("/usr/lib/libroot.so"),
+            file: File::new(),
+            rpath_chain: Vec::new(),
+        };
+        let header = object.file.header;
+        let attempts = resolver.candidates("sub/libx.so", &object, &[], &[], &None, &header);
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].source, SearchSource::Direct);
+        assert_eq!(attempts[0].path, Path::new("/work/sub/libx.so"));
+    }
+}
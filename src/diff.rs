@@ -0,0 +1,557 @@
+use crate::dynamic::Dyn;
+use crate::note::Note;
+use crate::types;
+use crate::{File, ParseError, Section};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A changed scalar field, rendered as strings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    /// Field name, e.g. "entry"
+    pub field: String,
+    /// Old value
+    pub old: String,
+    /// New value
+    pub new: String,
+}
+
+/// A change to one entry of the program header table, matched by index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramHeaderChange {
+    /// Index in the program header table
+    pub index: usize,
+    /// Old program header, None if added
+    pub old: Option<types::ProgramHeader>,
+    /// New program header, None if removed
+    pub new: Option<types::ProgramHeader>,
+}
+
+/// Summary of how the contents of a section differ.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ByteDiff {
+    /// Number of differing bytes within the common length, plus the length difference
+    pub differing_bytes: u64,
+    /// Offset of the first differing byte
+    pub first_difference: u64,
+    /// Offset of the last differing byte within the common length, if any
+    pub last_difference: Option<u64>,
+}
+
+/// A difference in a section, matched by name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SectionChange {
+    /// Section only exists in the new file
+    Added { name: String, size: u64 },
+    /// Section only exists in the old file
+    Removed { name: String, size: u64 },
+    /// Section exists in both files but its header or contents differ
+    Changed {
+        name: String,
+        header: Vec<FieldChange>,
+        data: Option<ByteDiff>,
+    },
+}
+
+/// A difference in a symbol table, matched by table and name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SymbolChange {
+    /// Symbol only exists in the new file
+    Added {
+        table: String,
+        symbol: types::Symbol,
+    },
+    /// Symbol only exists in the old file
+    Removed {
+        table: String,
+        symbol: types::Symbol,
+    },
+    /// Symbol value changed
+    Moved {
+        table: String,
+        name: String,
+        old_value: u64,
+        new_value: u64,
+    },
+    /// Other symbol attributes changed
+    Changed {
+        table: String,
+        name: String,
+        fields: Vec<FieldChange>,
+    },
+}
+
+/// A note that is only present in one of the files.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NoteChange {
+    /// Name of the note section
+    pub section: String,
+    /// Old note, None if added
+    pub old: Option<Note>,
+    /// New note, None if removed
+    pub new: Option<Note>,
+}
+
+/// The differences between two ELF files.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileDiff {
+    pub header: Vec<FieldChange>,
+    pub phdrs: Vec<ProgramHeaderChange>,
+    pub sections: Vec<SectionChange>,
+    pub symbols: Vec<SymbolChange>,
+    /// Dynamic entries only present in the old file
+    pub dynamic_removed: Vec<Dyn>,
+    /// Dynamic entries only present in the new file
+    pub dynamic_added: Vec<Dyn>,
+    pub notes: Vec<NoteChange>,
+}
+
+impl FileDiff {
+    /// Returns true if no differences were found
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty()
+            && self.phdrs.is_empty()
+            && self.sections.is_empty()
+            && self.symbols.is_empty()
+            && self.dynamic_removed.is_empty()
+            && self.dynamic_added.is_empty()
+            && self.notes.is_empty()
+    }
+}
+
+fn compare_field<T: PartialEq + fmt::Display>(
+    changes: &mut Vec<FieldChange>,
+    field: &str,
+    old: T,
+    new: T,
+) {
+    if old != new {
+        changes.push(FieldChange {
+            field: field.to_string(),
+            old: old.to_string(),
+            new: new.to_string(),
+        });
+    }
+}
+
+/// Pair items with the same key, numbering repeated keys so that e.g. the
+/// second `.group` section of one file is matched with the second of the other.
+fn keyed<T, F: Fn(&T) -> String>(items: Vec<T>, key: F) -> Vec<((String, usize), T)> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    items
+        .into_iter()
+        .map(|item| {
+            let name = key(&item);
+            let count = seen.entry(name.clone()).or_insert(0);
+            let occurrence = *count;
+            *count += 1;
+            ((name, occurrence), item)
+        })
+        .collect()
+}
+
+/// Index keyed items by their key.
+fn by_key<T>(items: &[((String, usize), T)]) -> HashMap<&(String, usize), &T> {
+    items.iter().map(|(key, item)| (key, item)).collect()
+}
+
+fn diff_bytes(old: &[u8], new: &[u8]) -> Option<ByteDiff> {
+    let common = old.len().min(new.len());
+    let mut differing = (old.len().max(new.len()) - common) as u64;
+    let mut first = None;
+    let mut last = None;
+    for i in 0..common {
+        if old[i] != new[i] {
+            differing += 1;
+            first.get_or_insert(i as u64);
+            last = Some(i as u64);
+        }
+    }
+    if differing == 0 {
+        return None;
+    }
+    Some(ByteDiff {
+        differing_bytes: differing,
+        first_difference: first.unwrap_or(common as u64),
+        last_difference: last,
+    })
+}
+
+fn diff_section(old: &Section, new: &Section) -> Option<SectionChange> {
+    let (o, n) = (&old.shdr, &new.shdr);
+    let mut header = Vec::new();
+    compare_field(&mut header, "type", o.shtype, n.shtype);
+    compare_field(&mut header, "flags", o.flags, n.flags);
+    compare_field(
+        &mut header,
+        "addr",
+        format!("{:#x}", o.addr),
+        format!("{:#x}", n.addr),
+    );
+    compare_field(
+        &mut header,
+        "offset",
+        format!("{:#x}", o.offset),
+        format!("{:#x}", n.offset),
+    );
+    compare_field(&mut header, "size", o.size, n.size);
+    compare_field(&mut header, "link", o.link, n.link);
+    compare_field(&mut header, "info", o.info, n.info);
+    compare_field(&mut header, "addralign", o.addralign, n.addralign);
+    compare_field(&mut header, "entsize", o.entsize, n.entsize);
+    let data = diff_bytes(&old.data, &new.data);
+    if header.is_empty() && data.is_none() {
+        return None;
+    }
+    Some(SectionChange::Changed {
+        name: o.name.clone(),
+        header,
+        data,
+    })
+}
+
+fn diff_symbols(
+    table: &str,
+    old: Vec<types::Symbol>,
+    new: Vec<types::Symbol>,
+) -> Vec<SymbolChange> {
+    let mut changes = Vec::new();
+    let old = keyed(old, |sym| sym.name.clone());
+    let new = keyed(new, |sym| sym.name.clone());
+    let (old_keys, new_keys) = (by_key(&old), by_key(&new));
+    for (key, old_sym) in old.iter() {
+        match new_keys.get(key) {
+            Some(new_sym) => {
+                if old_sym.value != new_sym.value {
+                    changes.push(SymbolChange::Moved {
+                        table: table.to_string(),
+                        name: old_sym.name.clone(),
+                        old_value: old_sym.value,
+                        new_value: new_sym.value,
+                    });
+                }
+                let mut fields = Vec::new();
+                compare_field(&mut fields, "size", old_sym.size, new_sym.size);
+                compare_field(&mut fields, "type", old_sym.symtype, new_sym.symtype);
+                compare_field(&mut fields, "bind", old_sym.bind, new_sym.bind);
+                compare_field(&mut fields, "vis", old_sym.vis, new_sym.vis);
+                compare_field(&mut fields, "section", old_sym.shndx, new_sym.shndx);
+                if !fields.is_empty() {
+                    changes.push(SymbolChange::Changed {
+                        table: table.to_string(),
+                        name: old_sym.name.clone(),
+                        fields,
+                    });
+                }
+            }
+            None => changes.push(SymbolChange::Removed {
+                table: table.to_string(),
+                symbol: old_sym.clone(),
+            }),
+        }
+    }
+    for (key, new_sym) in new.iter() {
+        if !old_keys.contains_key(key) {
+            changes.push(SymbolChange::Added {
+                table: table.to_string(),
+                symbol: new_sym.clone(),
+            });
+        }
+    }
+    changes
+}
+
+fn symbol_table(file: &File, shtype: types::SectionType) -> Option<&Section> {
+    file.sections
+        .iter()
+        .find(|section| section.shdr.shtype == shtype)
+}
+
+/// Compare two ELF files structurally.
+pub fn diff_files(old: &File, new: &File) -> Result<FileDiff, ParseError> {
+    let mut header = Vec::new();
+    let (o, n) = (&old.header, &new.header);
+    compare_field(
+        &mut header,
+        "class",
+        format!("{:?}", o.class),
+        format!("{:?}", n.class),
+    );
+    compare_field(&mut header, "endianness", o.endianness, n.endianness);
+    compare_field(&mut header, "osabi", o.osabi, n.osabi);
+    compare_field(&mut header, "abiversion", o.abiversion, n.abiversion);
+    compare_field(&mut header, "elftype", o.elftype, n.elftype);
+    compare_field(
+        &mut header,
+        "machine",
+        o.cpu_architecture,
+        n.cpu_architecture,
+    );
+    compare_field(
+        &mut header,
+        "entry",
+        format!("{:#x}", o.entry),
+        format!("{:#x}", n.entry),
+    );
+
+    let phdrs = (0..old.phdrs.len().max(new.phdrs.len()))
+        .filter_map(|index| {
+            let (o, n) = (old.phdrs.get(index), new.phdrs.get(index));
+            if o == n {
+                return None;
+            }
+            Some(ProgramHeaderChange {
+                index,
+                old: o.copied(),
+                new: n.copied(),
+            })
+        })
+        .collect();
+
+    let mut sections = Vec::new();
+    let old_sections = keyed(old.sections.iter().collect(), |s| s.shdr.name.clone());
+    let new_sections = keyed(new.sections.iter().collect(), |s| s.shdr.name.clone());
+    let (old_keys, new_keys) = (by_key(&old_sections), by_key(&new_sections));
+    for (key, section) in old_sections.iter() {
+        match new_keys.get(key) {
+            Some(new_section) => sections.extend(diff_section(section, new_section)),
+            None => sections.push(SectionChange::Removed {
+                name: section.shdr.name.clone(),
+                size: section.shdr.size,
+            }),
+        }
+    }
+    for (key, section) in new_sections.iter() {
+        if !old_keys.contains_key(key) {
+            sections.push(SectionChange::Added {
+                name: section.shdr.name.clone(),
+                size: section.shdr.size,
+            });
+        }
+    }
+
+    let mut symbols = Vec::new();
+    for (table, shtype) in [
+        (".symtab", types::SectionType::Symtab),
+        (".dynsym", types::SectionType::Dynsym),
+    ] {
+        let old_syms = match symbol_table(old, shtype) {
+            Some(section) => old.get_symbols(section)?,
+            None => Vec::new(),
+        };
+        let new_syms = match symbol_table(new, shtype) {
+            Some(section) => new.get_symbols(section)?,
+            None => Vec::new(),
+        };
+        symbols.extend(diff_symbols(table, old_syms, new_syms));
+    }
+
+    let old_dynamic = old.get_dynamic()?;
+    let new_dynamic = new.get_dynamic()?;
+    let dynamic_removed = old_dynamic
+        .iter()
+        .filter(|entry| !new_dynamic.contains(entry))
+        .copied()
+        .collect();
+    let dynamic_added = new_dynamic
+        .iter()
+        .filter(|entry| !old_dynamic.contains(entry))
+        .copied()
+        .collect();
+
+    let mut notes = Vec::new();
+    for section in old.sections.iter().chain(new.sections.iter()) {
+        if section.shdr.shtype != types::SectionType::Note
+            || notes
+                .iter()
+                .any(|note: &NoteChange| note.section == section.shdr.name)
+        {
+            continue;
+        }
+        let name = &section.shdr.name;
+        let old_notes = match old.get_section(name) {
+            Some(section) => old.get_notes(section)?,
+            None => Vec::new(),
+        };
+        let new_notes = match new.get_section(name) {
+            Some(section) => new.get_notes(section)?,
+            None => Vec::new(),
+        };
+        for note in old_notes.iter().filter(|note| !new_notes.contains(note)) {
+            notes.push(NoteChange {
+                section: name.clone(),
+                old: Some(note.clone()),
+                new: None,
+            });
+        }
+        for note in new_notes.iter().filter(|note| !old_notes.contains(note)) {
+            notes.push(NoteChange {
+                section: name.clone(),
+                old: None,
+                new: Some(note.clone()),
+            });
+        }
+    }
+
+    Ok(FileDiff {
+        header,
+        phdrs,
+        sections,
+        symbols,
+        dynamic_removed,
+        dynamic_added,
+        notes,
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl fmt::Display for FileDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.header.is_empty() {
+            writeln!(f, "File header:")?;
+            for change in self.header.iter() {
+                writeln!(f, "  {}: {} -> {}", change.field, change.old, change.new)?;
+            }
+        }
+        if !self.phdrs.is_empty() {
+            writeln!(f, "Program headers:")?;
+            for change in self.phdrs.iter() {
+                if let Some(old) = &change.old {
+                    writeln!(f, "- [{}] {}", change.index, old)?;
+                }
+                if let Some(new) = &change.new {
+                    writeln!(f, "+ [{}] {}", change.index, new)?;
+                }
+            }
+        }
+        if !self.sections.is_empty() {
+            writeln!(f, "Sections:")?;
+            for change in self.sections.iter() {
+                match change {
+                    SectionChange::Added { name, size } => {
+                        writeln!(f, "+ {} ({} bytes)", name, size)?
+                    }
+                    SectionChange::Removed { name, size } => {
+                        writeln!(f, "- {} ({} bytes)", name, size)?
+                    }
+                    SectionChange::Changed { name, header, data } => {
+                        writeln!(f, "~ {}", name)?;
+                        for field in header.iter() {
+                            writeln!(f, "    {}: {} -> {}", field.field, field.old, field.new)?;
+                        }
+                        if let Some(data) = data {
+                            writeln!(
+                                f,
+                                "    contents: {} bytes differ, first at {:#x}",
+                                data.differing_bytes, data.first_difference
+                            )?;
+                        }
+                    }
+                }
+            }
+        }
+        if !self.symbols.is_empty() {
+            writeln!(f, "Symbols:")?;
+            for change in self.symbols.iter() {
+                match change {
+                    SymbolChange::Added { table, symbol } => {
+                        writeln!(f, "+ {} {} {:#x}", table, symbol.name, symbol.value)?
+                    }
+                    SymbolChange::Removed { table, symbol } => {
+                        writeln!(f, "- {} {} {:#x}", table, symbol.name, symbol.value)?
+                    }
+                    SymbolChange::Moved {
+                        table,
+                        name,
+                        old_value,
+                        new_value,
+                    } => writeln!(
+                        f,
+                        "~ {} {} {:#x} -> {:#x}",
+                        table, name, old_value, new_value
+                    )?,
+                    SymbolChange::Changed {
+                        table,
+                        name,
+                        fields,
+                    } => {
+                        writeln!(f, "~ {} {}", table, name)?;
+                        for field in fields.iter() {
+                            writeln!(f, "    {}: {} -> {}", field.field, field.old, field.new)?;
+                        }
+                    }
+                }
+            }
+        }
+        if !self.dynamic_removed.is_empty() || !self.dynamic_added.is_empty() {
+            writeln!(f, "Dynamic section:")?;
+            for entry in self.dynamic_removed.iter() {
+                writeln!(f, "- {} {:#x}", entry.d_tag, entry.d_val)?;
+            }
+            for entry in self.dynamic_added.iter() {
+                writeln!(f, "+ {} {:#x}", entry.d_tag, entry.d_val)?;
+            }
+        }
+        if !self.notes.is_empty() {
+            writeln!(f, "Notes:")?;
+            for change in self.notes.iter() {
+                if let Some(note) = &change.old {
+                    writeln!(
+                        f,
+                        "- {} {} {:#x} {}",
+                        change.section,
+                        note.name,
+                        note.ntype,
+                        hex(&note.desc)
+                    )?;
+                }
+                if let Some(note) = &change.new {
+                    writeln!(
+                        f,
+                        "+ {} {} {:#x} {}",
+                        change.section,
+                        note.name,
+                        note.ntype,
+                        hex(&note.desc)
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_files, SectionChange, SymbolChange};
+    use crate::File;
+
+    #[test]
+    fn test_diff_files() {
+        let old = File::open_path("tests/samples/libabi_v1.so").unwrap();
+        let new = File::open_path("tests/samples/libabi_v2.so").unwrap();
+        assert!(diff_files(&old, &old).unwrap().is_empty());
+
+        let diff = diff_files(&old, &new).unwrap();
+        assert!(diff.sections.iter().any(|change| matches!(
+            change,
+            SectionChange::Changed { name, data: Some(_), .. } if name == ".text"
+        )));
+        assert!(diff.symbols.iter().any(|change| matches!(
+            change,
+            SymbolChange::Added { table, symbol } if table == ".dynsym" && symbol.name == "abi_new"
+        )));
+        assert!(diff.symbols.iter().any(|change| matches!(
+            change,
+            SymbolChange::Removed { table, symbol } if table == ".symtab" && symbol.name == "abi_old"
+        )));
+        assert!(!diff.notes.is_empty(), "build IDs differ");
+
+        let rendered = diff.to_string();
+        assert!(rendered.contains("+ .dynsym abi_new"));
+        assert!(rendered.contains("Dynamic section:"));
+    }
+}
@@ -0,0 +1,509 @@
+use crate::types;
+use crate::ParseError;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+/// Offset size of a DWARF unit, selected by its initial length field.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DwarfFormat {
+    /// 32-bit DWARF, with 4 byte section offsets
+    Dwarf32,
+    /// 64-bit DWARF, with 8 byte section offsets
+    Dwarf64,
+}
+
+/// Target address. Named DW_FORM_addr in C code.
+pub const DW_FORM_ADDR: u64 = 0x01;
+/// Block with a 2 byte length. Named DW_FORM_block2 in C code.
+pub const DW_FORM_BLOCK2: u64 = 0x03;
+/// Block with a 4 byte length. Named DW_FORM_block4 in C code.
+pub const DW_FORM_BLOCK4: u64 = 0x04;
+/// 2 byte constant. Named DW_FORM_data2 in C code.
+pub const DW_FORM_DATA2: u64 = 0x05;
+/// 4 byte constant. Named DW_FORM_data4 in C code.
+pub const DW_FORM_DATA4: u64 = 0x06;
+/// 8 byte constant. Named DW_FORM_data8 in C code.
+pub const DW_FORM_DATA8: u64 = 0x07;
+/// Null-terminated string. Named DW_FORM_string in C code.
+pub const DW_FORM_STRING: u64 = 0x08;
+/// Block with a LEB128 length. Named DW_FORM_block in C code.
+pub const DW_FORM_BLOCK: u64 = 0x09;
+/// Block with a 1 byte length. Named DW_FORM_block1 in C code.
+pub const DW_FORM_BLOCK1: u64 = 0x0a;
+/// 1 byte constant. Named DW_FORM_data1 in C code.
+pub const DW_FORM_DATA1: u64 = 0x0b;
+/// 1 byte flag. Named DW_FORM_flag in C code.
+pub const DW_FORM_FLAG: u64 = 0x0c;
+/// Signed LEB128 constant. Named DW_FORM_sdata in C code.
+pub const DW_FORM_SDATA: u64 = 0x0d;
+/// Offset into .debug_str. Named DW_FORM_strp in C code.
+pub const DW_FORM_STRP: u64 = 0x0e;
+/// Unsigned LEB128 constant. Named DW_FORM_udata in C code.
+pub const DW_FORM_UDATA: u64 = 0x0f;
+/// Offset into .debug_info. Named DW_FORM_ref_addr in C code.
+pub const DW_FORM_REF_ADDR: u64 = 0x10;
+/// 1 byte unit relative reference. Named DW_FORM_ref1 in C code.
+pub const DW_FORM_REF1: u64 = 0x11;
+/// 2 byte unit relative reference. Named DW_FORM_ref2 in C code.
+pub const DW_FORM_REF2: u64 = 0x12;
+/// 4 byte unit relative reference. Named DW_FORM_ref4 in C code.
+pub const DW_FORM_REF4: u64 = 0x13;
+/// 8 byte unit relative reference. Named DW_FORM_ref8 in C code.
+pub const DW_FORM_REF8: u64 = 0x14;
+/// LEB128 unit relative reference. Named DW_FORM_ref_udata in C code.
+pub const DW_FORM_REF_UDATA: u64 = 0x15;
+/// Form given in the data. Named DW_FORM_indirect in C code.
+pub const DW_FORM_INDIRECT: u64 = 0x16;
+/// Offset into another debug section. Named DW_FORM_sec_offset in C code.
+pub const DW_FORM_SEC_OFFSET: u64 = 0x17;
+/// DWARF expression. Named DW_FORM_exprloc in C code.
+pub const DW_FORM_EXPRLOC: u64 = 0x18;
+/// Flag that is set by its presence. Named DW_FORM_flag_present in C code.
+pub const DW_FORM_FLAG_PRESENT: u64 = 0x19;
+/// Index into .debug_str_offsets. Named DW_FORM_strx in C code.
+pub const DW_FORM_STRX: u64 = 0x1a;
+/// Index into .debug_addr. Named DW_FORM_addrx in C code.
+pub const DW_FORM_ADDRX: u64 = 0x1b;
+/// 4 byte reference into the supplementary file. Named DW_FORM_ref_sup4 in C code.
+pub const DW_FORM_REF_SUP4: u64 = 0x1c;
+/// Offset into .debug_str of the supplementary file. Named DW_FORM_strp_sup in C code.
+pub const DW_FORM_STRP_SUP: u64 = 0x1d;
+/// 16 byte constant. Named DW_FORM_data16 in C code.
+pub const DW_FORM_DATA16: u64 = 0x1e;
+/// Offset into .debug_line_str. Named DW_FORM_line_strp in C code.
+pub const DW_FORM_LINE_STRP: u64 = 0x1f;
+/// Type unit signature. Named DW_FORM_ref_sig8 in C code.
+pub const DW_FORM_REF_SIG8: u64 = 0x20;
+/// Constant stored in the abbreviation. Named DW_FORM_implicit_const in C code.
+pub const DW_FORM_IMPLICIT_CONST: u64 = 0x21;
+/// Index into .debug_loclists. Named DW_FORM_loclistx in C code.
+pub const DW_FORM_LOCLISTX: u64 = 0x22;
+/// Index into .debug_rnglists. Named DW_FORM_rnglistx in C code.
+pub const DW_FORM_RNGLISTX: u64 = 0x23;
+/// 8 byte reference into the supplementary file. Named DW_FORM_ref_sup8 in C code.
+pub const DW_FORM_REF_SUP8: u64 = 0x24;
+/// 1 byte index into .debug_str_offsets. Named DW_FORM_strx1 in C code.
+pub const DW_FORM_STRX1: u64 = 0x25;
+/// 2 byte index into .debug_str_offsets. Named DW_FORM_strx2 in C code.
+pub const DW_FORM_STRX2: u64 = 0x26;
+/// 3 byte index into .debug_str_offsets. Named DW_FORM_strx3 in C code.
+pub const DW_FORM_STRX3: u64 = 0x27;
+/// 4 byte index into .debug_str_offsets. Named DW_FORM_strx4 in C code.
+pub const DW_FORM_STRX4: u64 = 0x28;
+/// 1 byte index into .debug_addr. Named DW_FORM_addrx1 in C code.
+pub const DW_FORM_ADDRX1: u64 = 0x29;
+/// 2 byte index into .debug_addr. Named DW_FORM_addrx2 in C code.
+pub const DW_FORM_ADDRX2: u64 = 0x2a;
+/// 3 byte index into .debug_addr. Named DW_FORM_addrx3 in C code.
+pub const DW_FORM_ADDRX3: u64 = 0x2b;
+/// 4 byte index into .debug_addr. Named DW_FORM_addrx4 in C code.
+pub const DW_FORM_ADDRX4: u64 = 0x2c;
+/// GNU extension, index into .debug_addr. Named DW_FORM_GNU_addr_index in C code.
+pub const DW_FORM_GNU_ADDR_INDEX: u64 = 0x1f01;
+/// GNU extension, index into .debug_str_offsets. Named DW_FORM_GNU_str_index in C code.
+pub const DW_FORM_GNU_STR_INDEX: u64 = 0x1f02;
+/// GNU extension, reference into the alternate file. Named DW_FORM_GNU_ref_alt in C code.
+pub const DW_FORM_GNU_REF_ALT: u64 = 0x1f20;
+/// GNU extension, offset into .debug_str of the alternate file. Named DW_FORM_GNU_strp_alt in C code.
+pub const DW_FORM_GNU_STRP_ALT: u64 = 0x1f21;
+
+/// ISO C89. Named DW_LANG_C89 in C code.
+pub const DW_LANG_C89: u64 = 0x01;
+/// Non-standard C. Named DW_LANG_C in C code.
+pub const DW_LANG_C: u64 = 0x02;
+/// ISO C++98. Named DW_LANG_C_plus_plus in C code.
+pub const DW_LANG_C_PLUS_PLUS: u64 = 0x04;
+/// ISO C99. Named DW_LANG_C99 in C code.
+pub const DW_LANG_C99: u64 = 0x0c;
+/// ISO C++03. Named DW_LANG_C_plus_plus_03 in C code.
+pub const DW_LANG_C_PLUS_PLUS_03: u64 = 0x19;
+/// ISO C++11. Named DW_LANG_C_plus_plus_11 in C code.
+pub const DW_LANG_C_PLUS_PLUS_11: u64 = 0x1a;
+/// Rust. Named DW_LANG_Rust in C code.
+pub const DW_LANG_RUST: u64 = 0x1c;
+/// ISO C11. Named DW_LANG_C11 in C code.
+pub const DW_LANG_C11: u64 = 0x1d;
+/// ISO C++14. Named DW_LANG_C_plus_plus_14 in C code.
+pub const DW_LANG_C_PLUS_PLUS_14: u64 = 0x21;
+/// MIPS assembler, also used by GNU as. Named DW_LANG_Mips_Assembler in C code.
+pub const DW_LANG_MIPS_ASSEMBLER: u64 = 0x8001;
+
+/// The value of an attribute, as encoded by its form.
+///
+/// Values that refer to other sections, such as string offsets and
+/// indices, are returned unresolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttributeValue {
+    /// Target address
+    Address(u64),
+    /// Index into .debug_addr
+    AddressIndex(u64),
+    /// Block of uninterpreted data
+    Block(Vec<u8>),
+    /// Unsigned constant
+    Data(u64),
+    /// Signed constant
+    Sdata(i64),
+    /// 16 byte constant
+    Data16([u8; 16]),
+    /// Flag
+    Flag(bool),
+    /// String stored inline
+    String(String),
+    /// Offset into .debug_str
+    StrOffset(u64),
+    /// Offset into .debug_line_str
+    LineStrOffset(u64),
+    /// Index into .debug_str_offsets
+    StrIndex(u64),
+    /// Offset into .debug_str of the supplementary or alternate file
+    SupStrOffset(u64),
+    /// Reference relative to the start of the unit
+    UnitRef(u64),
+    /// Reference relative to the start of .debug_info
+    DebugInfoRef(u64),
+    /// Reference into .debug_info of the supplementary or alternate file
+    SupRef(u64),
+    /// Signature of a type unit
+    TypeSignature(u64),
+    /// Offset into another debug section
+    SecOffset(u64),
+    /// DWARF expression
+    Exprloc(Vec<u8>),
+    /// Index into .debug_loclists
+    LocListIndex(u64),
+    /// Index into .debug_rnglists
+    RangeListIndex(u64),
+}
+
+impl AttributeValue {
+    /// Get the value of a constant class attribute.
+    pub fn udata(&self) -> Option<u64> {
+        match *self {
+            AttributeValue::Data(value) => Some(value),
+            AttributeValue::Sdata(value) if value >= 0 => Some(value as u64),
+            _ => None,
+        }
+    }
+}
+
+/// Parameters of a unit that affect how its attributes are encoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Encoding {
+    pub format: DwarfFormat,
+    pub version: u16,
+    pub address_size: u8,
+}
+
+/// A cursor over DWARF section data.
+///
+/// Reads past the end of the data fail with an `UnexpectedEof` I/O error.
+#[derive(Clone)]
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pub offset: usize,
+    endianness: types::ElfEndianness,
+}
+
+fn eof() -> ParseError {
+    ParseError::IoError(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8], endianness: types::ElfEndianness) -> Reader<'a> {
+        Reader {
+            data,
+            offset: 0,
+            endianness,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], ParseError> {
+        let end = self.offset.checked_add(len).ok_or_else(eof)?;
+        let bytes = self.data.get(self.offset..end).ok_or_else(eof)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    /// Number of bytes left to read.
+    pub fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.offset)
+    }
+
+    /// Read all remaining bytes.
+    pub fn rest(&mut self) -> &'a [u8] {
+        let start = self.offset.min(self.data.len());
+        self.offset = self.data.len();
+        &self.data[start..]
+    }
+
+    /// Split off a reader over the next `len` bytes, with offsets relative to this reader.
+    pub fn sub(&mut self, len: usize) -> Result<Reader<'a>, ParseError> {
+        let start = self.offset;
+        self.bytes(len)?;
+        Ok(Reader {
+            data: &self.data[..start + len],
+            offset: start,
+            endianness: self.endianness,
+        })
+    }
+
+    pub fn u8(&mut self) -> Result<u8, ParseError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, ParseError> {
+        let bytes = self.bytes(2)?;
+        Ok(match self.endianness {
+            types::ElfEndianness::Lsb => LittleEndian::read_u16(bytes),
+            types::ElfEndianness::Msb => BigEndian::read_u16(bytes),
+        })
+    }
+
+    pub fn u32(&mut self) -> Result<u32, ParseError> {
+        let bytes = self.bytes(4)?;
+        Ok(match self.endianness {
+            types::ElfEndianness::Lsb => LittleEndian::read_u32(bytes),
+            types::ElfEndianness::Msb => BigEndian::read_u32(bytes),
+        })
+    }
+
+    pub fn u64(&mut self) -> Result<u64, ParseError> {
+        let bytes = self.bytes(8)?;
+        Ok(match self.endianness {
+            types::ElfEndianness::Lsb => LittleEndian::read_u64(bytes),
+            types::ElfEndianness::Msb => BigEndian::read_u64(bytes),
+        })
+    }
+
+    /// Read an unsigned value of `size` bytes, such as an address.
+    pub fn sized(&mut self, size: u8) -> Result<u64, ParseError> {
+        match size {
+            1 => self.u8().map(u64::from),
+            2 => self.u16().map(u64::from),
+            3 => {
+                let bytes = self.bytes(3)?;
+                Ok(match self.endianness {
+                    types::ElfEndianness::Lsb => LittleEndian::read_uint(bytes, 3),
+                    types::ElfEndianness::Msb => BigEndian::read_uint(bytes, 3),
+                })
+            }
+            4 => self.u32().map(u64::from),
+            8 => self.u64(),
+            _ => Err(ParseError::InvalidFormat(Some(format!(
+                "unsupported DWARF value size {}",
+                size
+            )))),
+        }
+    }
+
+    pub fn uleb128(&mut self) -> Result<u64, ParseError> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                value |= u64::from(byte & 0x7f) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    pub fn sleb128(&mut self) -> Result<i64, ParseError> {
+        let mut value = 0i64;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                value |= i64::from(byte & 0x7f) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Read a null-terminated string.
+    pub fn cstr(&mut self) -> Result<&'a [u8], ParseError> {
+        let rest = &self.data[self.offset.min(self.data.len())..];
+        let len = rest.iter().position(|&b| b == 0).ok_or_else(eof)?;
+        self.offset += len + 1;
+        Ok(&rest[..len])
+    }
+
+    /// Read an initial length field, returning the unit length and format.
+    pub fn initial_length(&mut self) -> Result<(u64, DwarfFormat), ParseError> {
+        match self.u32()? {
+            0xffff_ffff => Ok((self.u64()?, DwarfFormat::Dwarf64)),
+            len if len >= 0xffff_fff0 => Err(ParseError::InvalidFormat(Some(format!(
+                "reserved DWARF initial length {:#x}",
+                len
+            )))),
+            len => Ok((u64::from(len), DwarfFormat::Dwarf32)),
+        }
+    }
+
+    /// Read a section offset of the size given by the format.
+    pub fn offset(&mut self, format: DwarfFormat) -> Result<u64, ParseError> {
+        match format {
+            DwarfFormat::Dwarf32 => self.u32().map(u64::from),
+            DwarfFormat::Dwarf64 => self.u64(),
+        }
+    }
+
+    /// Read an attribute value of the given form.
+    pub fn attribute(
+        &mut self,
+        form: u64,
+        implicit_const: i64,
+        encoding: Encoding,
+    ) -> Result<AttributeValue, ParseError> {
+        // The actual form of an indirect attribute precedes its value
+        let form = if form == DW_FORM_INDIRECT {
+            let form = self.uleb128()?;
+            if form == DW_FORM_INDIRECT {
+                return Err(ParseError::InvalidFormat(Some(
+                    "nested DW_FORM_indirect".to_string(),
+                )));
+            }
+            form
+        } else {
+            form
+        };
+        Ok(match form {
+            DW_FORM_ADDR => AttributeValue::Address(self.sized(encoding.address_size)?),
+            DW_FORM_BLOCK1 => {
+                let len = self.u8()? as usize;
+                AttributeValue::Block(self.bytes(len)?.to_vec())
+            }
+            DW_FORM_BLOCK2 => {
+                let len = self.u16()? as usize;
+                AttributeValue::Block(self.bytes(len)?.to_vec())
+            }
+            DW_FORM_BLOCK4 => {
+                let len = self.u32()? as usize;
+                AttributeValue::Block(self.bytes(len)?.to_vec())
+            }
+            DW_FORM_BLOCK => {
+                let len = self.uleb128()? as usize;
+                AttributeValue::Block(self.bytes(len)?.to_vec())
+            }
+            DW_FORM_DATA1 => AttributeValue::Data(self.sized(1)?),
+            DW_FORM_DATA2 => AttributeValue::Data(self.sized(2)?),
+            DW_FORM_DATA4 => AttributeValue::Data(self.sized(4)?),
+            DW_FORM_DATA8 => AttributeValue::Data(self.sized(8)?),
+            DW_FORM_DATA16 => {
+                let mut data = [0u8; 16];
+                data.copy_from_slice(self.bytes(16)?);
+                AttributeValue::Data16(data)
+            }
+            DW_FORM_UDATA => AttributeValue::Data(self.uleb128()?),
+            DW_FORM_SDATA => AttributeValue::Sdata(self.sleb128()?),
+            DW_FORM_IMPLICIT_CONST => AttributeValue::Sdata(implicit_const),
+            DW_FORM_FLAG => AttributeValue::Flag(self.u8()? != 0),
+            DW_FORM_FLAG_PRESENT => AttributeValue::Flag(true),
+            DW_FORM_STRING => {
+                AttributeValue::String(String::from_utf8_lossy(self.cstr()?).into_owned())
+            }
+            DW_FORM_STRP => AttributeValue::StrOffset(self.offset(encoding.format)?),
+            DW_FORM_LINE_STRP => AttributeValue::LineStrOffset(self.offset(encoding.format)?),
+            DW_FORM_STRP_SUP | DW_FORM_GNU_STRP_ALT => {
+                AttributeValue::SupStrOffset(self.offset(encoding.format)?)
+            }
+            DW_FORM_STRX | DW_FORM_GNU_STR_INDEX => AttributeValue::StrIndex(self.uleb128()?),
+            DW_FORM_STRX1 => AttributeValue::StrIndex(self.sized(1)?),
+            DW_FORM_STRX2 => AttributeValue::StrIndex(self.sized(2)?),
+            DW_FORM_STRX3 => AttributeValue::StrIndex(self.sized(3)?),
+            DW_FORM_STRX4 => AttributeValue::StrIndex(self.sized(4)?),
+            DW_FORM_ADDRX | DW_FORM_GNU_ADDR_INDEX => AttributeValue::AddressIndex(self.uleb128()?),
+            DW_FORM_ADDRX1 => AttributeValue::AddressIndex(self.sized(1)?),
+            DW_FORM_ADDRX2 => AttributeValue::AddressIndex(self.sized(2)?),
+            DW_FORM_ADDRX3 => AttributeValue::AddressIndex(self.sized(3)?),
+            DW_FORM_ADDRX4 => AttributeValue::AddressIndex(self.sized(4)?),
+            DW_FORM_REF1 => AttributeValue::UnitRef(self.sized(1)?),
+            DW_FORM_REF2 => AttributeValue::UnitRef(self.sized(2)?),
+            DW_FORM_REF4 => AttributeValue::UnitRef(self.sized(4)?),
+            DW_FORM_REF8 => AttributeValue::UnitRef(self.sized(8)?),
+            DW_FORM_REF_UDATA => AttributeValue::UnitRef(self.uleb128()?),
+            DW_FORM_REF_ADDR => {
+                // DWARF 2 encoded these with the size of an address
+                if encoding.version <= 2 {
+                    AttributeValue::DebugInfoRef(self.sized(encoding.address_size)?)
+                } else {
+                    AttributeValue::DebugInfoRef(self.offset(encoding.format)?)
+                }
+            }
+            DW_FORM_REF_SUP4 => AttributeValue::SupRef(self.sized(4)?),
+            DW_FORM_REF_SUP8 => AttributeValue::SupRef(self.sized(8)?),
+            DW_FORM_GNU_REF_ALT => AttributeValue::SupRef(self.offset(encoding.format)?),
+            DW_FORM_REF_SIG8 => AttributeValue::TypeSignature(self.u64()?),
+            DW_FORM_SEC_OFFSET => AttributeValue::SecOffset(self.offset(encoding.format)?),
+            DW_FORM_EXPRLOC => {
+                let len = self.uleb128()? as usize;
+                AttributeValue::Exprloc(self.bytes(len)?.to_vec())
+            }
+            DW_FORM_LOCLISTX => AttributeValue::LocListIndex(self.uleb128()?),
+            DW_FORM_RNGLISTX => AttributeValue::RangeListIndex(self.uleb128()?),
+            _ => {
+                return Err(ParseError::InvalidFormat(Some(format!(
+                    "unknown DWARF form {:#x}",
+                    form
+                ))))
+            }
+        })
+    }
+}
+
+/// Get the null-terminated string at `offset` of a string section such as `.debug_str`.
+pub(crate) fn string_at(data: &[u8], offset: u64) -> Result<String, ParseError> {
+    let rest = data.get(offset as usize..).ok_or_else(|| {
+        ParseError::InvalidFormat(Some(format!("string offset {:#x} out of range", offset)))
+    })?;
+    let len = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+    Ok(String::from_utf8_lossy(&rest[..len]).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leb128() {
+        let data = [0xe5, 0x8e, 0x26, 0x7f, 0x80, 0x7f, 0x02];
+        let mut reader = Reader::new(&data, types::ElfEndianness::Lsb);
+        assert_eq!(reader.uleb128().unwrap(), 624_485);
+        assert_eq!(reader.sleb128().unwrap(), -1);
+        assert_eq!(reader.sleb128().unwrap(), -128);
+        assert_eq!(reader.sleb128().unwrap(), 2);
+        assert!(reader.is_empty());
+        assert!(reader.u8().is_err());
+    }
+
+    #[test]
+    fn test_indirect_form() {
+        let encoding = Encoding {
+            format: DwarfFormat::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let data = [DW_FORM_DATA1 as u8, 0x2a];
+        let mut reader = Reader::new(&data, types::ElfEndianness::Lsb);
+        assert_eq!(
+            reader.attribute(DW_FORM_INDIRECT, 0, encoding).unwrap(),
+            AttributeValue::Data(0x2a)
+        );
+        let data = [DW_FORM_INDIRECT as u8; 4096];
+        let mut reader = Reader::new(&data, types::ElfEndianness::Lsb);
+        assert!(reader.attribute(DW_FORM_INDIRECT, 0, encoding).is_err());
+    }
+}
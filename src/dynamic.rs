@@ -0,0 +1,179 @@
+use crate::types;
+use crate::{utils, File, ParseError};
+use std::borrow::Cow;
+use std::io;
+
+/// An entry in the dynamic section.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Dyn {
+    /// Entry type
+    pub d_tag: types::DynTag,
+    /// Integer value or address, interpreted according to the tag
+    pub d_val: u64,
+}
+
+impl std::fmt::Display for Dyn {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Dynamic: Tag: {} Value: {:#x}", self.d_tag, self.d_val)
+    }
+}
+
+impl File {
+    /// Parse the entries of the dynamic section, up to the terminating DT_NULL.
+    ///
+    /// The entries are read from the SHT_DYNAMIC section, or from the
+    /// PT_DYNAMIC segment if the section headers have been stripped.
+    pub fn get_dynamic(&self) -> Result<Vec<Dyn>, ParseError> {
+        let data = match self
+            .sections
+            .iter()
+            .find(|section| section.shdr.shtype == types::SectionType::Dynamic)
+        {
+            Some(section) => section.data.clone(),
+            None => match self
+                .phdrs
+                .iter()
+                .find(|phdr| phdr.progtype == types::PT_DYNAMIC)
+            {
+                Some(phdr) => self.get_segment_data(phdr),
+                None => return Ok(Vec::new()),
+            },
+        };
+
+        let entsize = match self.header.class {
+            types::ElfClass::Format32 => 8,
+            types::ElfClass::Format64 => 16,
+        };
+        let mut entries = Vec::new();
+        let mut io_section = io::Cursor::new(&data);
+        while io_section.position() as usize + entsize <= data.len() {
+            let (d_tag, d_val) = match self.header.class {
+                types::ElfClass::Format32 => (
+                    read_u32!(self, io_section)? as u64,
+                    read_u32!(self, io_section)? as u64,
+                ),
+                types::ElfClass::Format64 => {
+                    (read_u64!(self, io_section)?, read_u64!(self, io_section)?)
+                }
+            };
+            let entry = Dyn {
+                d_tag: types::DynTag(d_tag),
+                d_val,
+            };
+            if entry.d_tag == types::DT_NULL {
+                break;
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Get the dynamic string table.
+    ///
+    /// This is the section linked from the dynamic section, or `.dynstr`. If
+    /// the section headers have been stripped, the table is read from the
+    /// loadable segments at DT_STRTAB, with DT_STRSZ bytes.
+    pub(crate) fn get_dynamic_strtab(&self) -> Result<Cow<'_, [u8]>, ParseError> {
+        if let Some(strtab) = self
+            .sections
+            .iter()
+            .find(|section| section.shdr.shtype == types::SectionType::Dynamic)
+            .and_then(|section| self.sections.get(section.shdr.link as usize))
+            .or_else(|| self.get_section(".dynstr"))
+        {
+            return Ok(Cow::Borrowed(&strtab.data));
+        }
+        let dynamic = self.get_dynamic()?;
+        let value = |tag| {
+            dynamic
+                .iter()
+                .find(|entry| entry.d_tag == tag)
+                .map(|entry| entry.d_val)
+        };
+        match (value(types::DT_STRTAB), value(types::DT_STRSZ)) {
+            (Some(address), Some(size)) => Ok(Cow::Owned(self.read_virtual(address, size)?)),
+            _ => Err(ParseError::InvalidFormat(Some(
+                "No dynamic string table".to_string(),
+            ))),
+        }
+    }
+
+    /// Look up a string in the dynamic string table by offset.
+    pub fn get_dynamic_string(&self, offset: u64) -> Result<String, ParseError> {
+        let strtab = self.get_dynamic_strtab()?;
+        Ok(utils::get_string(&strtab, offset as usize)?)
+    }
+
+    fn get_dynamic_strings(&self, tag: types::DynTag) -> Result<Vec<String>, ParseError> {
+        let entries: Vec<Dyn> = self
+            .get_dynamic()?
+            .into_iter()
+            .filter(|entry| entry.d_tag == tag)
+            .collect();
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let strtab = self.get_dynamic_strtab()?;
+        entries
+            .iter()
+            .map(|entry| Ok(utils::get_string(&strtab, entry.d_val as usize)?))
+            .collect()
+    }
+
+    /// Get the names of the libraries listed in DT_NEEDED entries, in order.
+    pub fn get_needed(&self) -> Result<Vec<String>, ParseError> {
+        self.get_dynamic_strings(types::DT_NEEDED)
+    }
+
+    /// Get the DT_SONAME of a shared object.
+    pub fn get_soname(&self) -> Result<Option<String>, ParseError> {
+        Ok(self
+            .get_dynamic_strings(types::DT_SONAME)?
+            .into_iter()
+            .next())
+    }
+
+    /// Get the DT_RPATH search path, if present.
+    pub fn get_rpath(&self) -> Result<Option<String>, ParseError> {
+        Ok(self
+            .get_dynamic_strings(types::DT_RPATH)?
+            .into_iter()
+            .next())
+    }
+
+    /// Get the DT_RUNPATH search path, if present.
+    pub fn get_runpath(&self) -> Result<Option<String>, ParseError> {
+        Ok(self
+            .get_dynamic_strings(types::DT_RUNPATH)?
+            .into_iter()
+            .next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types;
+    use crate::File;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_dynamic() {
+        let file = File::open_path(PathBuf::from("tests/samples/x86_64_hardened")).unwrap();
+        let dynamic = file.get_dynamic().unwrap();
+        assert_eq!(dynamic.len(), 27);
+        assert!(dynamic
+            .iter()
+            .any(|entry| entry.d_tag == types::DT_FLAGS && entry.d_val == types::DF_BIND_NOW));
+        assert_eq!(file.get_needed().unwrap(), vec!["libm.so.6", "libc.so.6"]);
+        assert_eq!(file.get_runpath().unwrap(), None);
+    }
+
+    #[test]
+    fn test_dynamic_strings_without_sections() {
+        // The strings are found through DT_STRTAB once the sections are gone
+        let mut file = File::open_path(PathBuf::from("tests/samples/x86_64_hardened")).unwrap();
+        file.sections.clear();
+        assert_eq!(file.get_dynamic().unwrap().len(), 27);
+        assert_eq!(file.get_needed().unwrap(), vec!["libm.so.6", "libc.so.6"]);
+    }
+}
@@ -0,0 +1,752 @@
+use crate::debug_info::Range;
+use crate::dwarf::{DwarfFormat, Reader};
+use crate::types;
+use crate::{File, ParseError};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Pointer encoding omitted. Named DW_EH_PE_omit in C code.
+pub const DW_EH_PE_OMIT: u8 = 0xff;
+/// Absolute pointer of the address size. Named DW_EH_PE_absptr in C code.
+pub const DW_EH_PE_ABSPTR: u8 = 0x00;
+/// Unsigned LEB128. Named DW_EH_PE_uleb128 in C code.
+pub const DW_EH_PE_ULEB128: u8 = 0x01;
+/// Unsigned 2 byte value. Named DW_EH_PE_udata2 in C code.
+pub const DW_EH_PE_UDATA2: u8 = 0x02;
+/// Unsigned 4 byte value. Named DW_EH_PE_udata4 in C code.
+pub const DW_EH_PE_UDATA4: u8 = 0x03;
+/// Unsigned 8 byte value. Named DW_EH_PE_udata8 in C code.
+pub const DW_EH_PE_UDATA8: u8 = 0x04;
+/// Signed LEB128. Named DW_EH_PE_sleb128 in C code.
+pub const DW_EH_PE_SLEB128: u8 = 0x09;
+/// Signed 2 byte value. Named DW_EH_PE_sdata2 in C code.
+pub const DW_EH_PE_SDATA2: u8 = 0x0a;
+/// Signed 4 byte value. Named DW_EH_PE_sdata4 in C code.
+pub const DW_EH_PE_SDATA4: u8 = 0x0b;
+/// Signed 8 byte value. Named DW_EH_PE_sdata8 in C code.
+pub const DW_EH_PE_SDATA8: u8 = 0x0c;
+/// Relative to the address of the pointer. Named DW_EH_PE_pcrel in C code.
+pub const DW_EH_PE_PCREL: u8 = 0x10;
+/// Relative to the start of the text. Named DW_EH_PE_textrel in C code.
+pub const DW_EH_PE_TEXTREL: u8 = 0x20;
+/// Relative to the start of the data. Named DW_EH_PE_datarel in C code.
+pub const DW_EH_PE_DATAREL: u8 = 0x30;
+/// Relative to the start of the function. Named DW_EH_PE_funcrel in C code.
+pub const DW_EH_PE_FUNCREL: u8 = 0x40;
+/// Aligned to the address size. Named DW_EH_PE_aligned in C code.
+pub const DW_EH_PE_ALIGNED: u8 = 0x50;
+/// Address of the actual pointer. Named DW_EH_PE_indirect in C code.
+pub const DW_EH_PE_INDIRECT: u8 = 0x80;
+
+/// The section call frame information was read from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameSection {
+    /// `.eh_frame`, used for exception handling and unwinding at run time
+    EhFrame,
+    /// `.debug_frame`, used by debuggers
+    DebugFrame,
+}
+
+/// An encoded pointer from call frame information.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pointer {
+    /// The address itself
+    Direct(u64),
+    /// The address of a pointer-sized slot that holds the address
+    Indirect(u64),
+}
+
+/// A Common Information Entry, shared by the FDEs of a group of functions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cie {
+    /// Offset of the entry in its section
+    pub offset: u64,
+    /// CIE format version
+    pub version: u8,
+    /// Augmentation string, such as "zR" or "zPLR"
+    pub augmentation: String,
+    /// Size of a target address in bytes
+    pub address_size: u8,
+    /// Factor applied to advance_loc operands
+    pub code_alignment_factor: u64,
+    /// Factor applied to offset operands
+    pub data_alignment_factor: i64,
+    /// Register holding the return address
+    pub return_address_register: u64,
+    /// Encoding of the addresses in the FDEs using this CIE
+    pub fde_encoding: u8,
+    /// Encoding of the LSDA pointers in the FDEs using this CIE
+    pub lsda_encoding: u8,
+    /// Personality routine
+    pub personality: Option<Pointer>,
+    /// The FDEs using this CIE describe signal handler frames
+    pub signal_frame: bool,
+    /// Call frame instructions that set up the initial register rules
+    pub initial_instructions: Vec<u8>,
+}
+
+/// A Frame Description Entry, describing the frames of one function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fde {
+    /// Offset of the entry in its section
+    pub offset: u64,
+    /// Offset of the entry's CIE in the same section
+    pub cie_offset: u64,
+    /// First address of the function
+    pub initial_location: u64,
+    /// Size of the function in bytes
+    pub address_range: u64,
+    /// Language specific data area, such as a `.gcc_except_table` entry
+    pub lsda: Option<Pointer>,
+    /// Call frame instructions of the function
+    pub instructions: Vec<u8>,
+}
+
+impl Fde {
+    /// Get the addresses covered by this FDE.
+    pub fn range(&self) -> Range {
+        Range {
+            begin: self.initial_location,
+            end: self.initial_location.wrapping_add(self.address_range),
+        }
+    }
+
+    /// Returns true if this FDE covers an address.
+    pub fn contains(&self, address: u64) -> bool {
+        self.range().contains(address)
+    }
+}
+
+/// The CIEs and FDEs of a `.eh_frame` or `.debug_frame` section.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameTable {
+    /// Section the table was read from
+    pub section: FrameSection,
+    /// CIEs, sorted by offset
+    pub cies: Vec<Cie>,
+    /// FDEs, sorted by initial location
+    pub fdes: Vec<Fde>,
+}
+
+impl FrameTable {
+    /// Get the CIE of an FDE.
+    pub fn cie(&self, fde: &Fde) -> Option<&Cie> {
+        self.cies
+            .binary_search_by_key(&fde.cie_offset, |cie| cie.offset)
+            .ok()
+            .map(|index| &self.cies[index])
+    }
+
+    /// Find the FDE covering an address.
+    pub fn find_fde(&self, address: u64) -> Option<&Fde> {
+        let index = self
+            .fdes
+            .partition_point(|fde| fde.initial_location <= address);
+        self.fdes[..index]
+            .iter()
+            .rev()
+            .find(|fde| fde.contains(address))
+    }
+
+    /// Iterate over the address ranges of all FDEs, in address order.
+    pub fn ranges(&self) -> impl Iterator<Item = Range> + '_ {
+        self.fdes.iter().map(Fde::range)
+    }
+}
+
+/// The binary search table of a `.eh_frame_hdr` section.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EhFrameHdr {
+    /// Address of the `.eh_frame_hdr` section
+    pub address: u64,
+    /// Address of the `.eh_frame` section
+    pub eh_frame_ptr: u64,
+    /// (initial location, FDE address) pairs, sorted by initial location
+    pub table: Vec<(u64, u64)>,
+}
+
+impl EhFrameHdr {
+    /// Find the address of the FDE that may cover an address.
+    ///
+    /// This is the last FDE starting at or before the address; the FDE's
+    /// range still has to be checked.
+    pub fn lookup(&self, address: u64) -> Option<u64> {
+        let index = self
+            .table
+            .partition_point(|&(location, _)| location <= address);
+        index.checked_sub(1).map(|index| self.table[index].1)
+    }
+}
+
+/// Base addresses for decoding pointers.
+#[derive(Copy, Clone, Debug)]
+struct Bases {
+    /// Address of the start of the section data
+    section: u64,
+    text: Option<u64>,
+    data: Option<u64>,
+}
+
+/// How to read the entries of a frame section.
+#[derive(Copy, Clone, Debug)]
+struct Context {
+    section: FrameSection,
+    endianness: types::ElfEndianness,
+    address_size: u8,
+    bases: Bases,
+}
+
+fn read_pointer(
+    reader: &mut Reader,
+    encoding: u8,
+    context: &Context,
+    address_size: u8,
+) -> Result<Option<Pointer>, ParseError> {
+    if encoding == DW_EH_PE_OMIT {
+        return Ok(None);
+    }
+    let field = context.bases.section.wrapping_add(reader.offset as u64);
+    if encoding & 0x70 == DW_EH_PE_ALIGNED {
+        let align = address_size as usize;
+        let padding = (align - reader.offset % align) % align;
+        reader.bytes(padding)?;
+    }
+    let value = match encoding & 0x0f {
+        DW_EH_PE_ABSPTR => reader.sized(address_size)?,
+        DW_EH_PE_ULEB128 => reader.uleb128()?,
+        DW_EH_PE_UDATA2 => reader.sized(2)?,
+        DW_EH_PE_UDATA4 => reader.sized(4)?,
+        DW_EH_PE_UDATA8 => reader.sized(8)?,
+        DW_EH_PE_SLEB128 => reader.sleb128()? as u64,
+        DW_EH_PE_SDATA2 => reader.sized(2)? as u16 as i16 as u64,
+        DW_EH_PE_SDATA4 => reader.sized(4)? as u32 as i32 as u64,
+        DW_EH_PE_SDATA8 => reader.sized(8)?,
+        format => {
+            return Err(ParseError::InvalidFormat(Some(format!(
+                "unknown pointer format {:#x}",
+                format
+            ))))
+        }
+    };
+    let base = match encoding & 0x70 {
+        DW_EH_PE_ABSPTR | DW_EH_PE_ALIGNED => 0,
+        DW_EH_PE_PCREL => field,
+        DW_EH_PE_TEXTREL => context.bases.text.ok_or(ParseError::NotImplemented)?,
+        DW_EH_PE_DATAREL => context.bases.data.ok_or(ParseError::NotImplemented)?,
+        _ => return Err(ParseError::NotImplemented),
+    };
+    let mut address = base.wrapping_add(value);
+    if address_size < 8 {
+        address &= (1u64 << (address_size * 8)) - 1;
+    }
+    Ok(Some(if encoding & DW_EH_PE_INDIRECT != 0 {
+        Pointer::Indirect(address)
+    } else {
+        Pointer::Direct(address)
+    }))
+}
+
+/// Read the pointer of an encoding that only selects the value format.
+fn read_value(
+    reader: &mut Reader,
+    encoding: u8,
+    context: &Context,
+    address_size: u8,
+) -> Result<u64, ParseError> {
+    let context = Context {
+        bases: Bases {
+            section: 0,
+            ..context.bases
+        },
+        ..*context
+    };
+    match read_pointer(reader, encoding & 0x0f, &context, address_size)? {
+        Some(Pointer::Direct(value)) | Some(Pointer::Indirect(value)) => Ok(value),
+        None => Ok(0),
+    }
+}
+
+/// The fields common to CIEs and FDEs.
+struct EntryHeader<'a> {
+    offset: u64,
+    format: DwarfFormat,
+    /// CIE id or CIE pointer
+    id: u64,
+    /// Offset of the id field
+    id_offset: u64,
+    /// Contents after the id field
+    body: Reader<'a>,
+}
+
+/// Read an entry header, or None for the terminator of `.eh_frame`.
+fn read_entry<'a>(
+    reader: &mut Reader<'a>,
+    context: &Context,
+) -> Result<Option<EntryHeader<'a>>, ParseError> {
+    let offset = reader.offset as u64;
+    let (length, format) = reader.initial_length()?;
+    if length == 0 && context.section == FrameSection::EhFrame {
+        return Ok(None);
+    }
+    let mut body = reader.sub(length as usize)?;
+    let id_offset = body.offset as u64;
+    // The CIE id is always 4 bytes in .eh_frame
+    let id = match (context.section, format) {
+        (FrameSection::DebugFrame, DwarfFormat::Dwarf64) => body.u64()?,
+        _ => u64::from(body.u32()?),
+    };
+    Ok(Some(EntryHeader {
+        offset,
+        format,
+        id,
+        id_offset,
+        body,
+    }))
+}
+
+impl<'a> EntryHeader<'a> {
+    fn is_cie(&self, context: &Context) -> bool {
+        match (context.section, self.format) {
+            (FrameSection::EhFrame, _) => self.id == 0,
+            (FrameSection::DebugFrame, DwarfFormat::Dwarf32) => self.id == 0xffff_ffff,
+            (FrameSection::DebugFrame, DwarfFormat::Dwarf64) => self.id == u64::MAX,
+        }
+    }
+
+    fn cie_offset(&self, context: &Context) -> u64 {
+        match context.section {
+            FrameSection::EhFrame => self.id_offset.wrapping_sub(self.id),
+            FrameSection::DebugFrame => self.id,
+        }
+    }
+}
+
+fn parse_cie(mut entry: EntryHeader, context: &Context) -> Result<Cie, ParseError> {
+    let body = &mut entry.body;
+    let version = body.u8()?;
+    if version != 1 && version != 3 && version != 4 {
+        return Err(ParseError::InvalidFormat(Some(format!(
+            "unsupported CIE version {}",
+            version
+        ))));
+    }
+    let augmentation = String::from_utf8_lossy(body.cstr()?).into_owned();
+    let mut address_size = context.address_size;
+    if version >= 4 {
+        address_size = body.u8()?;
+        // segment_selector_size
+        body.u8()?;
+    }
+    let code_alignment_factor = body.uleb128()?;
+    let data_alignment_factor = body.sleb128()?;
+    let return_address_register = if version == 1 {
+        u64::from(body.u8()?)
+    } else {
+        body.uleb128()?
+    };
+
+    let mut cie = Cie {
+        offset: entry.offset,
+        version,
+        augmentation: augmentation.clone(),
+        address_size,
+        code_alignment_factor,
+        data_alignment_factor,
+        return_address_register,
+        fde_encoding: DW_EH_PE_ABSPTR,
+        lsda_encoding: DW_EH_PE_OMIT,
+        personality: None,
+        signal_frame: false,
+        initial_instructions: Vec::new(),
+    };
+    if augmentation.starts_with('z') {
+        let len = body.uleb128()? as usize;
+        let mut data = body.sub(len)?;
+        for c in augmentation.chars().skip(1) {
+            match c {
+                'L' => cie.lsda_encoding = data.u8()?,
+                'P' => {
+                    let encoding = data.u8()?;
+                    cie.personality = read_pointer(&mut data, encoding, context, address_size)?;
+                }
+                'R' => cie.fde_encoding = data.u8()?,
+                'S' => cie.signal_frame = true,
+                // AArch64 BTI and MTE markers carry no data
+                'B' | 'G' => {}
+                // The rest of the augmentation data can be skipped thanks to 'z'
+                _ => break,
+            }
+        }
+    } else if !augmentation.is_empty() {
+        // Augmentations without 'z' cannot be skipped, so the
+        // instructions cannot be found
+        return Err(ParseError::NotImplemented);
+    }
+    cie.initial_instructions = body.rest().to_vec();
+    Ok(cie)
+}
+
+fn parse_fde(mut entry: EntryHeader, cie: &Cie, context: &Context) -> Result<Fde, ParseError> {
+    let cie_offset = entry.cie_offset(context);
+    let body = &mut entry.body;
+    let (initial_location, address_range) = match context.section {
+        FrameSection::EhFrame => {
+            let location = match read_pointer(body, cie.fde_encoding, context, cie.address_size)? {
+                Some(Pointer::Direct(address)) | Some(Pointer::Indirect(address)) => address,
+                None => 0,
+            };
+            (
+                location,
+                read_value(body, cie.fde_encoding, context, cie.address_size)?,
+            )
+        }
+        FrameSection::DebugFrame => (body.sized(cie.address_size)?, body.sized(cie.address_size)?),
+    };
+    let mut lsda = None;
+    if cie.augmentation.starts_with('z') {
+        let len = body.uleb128()? as usize;
+        let mut data = body.sub(len)?;
+        if cie.augmentation.contains('L') {
+            lsda = read_pointer(&mut data, cie.lsda_encoding, context, cie.address_size)?;
+        }
+    }
+    Ok(Fde {
+        offset: entry.offset,
+        cie_offset,
+        initial_location,
+        address_range,
+        lsda,
+        instructions: body.rest().to_vec(),
+    })
+}
+
+fn parse_frame_table(data: &[u8], context: &Context) -> Result<FrameTable, ParseError> {
+    let mut reader = Reader::new(data, context.endianness);
+    let mut cies: Vec<Cie> = Vec::new();
+    let mut cie_index: HashMap<u64, usize> = HashMap::new();
+    let mut fdes = Vec::new();
+    while !reader.is_empty() {
+        let entry = match read_entry(&mut reader, context)? {
+            Some(entry) => entry,
+            None => break,
+        };
+        if entry.is_cie(context) {
+            // A CIE may already have been parsed for an FDE that preceded it
+            if let Entry::Vacant(slot) = cie_index.entry(entry.offset) {
+                slot.insert(cies.len());
+                cies.push(parse_cie(entry, context)?);
+            }
+            continue;
+        }
+        let cie_offset = entry.cie_offset(context);
+        let index = match cie_index.get(&cie_offset) {
+            Some(&index) => index,
+            None => {
+                // CIEs normally precede their FDEs, but they don't have to
+                let mut cie_reader = Reader::new(data, context.endianness);
+                cie_reader.bytes(cie_offset as usize)?;
+                let cie_entry = read_entry(&mut cie_reader, context)?.ok_or_else(|| {
+                    ParseError::InvalidFormat(Some(format!("no CIE at {:#x}", cie_offset)))
+                })?;
+                cie_index.insert(cie_offset, cies.len());
+                cies.push(parse_cie(cie_entry, context)?);
+                cies.len() - 1
+            }
+        };
+        fdes.push(parse_fde(entry, &cies[index], context)?);
+    }
+    cies.sort_by_key(|cie| cie.offset);
+    fdes.sort_by_key(|fde| fde.initial_location);
+    Ok(FrameTable {
+        section: context.section,
+        cies,
+        fdes,
+    })
+}
+
+impl File {
+    fn frame_context(&self, section: FrameSection, address: u64) -> Context {
+        Context {
+            section,
+            endianness: self.header.endianness,
+            address_size: match self.header.class {
+                types::ElfClass::Format32 => 4,
+                types::ElfClass::Format64 => 8,
+            },
+            bases: Bases {
+                section: address,
+                text: self.get_section(".text").map(|text| text.shdr.addr),
+                data: self.get_section(".got").map(|got| got.shdr.addr),
+            },
+        }
+    }
+
+    /// Parse the CIEs and FDEs of the `.eh_frame` section.
+    ///
+    /// Returns None if the file has no `.eh_frame` section. Pointers in
+    /// relocatable objects are decoded without applying relocations.
+    pub fn get_eh_frame(&self) -> Result<Option<FrameTable>, ParseError> {
+        let section = match self.get_section(".eh_frame") {
+            Some(section) => section,
+            None => return Ok(None),
+        };
+        let context = self.frame_context(FrameSection::EhFrame, section.shdr.addr);
+        parse_frame_table(&section.data, &context).map(Some)
+    }
+
+    /// Parse the CIEs and FDEs of the `.debug_frame` section.
+    ///
+    /// Returns None if the file has no `.debug_frame` section. Compressed
+    /// sections are decompressed.
+    pub fn get_debug_frame(&self) -> Result<Option<FrameTable>, ParseError> {
+        let data = match self.get_debug_section_data(".debug_frame")? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let context = self.frame_context(FrameSection::DebugFrame, 0);
+        parse_frame_table(&data, &context).map(Some)
+    }
+
+    /// Parse the binary search table of the `.eh_frame_hdr` section.
+    ///
+    /// The section is located through the PT_GNU_EH_FRAME segment, falling
+    /// back to the section name for files without program headers. Returns
+    /// None if there is no such section.
+    pub fn get_eh_frame_hdr(&self) -> Result<Option<EhFrameHdr>, ParseError> {
+        let (data, address) = match self
+            .phdrs
+            .iter()
+            .find(|phdr| phdr.progtype == types::PT_GNU_EH_FRAME)
+        {
+            Some(phdr) => (self.get_segment_data(phdr), phdr.vaddr),
+            None => match self.get_section(".eh_frame_hdr") {
+                Some(section) => (section.data.clone(), section.shdr.addr),
+                None => return Ok(None),
+            },
+        };
+        let mut context = self.frame_context(FrameSection::EhFrame, address);
+        // Data relative pointers are relative to the start of .eh_frame_hdr
+        context.bases.data = Some(address);
+        let address_size = context.address_size;
+
+        let mut reader = Reader::new(&data, self.header.endianness);
+        let version = reader.u8()?;
+        if version != 1 {
+            return Err(ParseError::InvalidFormat(Some(format!(
+                "unsupported .eh_frame_hdr version {}",
+                version
+            ))));
+        }
+        let eh_frame_ptr_encoding = reader.u8()?;
+        let fde_count_encoding = reader.u8()?;
+        let table_encoding = reader.u8()?;
+        let eh_frame_ptr =
+            match read_pointer(&mut reader, eh_frame_ptr_encoding, &context, address_size)? {
+                Some(Pointer::Direct(address)) => address,
+                _ => return Err(ParseError::NotImplemented),
+            };
+        let mut table = Vec::new();
+        if fde_count_encoding != DW_EH_PE_OMIT && table_encoding != DW_EH_PE_OMIT {
+            let count = read_value(&mut reader, fde_count_encoding, &context, address_size)?;
+            for _ in 0..count {
+                let location = read_pointer(&mut reader, table_encoding, &context, address_size)?;
+                let fde = read_pointer(&mut reader, table_encoding, &context, address_size)?;
+                if let (Some(Pointer::Direct(location)), Some(Pointer::Direct(fde))) =
+                    (location, fde)
+                {
+                    table.push((location, fde));
+                }
+            }
+        }
+        Ok(Some(EhFrameHdr {
+            address,
+            eh_frame_ptr,
+            table,
+        }))
+    }
+
+    /// Find the FDE covering an address in the `.eh_frame` section, with its CIE.
+    ///
+    /// The `.eh_frame_hdr` search table is used if there is one, so only the
+    /// entries involved are parsed. Otherwise the whole section is parsed.
+    ///
+    /// Callers looking up many addresses should parse the search table once
+    /// with `get_eh_frame_hdr` and use `find_fde_with_hdr`.
+    pub fn find_fde(&self, address: u64) -> Result<Option<(Cie, Fde)>, ParseError> {
+        match self.get_eh_frame_hdr()? {
+            Some(hdr) if !hdr.table.is_empty() => self.find_fde_with_hdr(&hdr, address),
+            _ => {
+                let table = match self.get_eh_frame()? {
+                    Some(table) => table,
+                    None => return Ok(None),
+                };
+                Ok(table
+                    .find_fde(address)
+                    .and_then(|fde| table.cie(fde).map(|cie| (cie.clone(), fde.clone()))))
+            }
+        }
+    }
+
+    /// Find the FDE covering an address through an already parsed `.eh_frame_hdr`
+    /// search table, with its CIE.
+    ///
+    /// Only the FDE found in the table and its CIE are parsed.
+    pub fn find_fde_with_hdr(
+        &self,
+        hdr: &EhFrameHdr,
+        address: u64,
+    ) -> Result<Option<(Cie, Fde)>, ParseError> {
+        let fde_address = match hdr.lookup(address) {
+            Some(fde_address) => fde_address,
+            None => return Ok(None),
+        };
+        let (data, base) = match self.sections.iter().find(|section| {
+            section.shdr.name == ".eh_frame"
+                && fde_address >= section.shdr.addr
+                && fde_address < section.shdr.addr + section.data.len() as u64
+        }) {
+            Some(section) => (&section.data[..], section.shdr.addr),
+            // Without section headers, .eh_frame is read from the segment loading it
+            None => match self.phdrs.iter().find(|phdr| {
+                phdr.progtype == types::PT_LOAD
+                    && hdr.eh_frame_ptr >= phdr.vaddr
+                    && hdr.eh_frame_ptr - phdr.vaddr < phdr.filesz
+            }) {
+                Some(phdr) => match self.get_segment_bytes(phdr) {
+                    Some(bytes) => (
+                        bytes
+                            .get((hdr.eh_frame_ptr - phdr.vaddr) as usize..)
+                            .unwrap_or_default(),
+                        hdr.eh_frame_ptr,
+                    ),
+                    None => return Ok(None),
+                },
+                None => return Ok(None),
+            },
+        };
+        if fde_address < base || fde_address - base >= data.len() as u64 {
+            return Ok(None);
+        }
+        let context = self.frame_context(FrameSection::EhFrame, base);
+        let entry_at = |offset: u64| -> Result<EntryHeader, ParseError> {
+            let mut reader = Reader::new(data, self.header.endianness);
+            reader.bytes(offset as usize)?;
+            read_entry(&mut reader, &context)?.ok_or_else(|| {
+                ParseError::InvalidFormat(Some(format!("no frame entry at {:#x}", offset)))
+            })
+        };
+        let entry = entry_at(fde_address - base)?;
+        if entry.is_cie(&context) {
+            return Err(ParseError::InvalidFormat(Some(format!(
+                "search table entry {:#x} is not an FDE",
+                fde_address
+            ))));
+        }
+        let cie = parse_cie(entry_at(entry.cie_offset(&context))?, &context)?;
+        let fde = parse_fde(entry, &cie, &context)?;
+        Ok(if fde.contains(address) {
+            Some((cie, fde))
+        } else {
+            None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn ranges(table: &FrameTable) -> Vec<(u64, u64)> {
+        table
+            .ranges()
+            .map(|range| (range.begin, range.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_eh_frame() {
+        let file = File::open_path(PathBuf::from("tests/samples/dwarf/eh")).unwrap();
+        let table = file.get_eh_frame().unwrap().unwrap();
+        assert_eq!(table.cies.len(), 3);
+        assert_eq!(
+            ranges(&table),
+            vec![
+                (0x1020, 0x10a0),
+                (0x10a0, 0x10a8),
+                (0x10b0, 0x10d2),
+                (0x1199, 0x11ed),
+                (0x11ed, 0x121a),
+                (0x121a, 0x1228),
+            ]
+        );
+
+        // thrower() uses the C++ personality routine and has an LSDA
+        let fde = table.find_fde(0x11a0).unwrap();
+        assert_eq!(fde.initial_location, 0x1199);
+        let cie = table.cie(fde).unwrap();
+        assert_eq!(cie.augmentation, "zPLR");
+        assert_eq!(cie.code_alignment_factor, 1);
+        assert_eq!(cie.data_alignment_factor, -8);
+        assert_eq!(cie.return_address_register, 16);
+        assert!(matches!(cie.personality, Some(Pointer::Indirect(_))));
+        let except_table = file.get_section(".gcc_except_table").unwrap();
+        match fde.lsda {
+            Some(Pointer::Direct(lsda)) => {
+                assert!(lsda >= except_table.shdr.addr);
+                assert!(lsda < except_table.shdr.addr + except_table.shdr.size);
+            }
+            ref lsda => panic!("unexpected LSDA {:?}", lsda),
+        }
+        assert!(table.find_fde(0x1190).is_none());
+    }
+
+    #[test]
+    fn test_eh_frame_hdr() {
+        let file = File::open_path(PathBuf::from("tests/samples/dwarf/eh")).unwrap();
+        let hdr = file.get_eh_frame_hdr().unwrap().unwrap();
+        assert_eq!(hdr.address, 0x200c);
+        assert_eq!(hdr.eh_frame_ptr, 0x2048);
+        assert_eq!(hdr.table.len(), 6);
+        assert_eq!(hdr.lookup(0x1000), None);
+        assert_eq!(hdr.lookup(0x11a0), Some(0x2048 + 0xa8));
+
+        let table = file.get_eh_frame().unwrap().unwrap();
+        for address in [0x1020, 0x10a4, 0x11a0, 0x11ed, 0x1227] {
+            let (cie, fde) = file.find_fde(address).unwrap().unwrap();
+            let expected = table.find_fde(address).unwrap();
+            assert_eq!(&fde, expected);
+            assert_eq!(&cie, table.cie(expected).unwrap());
+            assert_eq!(
+                file.find_fde_with_hdr(&hdr, address).unwrap(),
+                Some((cie, fde))
+            );
+        }
+        assert!(file.find_fde(0x10d2).unwrap().is_none());
+        assert!(file.find_fde_with_hdr(&hdr, 0x10d2).unwrap().is_none());
+
+        // The FDEs are read from the loaded segment once the sections are gone
+        let mut stripped = File::open_path(PathBuf::from("tests/samples/dwarf/eh")).unwrap();
+        stripped.sections.clear();
+        assert_eq!(stripped.get_eh_frame_hdr().unwrap().unwrap(), hdr);
+        for address in [0x1020, 0x10a4, 0x11a0, 0x11ed, 0x1227] {
+            assert_eq!(
+                stripped.find_fde(address).unwrap(),
+                file.find_fde(address).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_debug_frame() {
+        let file = File::open_path(PathBuf::from("tests/samples/dwarf/debug_frame")).unwrap();
+        let table = file.get_debug_frame().unwrap().unwrap();
+        assert_eq!(table.section, FrameSection::DebugFrame);
+        assert_eq!(ranges(&table), vec![(0x1139, 0x1155), (0x1155, 0x1166)]);
+        let fde = table.find_fde(0x1150).unwrap();
+        let cie = table.cie(fde).unwrap();
+        assert_eq!(cie.augmentation, "");
+        assert_eq!(cie.return_address_register, 16);
+        assert_eq!(fde.lsda, None);
+    }
+}
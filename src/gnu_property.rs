@@ -0,0 +1,225 @@
+use crate::types;
+use crate::{File, ParseError};
+use std::io;
+
+/// A decoded entry of a NT_GNU_PROPERTY_TYPE_0 note.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GnuProperty {
+    /// Stack size needed by the program. Named GNU_PROPERTY_STACK_SIZE in C code.
+    StackSize(u64),
+    /// No copy relocations on protected data symbols. Named GNU_PROPERTY_NO_COPY_ON_PROTECTED in C code.
+    NoCopyOnProtected,
+    /// x86 ISA levels needed. Named GNU_PROPERTY_X86_ISA_1_NEEDED in C code.
+    X86Isa1Needed(types::X86Isa1Flag),
+    /// x86 ISA levels used. Named GNU_PROPERTY_X86_ISA_1_USED in C code.
+    X86Isa1Used(types::X86Isa1Flag),
+    /// x86 features every input was built with. Named GNU_PROPERTY_X86_FEATURE_1_AND in C code.
+    X86Feature1And(types::X86Feature1Flag),
+    /// AArch64 features every input was built with. Named GNU_PROPERTY_AARCH64_FEATURE_1_AND in C code.
+    Aarch64Feature1And(types::Aarch64Feature1Flag),
+    /// Any property this library does not decode
+    Unknown { pr_type: u32, data: Vec<u8> },
+}
+
+impl File {
+    /// Get the GNU program properties of this file.
+    ///
+    /// Properties are read from the PT_GNU_PROPERTY segment when there is one,
+    /// otherwise from the `.note.gnu.property` section.
+    pub fn get_gnu_properties(&self) -> Result<Vec<GnuProperty>, ParseError> {
+        let notes = match self
+            .phdrs
+            .iter()
+            .find(|phdr| phdr.progtype == types::PT_GNU_PROPERTY)
+        {
+            Some(phdr) => self.get_segment_notes(phdr)?,
+            None => match self.get_section(".note.gnu.property") {
+                Some(section) => self.get_notes(section)?,
+                None => Vec::new(),
+            },
+        };
+
+        let mut properties = Vec::new();
+        for note in notes
+            .iter()
+            .filter(|note| note.name == "GNU" && note.ntype == types::NT_GNU_PROPERTY_TYPE_0)
+        {
+            self.parse_gnu_properties(&note.desc, &mut properties)?;
+        }
+        Ok(properties)
+    }
+
+    /// Returns the x86 feature bits that all inputs were built with, if recorded.
+    pub fn get_x86_features(&self) -> Result<Option<types::X86Feature1Flag>, ParseError> {
+        Ok(self
+            .get_gnu_properties()?
+            .into_iter()
+            .find_map(|property| match property {
+                GnuProperty::X86Feature1And(flags) => Some(flags),
+                _ => None,
+            }))
+    }
+
+    /// Returns the AArch64 feature bits that all inputs were built with, if recorded.
+    pub fn get_aarch64_features(&self) -> Result<Option<types::Aarch64Feature1Flag>, ParseError> {
+        Ok(self
+            .get_gnu_properties()?
+            .into_iter()
+            .find_map(|property| match property {
+                GnuProperty::Aarch64Feature1And(flags) => Some(flags),
+                _ => None,
+            }))
+    }
+
+    fn parse_gnu_properties(
+        &self,
+        desc: &[u8],
+        properties: &mut Vec<GnuProperty>,
+    ) -> Result<(), ParseError> {
+        // Property data is padded to the natural word size of the file class
+        let align: u64 = match self.header.class {
+            types::ElfClass::Format32 => 4,
+            types::ElfClass::Format64 => 8,
+        };
+        let mut io_desc = io::Cursor::new(desc);
+        while io_desc.position() + 8 <= desc.len() as u64 {
+            let pr_type = read_u32!(self, io_desc)?;
+            let pr_datasz = read_u32!(self, io_desc)? as u64;
+            let start = io_desc.position();
+            let end = start + pr_datasz;
+            if end > desc.len() as u64 {
+                return Err(ParseError::InvalidFormat(Some(
+                    "GNU property extends past end of note".to_string(),
+                )));
+            }
+            let data = &desc[start as usize..end as usize];
+            let mut io_data = io::Cursor::new(data);
+
+            let property = match pr_type {
+                types::GNU_PROPERTY_STACK_SIZE if pr_datasz == align => {
+                    GnuProperty::StackSize(match self.header.class {
+                        types::ElfClass::Format32 => read_u32!(self, io_data)? as u64,
+                        types::ElfClass::Format64 => read_u64!(self, io_data)?,
+                    })
+                }
+                types::GNU_PROPERTY_NO_COPY_ON_PROTECTED if pr_datasz == 0 => {
+                    GnuProperty::NoCopyOnProtected
+                }
+                types::GNU_PROPERTY_X86_ISA_1_NEEDED if pr_datasz == 4 => {
+                    GnuProperty::X86Isa1Needed(types::X86Isa1Flag(read_u32!(self, io_data)?))
+                }
+                types::GNU_PROPERTY_X86_ISA_1_USED if pr_datasz == 4 => {
+                    GnuProperty::X86Isa1Used(types::X86Isa1Flag(read_u32!(self, io_data)?))
+                }
+                types::GNU_PROPERTY_X86_FEATURE_1_AND
+                    if pr_datasz == 4
+                        && self.header.cpu_architecture
+                            != types::ElfCpuArchitecture::EM_AARCH64 =>
+                {
+                    GnuProperty::X86Feature1And(types::X86Feature1Flag(read_u32!(self, io_data)?))
+                }
+                types::GNU_PROPERTY_AARCH64_FEATURE_1_AND
+                    if pr_datasz == 4
+                        && self.header.cpu_architecture
+                            == types::ElfCpuArchitecture::EM_AARCH64 =>
+                {
+                    GnuProperty::Aarch64Feature1And(types::Aarch64Feature1Flag(read_u32!(
+                        self, io_data
+                    )?))
+                }
+                _ => GnuProperty::Unknown {
+                    pr_type,
+                    data: data.to_vec(),
+                },
+            };
+            properties.push(property);
+
+            io_desc.set_position((end + align - 1) & !(align - 1));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GnuProperty;
+    use crate::types::{
+        self, GNU_PROPERTY_AARCH64_FEATURE_1_BTI, GNU_PROPERTY_AARCH64_FEATURE_1_PAC,
+        GNU_PROPERTY_X86_FEATURE_1_IBT, GNU_PROPERTY_X86_FEATURE_1_SHSTK,
+        GNU_PROPERTY_X86_ISA_1_BASELINE,
+    };
+    use crate::File;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_x86_cet_properties() {
+        let file = File::open_path(PathBuf::from("tests/samples/x86_64_hardened")).unwrap();
+        let features = file.get_x86_features().unwrap().expect("x86 features");
+        assert!(features.contains(GNU_PROPERTY_X86_FEATURE_1_IBT));
+        assert!(features.contains(GNU_PROPERTY_X86_FEATURE_1_SHSTK));
+        assert!(file
+            .get_gnu_properties()
+            .unwrap()
+            .contains(&GnuProperty::X86Isa1Needed(GNU_PROPERTY_X86_ISA_1_BASELINE)));
+
+        let file = File::open_path(PathBuf::from("tests/samples/test1")).unwrap();
+        assert!(file.get_gnu_properties().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_aarch64_properties() {
+        let mut file = File::new();
+        file.header.class = types::ElfClass::Format64;
+        file.header.cpu_architecture = types::ElfCpuArchitecture::EM_AARCH64;
+
+        // FEATURE_1_AND with BTI and PAC, padded to 8 bytes, then a stack size
+        let mut desc = Vec::new();
+        desc.extend_from_slice(&types::GNU_PROPERTY_AARCH64_FEATURE_1_AND.to_le_bytes());
+        desc.extend_from_slice(&4u32.to_le_bytes());
+        desc.extend_from_slice(&3u32.to_le_bytes());
+        desc.extend_from_slice(&[0; 4]);
+        desc.extend_from_slice(&types::GNU_PROPERTY_STACK_SIZE.to_le_bytes());
+        desc.extend_from_slice(&8u32.to_le_bytes());
+        desc.extend_from_slice(&0x1000u64.to_le_bytes());
+
+        let mut properties = Vec::new();
+        file.parse_gnu_properties(&desc, &mut properties).unwrap();
+        assert_eq!(properties.len(), 2);
+        match properties[0] {
+            GnuProperty::Aarch64Feature1And(flags) => {
+                assert!(flags.contains(GNU_PROPERTY_AARCH64_FEATURE_1_BTI));
+                assert!(flags.contains(GNU_PROPERTY_AARCH64_FEATURE_1_PAC));
+            }
+            ref other => panic!("unexpected property {:?}", other),
+        }
+        assert_eq!(properties[1], GnuProperty::StackSize(0x1000));
+    }
+
+    #[test]
+    fn test_32bit_property_alignment() {
+        let mut file = File::new();
+        file.header.cpu_architecture = types::ElfCpuArchitecture::EM_386;
+
+        // 32-bit notes pad properties to 4 bytes, so these follow each other directly
+        let mut desc = Vec::new();
+        desc.extend_from_slice(&types::GNU_PROPERTY_X86_FEATURE_1_AND.to_le_bytes());
+        desc.extend_from_slice(&4u32.to_le_bytes());
+        desc.extend_from_slice(&3u32.to_le_bytes());
+        desc.extend_from_slice(&types::GNU_PROPERTY_STACK_SIZE.to_le_bytes());
+        desc.extend_from_slice(&4u32.to_le_bytes());
+        desc.extend_from_slice(&0x2000u32.to_le_bytes());
+        desc.extend_from_slice(&types::GNU_PROPERTY_NO_COPY_ON_PROTECTED.to_le_bytes());
+        desc.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut properties = Vec::new();
+        file.parse_gnu_properties(&desc, &mut properties).unwrap();
+        assert_eq!(
+            properties,
+            vec![
+                GnuProperty::X86Feature1And(types::X86Feature1Flag(3)),
+                GnuProperty::StackSize(0x2000),
+                GnuProperty::NoCopyOnProtected,
+            ]
+        );
+    }
+}
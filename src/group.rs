@@ -0,0 +1,162 @@
+use crate::types;
+use crate::{File, ParseError, Section};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io;
+
+/// A section group, as described by an SHT_GROUP section.
+///
+/// Groups are used to tie sections together that must be kept or discarded
+/// as a unit by the linker, most commonly for COMDAT deduplication of C++
+/// template instantiations and inline functions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SectionGroup {
+    /// Index of the SHT_GROUP section in the section header table
+    pub index: usize,
+    /// Group flag word
+    pub flags: types::GroupFlag,
+    /// Symbol whose name is the group signature
+    pub signature: types::Symbol,
+    /// Section header indexes of the group members
+    pub members: Vec<u32>,
+}
+
+impl SectionGroup {
+    /// Returns true if the section with the given index is a member of this group
+    pub fn contains(&self, index: usize) -> bool {
+        self.members.iter().any(|&member| member as usize == index)
+    }
+}
+
+impl File {
+    /// Parse the contents of an SHT_GROUP section.
+    ///
+    /// The group signature is resolved through the symbol table referenced by
+    /// the section's `sh_link`, using `sh_info` as the symbol index.
+    pub fn get_section_group(&self, section: &Section) -> Result<SectionGroup, ParseError> {
+        let symbols = self.get_symbols(self.group_symtab(section)?)?;
+        self.parse_section_group(section, &symbols)
+    }
+
+    fn group_symtab(&self, section: &Section) -> Result<&Section, ParseError> {
+        self.sections
+            .get(section.shdr.link as usize)
+            .ok_or(ParseError::InvalidFormat(Some(
+                "Invalid SHT_GROUP sh_link".to_string(),
+            )))
+    }
+
+    /// Parse an SHT_GROUP section with the symbols of its linked symbol table.
+    fn parse_section_group(
+        &self,
+        section: &Section,
+        symbols: &[types::Symbol],
+    ) -> Result<SectionGroup, ParseError> {
+        if section.shdr.shtype != types::SectionType::Group {
+            return Err(ParseError::InvalidFormat(Some(format!(
+                "{} is not a SHT_GROUP section",
+                section.shdr.name
+            ))));
+        }
+        let index = self
+            .section_index(section)
+            .ok_or(ParseError::InvalidFormat(Some(
+                "Section does not belong to this file".to_string(),
+            )))?;
+
+        let mut io_section = io::Cursor::new(&section.data);
+        let flags = types::GroupFlag(read_u32!(self, io_section)?);
+        let mut members = Vec::new();
+        while (io_section.position() as usize) < section.data.len() {
+            members.push(read_u32!(self, io_section)?);
+        }
+
+        let signature =
+            symbols
+                .get(section.shdr.info as usize)
+                .cloned()
+                .ok_or(ParseError::InvalidFormat(Some(
+                    "Invalid SHT_GROUP sh_info".to_string(),
+                )))?;
+
+        Ok(SectionGroup {
+            index,
+            flags,
+            signature,
+            members,
+        })
+    }
+
+    /// Parse all section groups in the file.
+    ///
+    /// Parse them once and look up the group of each section with
+    /// `get_group_of`.
+    pub fn get_section_groups(&self) -> Result<Vec<SectionGroup>, ParseError> {
+        // Groups usually share one symbol table, which is parsed only once
+        let mut symbol_tables: HashMap<u32, Vec<types::Symbol>> = HashMap::new();
+        let mut groups = Vec::new();
+        for section in self.sections.iter() {
+            if section.shdr.shtype != types::SectionType::Group {
+                continue;
+            }
+            let symbols = match symbol_tables.entry(section.shdr.link) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    entry.insert(self.get_symbols(self.group_symtab(section)?)?)
+                }
+            };
+            groups.push(self.parse_section_group(section, symbols)?);
+        }
+        Ok(groups)
+    }
+
+    /// Find the group that the given section is a member of, if any.
+    ///
+    /// `groups` are the groups of this file, from `get_section_groups`. Only
+    /// sections with the SHF_GROUP flag set can be group members.
+    pub fn get_group_of<'a>(
+        &self,
+        groups: &'a [SectionGroup],
+        section: &Section,
+    ) -> Option<&'a SectionGroup> {
+        if section.shdr.flags.0 & types::SHF_GROUP.0 == 0 {
+            return None;
+        }
+        let index = self.section_index(section)?;
+        groups.iter().find(|group| group.contains(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types;
+    use crate::File;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_comdat_groups() {
+        let file = File::open_path(PathBuf::from("tests/samples/comdat.o")).unwrap();
+        let groups = file.get_section_groups().unwrap();
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|group| group.flags.is_comdat()));
+        assert_eq!(groups[0].signature.name, "_Z6answerv");
+
+        let text = file.get_section(".text._Z5twiceIiET_S0_").unwrap();
+        let group = file.get_group_of(&groups, text).expect("member of a group");
+        assert_eq!(group.signature.name, "_Z5twiceIiET_S0_");
+        assert_eq!(group.members.len(), 1);
+
+        let plain = file.get_section(".text").unwrap();
+        assert!(file.get_group_of(&groups, plain).is_none());
+    }
+
+    #[test]
+    fn test_section_type_values() {
+        // SHT_GROUP follows SHT_INIT_ARRAY, SHT_FINI_ARRAY and SHT_PREINIT_ARRAY
+        assert_eq!(types::SectionType::InitArray as u32, 14);
+        assert_eq!(types::SectionType::FiniArray as u32, 15);
+        assert_eq!(types::SectionType::PreinitArray as u32, 16);
+        assert_eq!(types::SectionType::Group as u32, 17);
+        assert_eq!(types::SectionType::SymtabShndx as u32, 18);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::types;
+use crate::{File, ParseError};
+
+/// An undefined dynamic symbol that must be provided by another object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Import {
+    /// The undefined symbol
+    pub symbol: types::Symbol,
+    /// Required symbol version, e.g. "GLIBC_2.2.5"
+    pub version: Option<String>,
+    /// The DT_NEEDED library that provides the symbol, when it can be determined
+    /// from the version requirement or because there is only one needed library
+    pub library: Option<String>,
+}
+
+/// A defined dynamic symbol that other objects can bind to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Export {
+    /// The exported symbol
+    pub symbol: types::Symbol,
+    /// Version the symbol is defined with, e.g. "LIBFOO_1.0"
+    pub version: Option<String>,
+    /// The version is the default one (`@@`) that unversioned references bind to
+    pub default_version: bool,
+}
+
+impl File {
+    /// Get the symbols this object imports through its dynamic symbol table.
+    pub fn get_imports(&self) -> Result<Vec<Import>, ParseError> {
+        let needed = self.get_needed()?;
+        let mut imports = Vec::new();
+        for (symbol, version) in self.get_dynamic_symbols_with_versions()? {
+            if symbol.shndx != types::SectionIndex::Undefined || symbol.name.is_empty() {
+                continue;
+            }
+            let library = match version.as_ref().and_then(|v| v.file.clone()) {
+                Some(file) => Some(file),
+                None if needed.len() == 1 => Some(needed[0].clone()),
+                None => None,
+            };
+            imports.push(Import {
+                symbol,
+                version: version.map(|v| v.name),
+                library,
+            });
+        }
+        Ok(imports)
+    }
+
+    /// Get the symbols this object exports through its dynamic symbol table.
+    ///
+    /// Only defined global, weak and unique symbols with default or protected
+    /// visibility are exported. The absolute symbols the linker emits for
+    /// version definitions are left out.
+    pub fn get_exports(&self) -> Result<Vec<Export>, ParseError> {
+        let definitions = self.get_version_definitions()?;
+        let mut exports = Vec::new();
+        for (symbol, version) in self.get_dynamic_symbols_with_versions()? {
+            if symbol.shndx == types::SectionIndex::Undefined || symbol.name.is_empty() {
+                continue;
+            }
+            if symbol.bind != types::STB_GLOBAL
+                && symbol.bind != types::STB_WEAK
+                && symbol.bind != types::STB_GNU_UNIQUE
+            {
+                continue;
+            }
+            if symbol.vis != types::STV_DEFAULT && symbol.vis != types::STV_PROTECTED {
+                continue;
+            }
+            if symbol.shndx == types::SectionIndex::Absolute
+                && definitions.iter().any(|def| def.name == symbol.name)
+            {
+                continue;
+            }
+            exports.push(Export {
+                symbol,
+                default_version: version.as_ref().is_some_and(|v| !v.hidden),
+                version: version.map(|v| v.name),
+            });
+        }
+        Ok(exports)
+    }
+
+    /// Dynamic symbols paired with their versions.
+    fn get_dynamic_symbols_with_versions(
+        &self,
+    ) -> Result<Vec<(types::Symbol, Option<crate::version::SymbolVersion>)>, ParseError> {
+        let dynsym = match self
+            .sections
+            .iter()
+            .find(|section| section.shdr.shtype == types::SectionType::Dynsym)
+        {
+            Some(section) => section,
+            None => return Ok(Vec::new()),
+        };
+        let mut versions = self.get_symbol_versions()?.into_iter();
+        Ok(self
+            .get_symbols(dynsym)?
+            .into_iter()
+            .map(|symbol| (symbol, versions.next().flatten()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types;
+    use crate::File;
+
+    #[test]
+    fn test_imports_exports() {
+        let file = File::open_path("tests/samples/libabi_v1.so").unwrap();
+
+        let imports = file.get_imports().unwrap();
+        let strlen = imports
+            .iter()
+            .find(|import| import.symbol.name == "strlen")
+            .unwrap();
+        assert_eq!(strlen.version.as_deref(), Some("GLIBC_2.2.5"));
+        assert_eq!(strlen.library.as_deref(), Some("libc.so.6"));
+
+        let exports = file.get_exports().unwrap();
+        let mut names: Vec<&str> = exports
+            .iter()
+            .map(|export| export.symbol.name.as_str())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "abi_add",
+                "abi_counter",
+                "abi_len",
+                "abi_old",
+                "abi_protected",
+                "abi_weak"
+            ]
+        );
+        assert!(exports.iter().all(
+            |export| export.default_version && export.version.as_deref() == Some("LIBABI_1.0")
+        ));
+        let weak = exports
+            .iter()
+            .find(|export| export.symbol.name == "abi_weak")
+            .unwrap();
+        assert_eq!(weak.symbol.bind, types::STB_WEAK);
+    }
+}
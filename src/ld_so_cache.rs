@@ -0,0 +1,335 @@
+use crate::types;
+use crate::ParseError;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::fs;
+use std::path::Path;
+
+/// Magic string of the old (libc5 era) cache format
+const CACHEMAGIC: &[u8] = b"ld.so-1.7.0";
+/// Magic string and version of the new cache format
+const CACHEMAGIC_NEW: &[u8] = b"glibc-ld.so.cache1.1";
+/// Magic number of the extension directory in the new format
+const CACHE_EXTENSION_MAGIC: u32 = 0xeaa4_2174;
+/// Extension section holding the name of the generating program
+const CACHE_EXTENSION_TAG_GENERATOR: u32 = 0;
+/// Extension section holding the glibc-hwcaps subdirectory names
+const CACHE_EXTENSION_TAG_GLIBC_HWCAPS: u32 = 1;
+/// Set in the hwcap field of entries that live in a glibc-hwcaps subdirectory
+const DL_CACHE_HWCAP_EXTENSION: u64 = 1 << 62;
+
+/// Size of the old format header
+const OLD_HEADER_SIZE: usize = 16;
+/// Size of an old format entry
+const OLD_ENTRY_SIZE: usize = 12;
+/// Size of the new format header
+const NEW_HEADER_SIZE: usize = 48;
+/// Size of a new format entry
+const NEW_ENTRY_SIZE: usize = 24;
+
+/// Mask of the library type bits of the cache entry flags
+pub const FLAG_TYPE_MASK: u32 = 0x00ff;
+/// ELF library
+pub const FLAG_ELF: u32 = 0x0001;
+/// ELF libc5 library
+pub const FLAG_ELF_LIBC5: u32 = 0x0002;
+/// ELF glibc library
+pub const FLAG_ELF_LIBC6: u32 = 0x0003;
+/// Mask of the architecture requirement bits of the cache entry flags
+pub const FLAG_REQUIRED_MASK: u32 = 0xff00;
+/// 64-bit SPARC library
+pub const FLAG_SPARC_LIB64: u32 = 0x0100;
+/// 64-bit IA-64 library
+pub const FLAG_IA64_LIB64: u32 = 0x0200;
+/// 64-bit x86-64 library
+pub const FLAG_X8664_LIB64: u32 = 0x0300;
+/// 64-bit s390 library
+pub const FLAG_S390_LIB64: u32 = 0x0400;
+/// 64-bit PowerPC library
+pub const FLAG_POWERPC_LIB64: u32 = 0x0500;
+/// MIPS n32 library
+pub const FLAG_MIPS64_LIBN32: u32 = 0x0600;
+/// MIPS n64 library
+pub const FLAG_MIPS64_LIBN64: u32 = 0x0700;
+/// x32 library
+pub const FLAG_X8664_LIBX32: u32 = 0x0800;
+/// ARM hard-float library
+pub const FLAG_ARM_LIBHF: u32 = 0x0900;
+/// 64-bit AArch64 library
+pub const FLAG_AARCH64_LIB64: u32 = 0x0a00;
+/// ARM soft-float library
+pub const FLAG_ARM_LIBSF: u32 = 0x0b00;
+/// RISC-V soft-float library
+pub const FLAG_RISCV_FLOAT_ABI_SOFT: u32 = 0x0f00;
+/// RISC-V double-float library
+pub const FLAG_RISCV_FLOAT_ABI_DOUBLE: u32 = 0x1000;
+
+/// An entry of the loader cache.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheEntry {
+    /// Library type and architecture flags, see the FLAG_* constants
+    pub flags: u32,
+    /// Library name, usually its SONAME
+    pub name: String,
+    /// Absolute path of the library
+    pub path: String,
+    /// Required OS version, zero if none (new format only)
+    pub osversion: u32,
+    /// Hardware capability bits (new format only)
+    pub hwcap: u64,
+    /// glibc-hwcaps subdirectory the library was found in, e.g. "x86-64-v3"
+    pub hwcaps_subdir: Option<String>,
+}
+
+impl CacheEntry {
+    /// Returns true if the loader would consider this entry for an object
+    /// with the given header.
+    pub fn matches(&self, header: &types::FileHeader) -> bool {
+        let kind = self.flags & FLAG_TYPE_MASK;
+        if kind != FLAG_ELF && kind != FLAG_ELF_LIBC5 && kind != FLAG_ELF_LIBC6 {
+            return false;
+        }
+        let required = self.flags & FLAG_REQUIRED_MASK;
+        match (header.class, header.cpu_architecture) {
+            (types::ElfClass::Format64, types::ElfCpuArchitecture::EM_X86_64) => {
+                required == FLAG_X8664_LIB64
+            }
+            (types::ElfClass::Format32, types::ElfCpuArchitecture::EM_X86_64) => {
+                required == FLAG_X8664_LIBX32
+            }
+            (types::ElfClass::Format64, types::ElfCpuArchitecture::EM_AARCH64) => {
+                required == FLAG_AARCH64_LIB64
+            }
+            (types::ElfClass::Format64, types::ElfCpuArchitecture::EM_PPC64) => {
+                required == FLAG_POWERPC_LIB64
+            }
+            (types::ElfClass::Format64, types::ElfCpuArchitecture::EM_S390) => {
+                required == FLAG_S390_LIB64
+            }
+            (types::ElfClass::Format64, types::ElfCpuArchitecture::EM_SPARCV9) => {
+                required == FLAG_SPARC_LIB64
+            }
+            (types::ElfClass::Format64, types::ElfCpuArchitecture::EM_IA_64) => {
+                required == FLAG_IA64_LIB64
+            }
+            (types::ElfClass::Format32, types::ElfCpuArchitecture::EM_ARM) => {
+                required == 0 || required == FLAG_ARM_LIBHF || required == FLAG_ARM_LIBSF
+            }
+            (types::ElfClass::Format64, types::ElfCpuArchitecture::EM_MIPS) => {
+                required == FLAG_MIPS64_LIBN64
+            }
+            (types::ElfClass::Format64, _) => false,
+            (types::ElfClass::Format32, _) => required == 0,
+        }
+    }
+}
+
+/// A parsed `/etc/ld.so.cache`.
+///
+/// Both the old `ld.so-1.7.0` format and the new `glibc-ld.so.cache1.1`
+/// format are supported, as well as files containing both. When both are
+/// present only the entries of the new format are used, like glibc does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LdSoCache {
+    /// Cache entries, in the order they appear in the file
+    pub entries: Vec<CacheEntry>,
+    /// Description of the program that generated the cache, if recorded
+    pub generator: Option<String>,
+}
+
+fn format_error(msg: &str) -> ParseError {
+    ParseError::InvalidFormat(Some(msg.to_string()))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn u32(&self, offset: usize) -> Result<u32, ParseError> {
+        let bytes = self
+            .data
+            .get(offset..offset + 4)
+            .ok_or_else(|| format_error("Truncated ld.so.cache"))?;
+        Ok(if self.big_endian {
+            BigEndian::read_u32(bytes)
+        } else {
+            LittleEndian::read_u32(bytes)
+        })
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64, ParseError> {
+        let bytes = self
+            .data
+            .get(offset..offset + 8)
+            .ok_or_else(|| format_error("Truncated ld.so.cache"))?;
+        Ok(if self.big_endian {
+            BigEndian::read_u64(bytes)
+        } else {
+            LittleEndian::read_u64(bytes)
+        })
+    }
+
+    fn string(&self, offset: usize) -> Result<String, ParseError> {
+        let bytes = self
+            .data
+            .get(offset..)
+            .ok_or_else(|| format_error("Invalid ld.so.cache string offset"))?;
+        Ok(bytes
+            .iter()
+            .take_while(|&&b| b != 0)
+            .map(|&b| b as char)
+            .collect())
+    }
+}
+
+impl LdSoCache {
+    pub fn open_path<T: AsRef<Path>>(path: T) -> Result<LdSoCache, ParseError> {
+        LdSoCache::parse(&fs::read(path)?)
+    }
+
+    /// Parse the contents of a loader cache file.
+    pub fn parse(data: &[u8]) -> Result<LdSoCache, ParseError> {
+        let mut reader = Reader {
+            data,
+            big_endian: false,
+        };
+
+        let mut new_start = 0;
+        let mut old_entries = Vec::new();
+        if data.starts_with(CACHEMAGIC) {
+            let nlibs = reader.u32(12)? as usize;
+            let strings = OLD_HEADER_SIZE + nlibs * OLD_ENTRY_SIZE;
+            for i in 0..nlibs {
+                let entry = OLD_HEADER_SIZE + i * OLD_ENTRY_SIZE;
+                old_entries.push(CacheEntry {
+                    flags: reader.u32(entry)?,
+                    name: reader.string(strings + reader.u32(entry + 4)? as usize)?,
+                    path: reader.string(strings + reader.u32(entry + 8)? as usize)?,
+                    osversion: 0,
+                    hwcap: 0,
+                    hwcaps_subdir: None,
+                });
+            }
+            // A new format cache may follow, aligned to 8 bytes
+            new_start = (strings + 7) & !7;
+            if !data[new_start.min(data.len())..].starts_with(CACHEMAGIC_NEW) {
+                return Ok(LdSoCache {
+                    entries: old_entries,
+                    generator: None,
+                });
+            }
+        } else if !data.starts_with(CACHEMAGIC_NEW) {
+            return Err(format_error("Invalid ld.so.cache magic"));
+        }
+
+        // Flags byte: 2 is little endian, 3 is big endian, 0 is unknown (native)
+        reader.big_endian = data.get(new_start + 28) == Some(&3);
+        let nlibs = reader.u32(new_start + 20)? as usize;
+        let extension_offset = reader.u32(new_start + 32)? as usize;
+
+        let mut generator = None;
+        let mut hwcaps = Vec::new();
+        if extension_offset != 0 && reader.u32(extension_offset)? == CACHE_EXTENSION_MAGIC {
+            let count = reader.u32(extension_offset + 4)? as usize;
+            for i in 0..count {
+                let section = extension_offset + 8 + i * 16;
+                let tag = reader.u32(section)?;
+                let offset = reader.u32(section + 8)? as usize;
+                let size = reader.u32(section + 12)? as usize;
+                match tag {
+                    CACHE_EXTENSION_TAG_GENERATOR => {
+                        let bytes = data
+                            .get(offset..offset + size)
+                            .ok_or_else(|| format_error("Invalid ld.so.cache generator"))?;
+                        generator = Some(String::from_utf8_lossy(bytes).into_owned());
+                    }
+                    CACHE_EXTENSION_TAG_GLIBC_HWCAPS => {
+                        for j in 0..size / 4 {
+                            let string = reader.u32(offset + j * 4)? as usize;
+                            hwcaps.push(reader.string(new_start + string)?);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut entries = Vec::new();
+        for i in 0..nlibs {
+            let entry = new_start + NEW_HEADER_SIZE + i * NEW_ENTRY_SIZE;
+            let hwcap = reader.u64(entry + 16)?;
+            let hwcaps_subdir = if hwcap & DL_CACHE_HWCAP_EXTENSION != 0 {
+                hwcaps.get((hwcap & 0xffff_ffff) as usize).cloned()
+            } else {
+                None
+            };
+            entries.push(CacheEntry {
+                flags: reader.u32(entry)?,
+                name: reader.string(new_start + reader.u32(entry + 4)? as usize)?,
+                path: reader.string(new_start + reader.u32(entry + 8)? as usize)?,
+                osversion: reader.u32(entry + 12)?,
+                hwcap,
+                hwcaps_subdir,
+            });
+        }
+
+        Ok(LdSoCache { entries, generator })
+    }
+
+    /// Find the cache entry the loader would use for `name` when loading it
+    /// into an object with the given header.
+    ///
+    /// Entries from glibc-hwcaps subdirectories are only considered if their
+    /// subdirectory is listed in `hwcaps`, which is in order of preference.
+    pub fn lookup(
+        &self,
+        name: &str,
+        header: &types::FileHeader,
+        hwcaps: &[String],
+    ) -> Option<&CacheEntry> {
+        let candidates = || {
+            self.entries
+                .iter()
+                .filter(move |entry| entry.name == name && entry.matches(header))
+        };
+        hwcaps
+            .iter()
+            .find_map(|subdir| {
+                candidates().find(|entry| entry.hwcaps_subdir.as_ref() == Some(subdir))
+            })
+            .or_else(|| candidates().find(|entry| entry.hwcaps_subdir.is_none()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LdSoCache, FLAG_ELF_LIBC6, FLAG_X8664_LIB64};
+    use crate::File;
+
+    #[test]
+    fn test_new_and_compat_formats() {
+        let header = File::open_path("tests/samples/x86_64_hardened")
+            .unwrap()
+            .header;
+        for path in &[
+            "tests/samples/ld.so.cache",
+            "tests/samples/ld.so.cache.compat",
+        ] {
+            let cache = LdSoCache::open_path(path).unwrap();
+            assert_eq!(cache.entries.len(), 3);
+            assert!(cache.generator.as_ref().unwrap().starts_with("ldconfig"));
+            assert_eq!(cache.entries[0].flags, FLAG_ELF_LIBC6 | FLAG_X8664_LIB64);
+            assert_eq!(cache.entries[0].name, "libfoo.so.1");
+            assert_eq!(cache.entries[0].path, "/lib64/libfoo.so.1");
+            assert_eq!(cache.entries[1].hwcaps_subdir.as_deref(), Some("x86-64-v3"));
+
+            let entry = cache.lookup("libbar.so.2", &header, &[]).unwrap();
+            assert_eq!(entry.path, "/lib64/libbar.so.2");
+            let entry = cache
+                .lookup("libbar.so.2", &header, &["x86-64-v3".to_string()])
+                .unwrap();
+            assert_eq!(entry.path, "/usr/lib/glibc-hwcaps/x86-64-v3/libbar.so.2");
+        }
+    }
+}
@@ -17,12 +17,50 @@ pub mod types;
 #[macro_use]
 pub mod utils;
 
+pub mod abi_diff;
+pub mod archive;
+pub mod arm_exidx;
+pub mod checksec;
+pub mod compression;
+pub mod coredump;
+pub mod debug_info;
+pub mod debug_line;
+pub mod debuglink;
+#[cfg(feature = "demangle")]
+pub mod demangle;
+pub mod dependencies;
+pub mod diff;
+pub mod dwarf;
+pub mod dynamic;
+pub mod eh_frame;
+pub mod gnu_property;
+pub mod group;
+pub mod imports;
+pub mod ld_so_cache;
+pub mod linker;
+pub mod loader;
+pub mod manylinux;
+pub mod memory_usage;
+pub mod note;
+pub mod objcopy;
+pub mod relocation;
+pub mod size;
+pub mod stack_sizes;
+pub mod unwind;
+pub mod version;
+pub mod writer;
+
 /// A file in the Executable and Linkable Format (ELF) format.
+///
+/// A parsed file keeps a copy of its contents for the bytes of segments that
+/// no section covers, so it takes about twice the file size in memory.
 pub struct File {
     /// The ELF file header.
     pub header: types::FileHeader,
     pub phdrs: Vec<types::ProgramHeader>,
     pub sections: Vec<Section>,
+    /// Contents of the file it was parsed from, or None if it was built in memory
+    data: Option<Vec<u8>>,
 }
 
 impl std::fmt::Debug for File {
@@ -75,6 +113,9 @@ impl File {
         File::open_stream(&mut io_file)
     }
 
+    /// Parse a file from a stream.
+    ///
+    /// The whole stream is read and kept in the returned `File`.
     #[allow(clippy::cognitive_complexity)]
     pub fn open_stream<T: io::Read + io::Seek>(io_file: &mut T) -> Result<File, ParseError> {
         // Read the platform-independent ident bytes
@@ -133,18 +174,28 @@ impl File {
             }
         }
 
-        let _flags = read_u32!(elf_f, io_file)?;
+        elf_f.header.flags = read_u32!(elf_f, io_file)?;
         let _ehsize = read_u16!(elf_f, io_file)?;
         let _phentsize = read_u16!(elf_f, io_file)?;
-        let phnum = read_u16!(elf_f, io_file)?;
+        let mut phnum = read_u16!(elf_f, io_file)? as u32;
         let _shentsize = read_u16!(elf_f, io_file)?;
-        let shnum = read_u16!(elf_f, io_file)?;
-        let shstrndx = read_u16!(elf_f, io_file)?;
+        let mut shnum = read_u16!(elf_f, io_file)? as usize;
+        let mut shstrndx = read_u16!(elf_f, io_file)? as usize;
+
+        // Files with PN_XNUM or more program headers store the real count in
+        // the sh_info field of the initial section header.
+        if phnum == types::PN_XNUM as u32 && shoff != 0 {
+            let info_offset = match elf_f.header.class {
+                types::ElfClass::Format32 => 28,
+                types::ElfClass::Format64 => 44,
+            };
+            io_file.seek(io::SeekFrom::Start(shoff + info_offset))?;
+            phnum = read_u32!(elf_f, io_file)?;
+        }
 
         // Parse the program headers
         io_file.seek(io::SeekFrom::Start(phoff))?;
         for _ in 0..phnum {
-            let progtype: types::ProgType;
             let offset: u64;
             let vaddr: u64;
             let paddr: u64;
@@ -153,7 +204,7 @@ impl File {
             let flags: types::ProgFlag;
             let align: u64;
 
-            progtype = types::ProgType(read_u32!(elf_f, io_file)?);
+            let progtype = types::ProgType(read_u32!(elf_f, io_file)?);
             match elf_f.header.class {
                 types::ElfClass::Format32 => {
                     offset = read_u32!(elf_f, io_file)? as u64;
@@ -190,9 +241,14 @@ impl File {
         // Parse the section headers
         let mut name_idxs: Vec<u32> = Vec::new();
         io_file.seek(io::SeekFrom::Start(shoff))?;
-        for _ in 0..shnum {
+        // Files with more than SHN_LORESERVE sections store the real section count
+        // in the sh_size field of the initial section header.
+        let mut extended_shnum = shnum == 0 && shoff != 0;
+        if extended_shnum {
+            shnum = 1;
+        }
+        while elf_f.sections.len() < shnum {
             let name: String = String::new();
-            let shtype: types::SectionType;
             let flags: types::SectionFlag;
             let addr: u64;
             let offset: u64;
@@ -203,7 +259,7 @@ impl File {
             let entsize: u64;
 
             name_idxs.push(read_u32!(elf_f, io_file)?);
-            shtype = types::SectionType::try_from(read_u32!(elf_f, io_file)?).unwrap();
+            let shtype = types::SectionType::try_from(read_u32!(elf_f, io_file)?).unwrap();
             match elf_f.header.class {
                 types::ElfClass::Format32 => {
                     flags = types::SectionFlag(read_u32!(elf_f, io_file)? as u64);
@@ -242,12 +298,23 @@ impl File {
                 },
                 data: Vec::new(),
             });
+
+            if extended_shnum {
+                shnum = size as usize;
+                extended_shnum = false;
+            }
+        }
+
+        // The real string table index is in sh_link of the initial section header
+        // when it does not fit in e_shstrndx.
+        if shstrndx == types::SHN_XINDEX as usize && !elf_f.sections.is_empty() {
+            shstrndx = elf_f.sections[0].shdr.link as usize;
         }
 
         // Read the section data
         let mut s_i: usize = 0;
         loop {
-            if s_i == shnum as usize {
+            if s_i == shnum {
                 break;
             }
 
@@ -266,18 +333,22 @@ impl File {
         // Parse the section names from the string header string table
         s_i = 0;
         loop {
-            if s_i == shnum as usize {
+            if s_i == shnum {
                 break;
             }
 
-            elf_f.sections[s_i].shdr.name = utils::get_string(
-                &elf_f.sections[shstrndx as usize].data,
-                name_idxs[s_i] as usize,
-            )?;
+            elf_f.sections[s_i].shdr.name =
+                utils::get_string(&elf_f.sections[shstrndx].data, name_idxs[s_i] as usize)?;
 
             s_i += 1;
         }
 
+        // Keep the file contents for the bytes of segments outside of sections
+        let mut data = Vec::new();
+        io_file.seek(io::SeekFrom::Start(0))?;
+        io_file.read_to_end(&mut data)?;
+        elf_f.data = Some(data);
+
         Ok(elf_f)
     }
 
@@ -287,9 +358,10 @@ impl File {
             || section.shdr.shtype == types::SectionType::Dynsym
         {
             let link = &self.sections[section.shdr.link as usize].data;
+            let xindex = self.get_symtab_shndx(section)?;
             let mut io_section = io::Cursor::new(&section.data);
             while (io_section.position() as usize) < section.data.len() {
-                self.parse_symbol(&mut io_section, &mut symbols, link)?;
+                self.parse_symbol(&mut io_section, &mut symbols, link, &xindex)?;
             }
         }
         Ok(symbols)
@@ -300,6 +372,7 @@ impl File {
         io_section: &mut dyn Read,
         symbols: &mut Vec<types::Symbol>,
         link: &[u8],
+        xindex: &[u32],
     ) -> Result<(), ParseError> {
         let name: u32;
         let value: u64;
@@ -327,6 +400,19 @@ impl File {
             }
         }
 
+        let shndx = if shndx == types::SHN_XINDEX {
+            match xindex.get(symbols.len()) {
+                Some(&index) => types::SectionIndex::Index(index),
+                None => {
+                    return Err(ParseError::InvalidFormat(Some(
+                        "Missing SHT_SYMTAB_SHNDX entry for SHN_XINDEX symbol".to_string(),
+                    )))
+                }
+            }
+        } else {
+            types::SectionIndex::from(shndx)
+        };
+
         symbols.push(types::Symbol {
             name: utils::get_string(link, name as usize)?,
             value,
@@ -339,6 +425,126 @@ impl File {
         Ok(())
     }
 
+    /// Read the SHT_SYMTAB_SHNDX table associated with a symbol table, if any.
+    fn get_symtab_shndx(&self, symtab: &Section) -> Result<Vec<u32>, ParseError> {
+        let mut table = Vec::new();
+        let symtab_index = match self.section_index(symtab) {
+            Some(index) => index,
+            None => return Ok(table),
+        };
+        let section = self.sections.iter().find(|section| {
+            section.shdr.shtype == types::SectionType::SymtabShndx
+                && section.shdr.link as usize == symtab_index
+        });
+        if let Some(section) = section {
+            let mut io_section = io::Cursor::new(&section.data);
+            while (io_section.position() as usize) < section.data.len() {
+                table.push(read_u32!(self, io_section)?);
+            }
+        }
+        Ok(table)
+    }
+
+    /// Get the section a symbol is defined in.
+    ///
+    /// Returns None for symbols that are undefined, absolute, common or that
+    /// refer to a reserved section index; see [types::Symbol::shndx] to tell
+    /// those apart.
+    pub fn get_symbol_section(&self, symbol: &types::Symbol) -> Option<&Section> {
+        match symbol.shndx {
+            types::SectionIndex::Index(index) => self.sections.get(index as usize),
+            _ => None,
+        }
+    }
+
+    /// Get the file contents of a segment.
+    ///
+    /// For parsed files, the contents are read from the file, truncated at
+    /// the end of the file. For files built in memory, they are reassembled
+    /// from the data of the sections that lie within the segment's file
+    /// range, and bytes not covered by any section are returned as zeroes.
+    pub fn get_segment_data(&self, phdr: &types::ProgramHeader) -> Vec<u8> {
+        if let Some(data) = self.get_segment_bytes(phdr) {
+            return data.to_vec();
+        }
+        // Bound the segment by the extent of the section data
+        let size = self
+            .sections
+            .iter()
+            .filter(|section| section.shdr.shtype != types::SectionType::Nobits)
+            .map(|section| {
+                section
+                    .shdr
+                    .offset
+                    .saturating_add(section.data.len() as u64)
+            })
+            .max()
+            .unwrap_or(0);
+        let start = phdr.offset.min(size);
+        let end = phdr.offset.saturating_add(phdr.filesz).min(size);
+        let mut data = vec![0u8; (end - start) as usize];
+        for section in self.sections.iter() {
+            if section.shdr.shtype == types::SectionType::Nobits
+                || section.shdr.shtype == types::SectionType::Null
+            {
+                continue;
+            }
+            let s_start = section.shdr.offset.max(start);
+            let s_end = section
+                .shdr
+                .offset
+                .saturating_add(section.data.len() as u64)
+                .min(end);
+            if s_start >= s_end {
+                continue;
+            }
+            let src = (s_start - section.shdr.offset) as usize;
+            let dst = (s_start - start) as usize;
+            let len = (s_end - s_start) as usize;
+            data[dst..dst + len].copy_from_slice(&section.data[src..src + len]);
+        }
+        data
+    }
+
+    /// Get the file contents of a segment without copying them.
+    ///
+    /// Returns None for files built in memory, which have no file contents.
+    pub(crate) fn get_segment_bytes(&self, phdr: &types::ProgramHeader) -> Option<&[u8]> {
+        let data = self.data.as_ref()?;
+        let start = usize::try_from(phdr.offset)
+            .unwrap_or(usize::MAX)
+            .min(data.len());
+        let size = usize::try_from(phdr.filesz).unwrap_or(usize::MAX);
+        let end = start.saturating_add(size).min(data.len());
+        Some(&data[start..end])
+    }
+
+    /// Get the load address (LMA) of an allocated section.
+    ///
+    /// This is the section address translated from the `vaddr` to the `paddr`
+    /// of the PT_LOAD segment containing it, or the section address itself if
+    /// no segment contains it.
+    /// Get the contents of the file it was parsed from.
+    pub(crate) fn raw_data(&self) -> Option<&[u8]> {
+        self.data.as_deref()
+    }
+
+    pub fn get_section_lma(&self, section: &Section) -> u64 {
+        let addr = section.shdr.addr;
+        self.phdrs
+            .iter()
+            .filter(|phdr| phdr.progtype == types::PT_LOAD)
+            .find(|phdr| addr >= phdr.vaddr && addr < phdr.vaddr + phdr.memsz)
+            .map_or(addr, |phdr| addr - phdr.vaddr + phdr.paddr)
+    }
+
+    /// Find the index of a section that belongs to this file in the section header table.
+    pub(crate) fn section_index(&self, section: &Section) -> Option<usize> {
+        self.sections
+            .iter()
+            .position(|candidate| std::ptr::eq(candidate, section))
+    }
+
     pub fn get_section<T: AsRef<str>>(&self, name: T) -> Option<&Section> {
         self.sections
             .iter()
@@ -355,13 +561,21 @@ impl File {
                 osabi: ELFOSABI_NONE,
                 abiversion: 0,
                 entry: 0,
+                flags: 0,
             },
             phdrs: Vec::new(),
             sections: Vec::new(),
+            data: None,
         }
     }
 }
 
+impl Default for File {
+    fn default() -> Self {
+        File::new()
+    }
+}
+
 #[derive(Debug)]
 pub struct Section {
     pub shdr: types::SectionHeader,
@@ -376,8 +590,11 @@ impl std::fmt::Display for Section {
 
 #[cfg(test)]
 mod tests {
-    use crate::types::{ElfClass, ElfEndianness, ElfFileType};
-    use crate::File;
+    use crate::types::{
+        ElfClass, ElfEndianness, ElfFileType, SectionFlag, SectionHeader, SectionIndex,
+        SectionType, PT_LOAD, SHN_XINDEX,
+    };
+    use crate::{File, Section};
     use std::path::PathBuf;
 
     #[test]
@@ -399,4 +616,109 @@ mod tests {
         //assert_eq!(".bss", bss.shdr.name);
         //assert!(bss.data.iter().all(|&b| b == 0));
     }
+
+    #[test]
+    fn test_symbol_section_index() {
+        let file = File::open_path(PathBuf::from("tests/samples/comdat.o")).unwrap();
+        let symtab = file.get_section(".symtab").expect("Get .symtab section");
+        let symbols = file.get_symbols(symtab).unwrap();
+        assert_eq!(symbols[0].shndx, SectionIndex::Undefined);
+        assert_eq!(symbols[1].shndx, SectionIndex::Absolute);
+
+        let use_it = symbols.iter().find(|sym| sym.name == "_Z6use_iti").unwrap();
+        assert_eq!(use_it.shndx, SectionIndex::Index(3));
+        let text = file
+            .get_symbol_section(use_it)
+            .expect("Get defining section");
+        assert_eq!(text.shdr.name, ".text");
+        assert!(file.get_symbol_section(&symbols[1]).is_none());
+    }
+
+    #[test]
+    fn test_extended_section_numbering() {
+        let section = |name: &str, shtype, link, info, data: Vec<u8>| Section {
+            shdr: SectionHeader {
+                name: name.to_string(),
+                shtype,
+                flags: SectionFlag(0),
+                addr: 0,
+                offset: 0,
+                size: data.len() as u64,
+                link,
+                info,
+                addralign: 1,
+                entsize: 0,
+            },
+            data,
+        };
+        let mut file = File::open_path(PathBuf::from("tests/samples/comdat.o")).unwrap();
+        file.sections = vec![section("", SectionType::Null, 0, 0, Vec::new())];
+        while file.sections.len() < 0xff10 {
+            file.sections
+                .push(section("s", SectionType::Progbits, 0, 0, Vec::new()));
+        }
+        // .data, .symtab, .symtab_shndx, .strtab and .shstrtab all have
+        // indices that do not fit in 16 bits
+        let data = file.sections.len() as u32;
+        let mut symtab = vec![0u8; 48];
+        symtab[24..28].copy_from_slice(&1u32.to_le_bytes());
+        // STB_GLOBAL, STT_OBJECT
+        symtab[28] = 0x11;
+        symtab[30..32].copy_from_slice(&SHN_XINDEX.to_le_bytes());
+        let mut shndx = vec![0u8; 8];
+        shndx[4..8].copy_from_slice(&data.to_le_bytes());
+        file.sections.extend(vec![
+            section(".data", SectionType::Progbits, 0, 0, vec![42]),
+            section(".symtab", SectionType::Symtab, data + 3, 1, symtab),
+            section(
+                ".symtab_shndx",
+                SectionType::SymtabShndx,
+                data + 1,
+                0,
+                shndx,
+            ),
+            section(".strtab", SectionType::Strtab, 0, 0, b"\0last\0".to_vec()),
+            section(".shstrtab", SectionType::Strtab, 0, 0, Vec::new()),
+        ]);
+        let mut image = std::io::Cursor::new(Vec::new());
+        file.write_stream(&mut image).unwrap();
+        let image = image.into_inner();
+        // e_shnum and e_shstrndx
+        assert_eq!(image[60..64], [0, 0, 0xff, 0xff]);
+
+        let file = File::open_stream(&mut std::io::Cursor::new(&image)).unwrap();
+        assert_eq!(file.sections.len(), data as usize + 5);
+        assert_eq!(file.sections[0xff00].shdr.name, "s");
+        let symtab = file.get_section(".symtab").unwrap();
+        let symbols = file.get_symbols(symtab).unwrap();
+        assert_eq!(symbols[1].name, "last");
+        assert_eq!(symbols[1].shndx, SectionIndex::Index(data));
+        let section = file.get_symbol_section(&symbols[1]).unwrap();
+        assert_eq!(section.shdr.name, ".data");
+        assert_eq!(section.data, [42]);
+    }
+
+    #[test]
+    fn test_segment_data() {
+        let file = File::open_path(PathBuf::from("tests/samples/x86_64_nosections")).unwrap();
+        assert!(file.sections.is_empty());
+        let mut phdr = file
+            .phdrs
+            .iter()
+            .find(|phdr| phdr.progtype == PT_LOAD)
+            .copied()
+            .unwrap();
+        let data = file.get_segment_data(&phdr);
+        assert_eq!(data.len() as u64, phdr.filesz);
+        assert_eq!(&data[..4], b"\x7fELF");
+
+        // Sizes past the end of the file are truncated
+        phdr.filesz = u64::MAX;
+        let size = std::fs::metadata("tests/samples/x86_64_nosections")
+            .unwrap()
+            .len();
+        assert_eq!(file.get_segment_data(&phdr).len() as u64, size);
+        phdr.offset = u64::MAX;
+        assert!(file.get_segment_data(&phdr).is_empty());
+    }
 }
@@ -0,0 +1,804 @@
+use crate::types;
+use crate::{File, ParseError, Section};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::io;
+
+/// Options for [link].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkOptions {
+    /// Address of the first output section. Must be page aligned.
+    pub base_address: u64,
+    /// Page size used to align segments
+    pub page_size: u64,
+    /// Name of the entry point symbol
+    pub entry: String,
+    /// Output sections to place first, in this order. Other output sections
+    /// follow in the order they are first seen in the inputs.
+    pub section_order: Vec<String>,
+}
+
+impl Default for LinkOptions {
+    fn default() -> Self {
+        LinkOptions {
+            base_address: 0x40_0000,
+            page_size: 0x1000,
+            entry: "_start".to_string(),
+            section_order: vec![
+                ".text".to_string(),
+                ".rodata".to_string(),
+                ".data".to_string(),
+                ".bss".to_string(),
+            ],
+        }
+    }
+}
+
+/// Input sections whose names start with one of these prefixes followed by
+/// a dot are merged into the output section with the prefix as its name,
+/// so that `-ffunction-sections` and `-fdata-sections` output is combined.
+const MERGED_PREFIXES: &[&str] = &[
+    ".text",
+    ".rodata",
+    ".data",
+    ".bss",
+    ".init_array",
+    ".fini_array",
+];
+
+fn output_section_name(name: &str) -> &str {
+    MERGED_PREFIXES
+        .iter()
+        .find(|prefix| {
+            name == **prefix
+                || (name.starts_with(**prefix) && name.as_bytes()[prefix.len()] == b'.')
+        })
+        .copied()
+        .unwrap_or(name)
+}
+
+/// Section index of the output section at `position`, which follows the null section
+fn output_index(position: usize) -> Result<u16, ParseError> {
+    u16::try_from(position + 1)
+        .ok()
+        .filter(|&index| index < types::SHN_LORESERVE)
+        .ok_or_else(|| {
+            ParseError::InvalidFormat(Some(format!(
+                "Output section index {} is in the reserved range",
+                position + 1
+            )))
+        })
+}
+
+fn align_up(value: u64, align: u64) -> u64 {
+    if align <= 1 {
+        value
+    } else {
+        value.div_ceil(align) * align
+    }
+}
+
+/// An output section being laid out
+struct OutputSection {
+    name: String,
+    shtype: types::SectionType,
+    flags: u64,
+    align: u64,
+    /// (object index, section index, offset in the output section)
+    inputs: Vec<(usize, usize, u64)>,
+    size: u64,
+    addr: u64,
+}
+
+impl OutputSection {
+    fn prog_flags(&self) -> types::ProgFlag {
+        let mut flags = types::PF_R.0;
+        if self.flags & types::SHF_WRITE.0 != 0 {
+            flags |= types::PF_W.0;
+        }
+        if self.flags & types::SHF_EXECINSTR.0 != 0 {
+            flags |= types::PF_X.0;
+        }
+        types::ProgFlag(flags)
+    }
+}
+
+/// Where a global symbol is defined
+#[derive(Clone, Debug)]
+enum Definition {
+    /// Defined in a section of an input object
+    Section(usize, usize, u64),
+    /// Absolute value
+    Absolute(u64),
+    /// Common symbol with size and alignment, allocated in .bss
+    Common(u64, u64),
+}
+
+#[derive(Clone, Debug)]
+struct GlobalSymbol {
+    definition: Definition,
+    weak: bool,
+    symtype: types::SymbolType,
+    size: u64,
+}
+
+/// Link relocatable objects into a static executable.
+///
+/// Allocated sections of all inputs are merged by name into output
+/// sections, which are placed from [LinkOptions::base_address] on and
+/// grouped into one PT_LOAD segment per run of sections with the same
+/// permissions. Only the first instance of each COMDAT group is kept.
+/// Global symbols are resolved across objects, with strong definitions
+/// taking precedence over weak and common ones; undefined weak symbols
+/// resolve to zero. Common symbols are allocated in `.bss`.
+///
+/// x86_64 and AArch64 objects are supported. Code must not need a GOT or
+/// PLT, so x86_64 objects should be compiled without `-fpic`. If the entry
+/// symbol is not defined, the entry point is the start of the first
+/// executable section. The output has a `.symtab` with the global symbols.
+pub fn link(objects: &[File], options: &LinkOptions) -> Result<File, ParseError> {
+    let first = objects.first().ok_or(ParseError::InvalidFormat(Some(
+        "No input objects".to_string(),
+    )))?;
+    for object in objects {
+        if object.header.elftype != types::ElfFileType::RelocatableObject {
+            return Err(ParseError::InvalidFormat(Some(format!(
+                "Cannot link {} files",
+                object.header.elftype
+            ))));
+        }
+        if object.header.class != first.header.class
+            || object.header.endianness != first.header.endianness
+            || object.header.cpu_architecture != first.header.cpu_architecture
+        {
+            return Err(ParseError::InvalidFormat(Some(
+                "Input objects are for different targets".to_string(),
+            )));
+        }
+    }
+    match first.header.cpu_architecture {
+        types::ElfCpuArchitecture::EM_X86_64 | types::ElfCpuArchitecture::EM_AARCH64 => {}
+        machine => {
+            return Err(ParseError::InvalidFormat(Some(format!(
+                "Cannot link {} objects",
+                machine
+            ))))
+        }
+    }
+    let page_size = options.page_size;
+    if page_size == 0
+        || !page_size.is_power_of_two()
+        || !options.base_address.is_multiple_of(page_size)
+    {
+        return Err(ParseError::InvalidFormat(Some(
+            "Base address and page size must be page aligned powers of two".to_string(),
+        )));
+    }
+
+    let discarded = discarded_sections(objects)?;
+    let kept = |object: usize, index: usize| -> bool {
+        let section = &objects[object].sections[index];
+        section.shdr.flags.0 & types::SHF_ALLOC.0 != 0
+            && matches!(
+                section.shdr.shtype,
+                types::SectionType::Progbits
+                    | types::SectionType::Nobits
+                    | types::SectionType::InitArray
+                    | types::SectionType::FiniArray
+                    | types::SectionType::PreinitArray
+            )
+            && !discarded.contains(&(object, index))
+    };
+
+    // Merge input sections into output sections
+    let mut outputs: Vec<OutputSection> = Vec::new();
+    for (object_index, object) in objects.iter().enumerate() {
+        for (index, section) in object.sections.iter().enumerate() {
+            if !kept(object_index, index) {
+                continue;
+            }
+            if section.shdr.flags.0 & types::SHF_TLS.0 != 0 {
+                return Err(ParseError::InvalidFormat(Some(format!(
+                    "Thread-local section {} is not supported",
+                    section.shdr.name
+                ))));
+            }
+            let name = output_section_name(&section.shdr.name);
+            let output = match outputs.iter().position(|output| output.name == name) {
+                Some(position) => &mut outputs[position],
+                None => {
+                    outputs.push(OutputSection {
+                        name: name.to_string(),
+                        shtype: section.shdr.shtype,
+                        flags: 0,
+                        align: 1,
+                        inputs: Vec::new(),
+                        size: 0,
+                        addr: 0,
+                    });
+                    outputs.last_mut().unwrap()
+                }
+            };
+            if output.shtype == types::SectionType::Nobits {
+                output.shtype = section.shdr.shtype;
+            }
+            output.flags |= section.shdr.flags.0
+                & (types::SHF_WRITE.0 | types::SHF_ALLOC.0 | types::SHF_EXECINSTR.0);
+            let align = section.shdr.addralign.max(1);
+            output.align = output.align.max(align);
+            let offset = align_up(output.size, align);
+            output.inputs.push((object_index, index, offset));
+            output.size = offset + section.shdr.size;
+        }
+    }
+
+    let globals = resolve_symbols(objects, &kept)?;
+
+    // Allocate common symbols at the end of .bss
+    let mut commons: Vec<(String, u64, u64)> = globals
+        .iter()
+        .filter_map(|(name, symbol)| match symbol.definition {
+            Definition::Common(size, align) => Some((name.clone(), size, align)),
+            _ => None,
+        })
+        .collect();
+    commons.sort();
+    let mut common_offsets = HashMap::new();
+    if !commons.is_empty() {
+        let bss = match outputs.iter().position(|output| output.name == ".bss") {
+            Some(position) => &mut outputs[position],
+            None => {
+                outputs.push(OutputSection {
+                    name: ".bss".to_string(),
+                    shtype: types::SectionType::Nobits,
+                    flags: types::SHF_ALLOC.0 | types::SHF_WRITE.0,
+                    align: 1,
+                    inputs: Vec::new(),
+                    size: 0,
+                    addr: 0,
+                });
+                outputs.last_mut().unwrap()
+            }
+        };
+        for (name, size, align) in commons {
+            let offset = align_up(bss.size, align);
+            bss.align = bss.align.max(align);
+            bss.size = offset + size;
+            common_offsets.insert(name, offset);
+        }
+    }
+
+    // Order and place output sections
+    let rank = |output: &OutputSection| {
+        options
+            .section_order
+            .iter()
+            .position(|name| *name == output.name)
+            .unwrap_or(options.section_order.len())
+    };
+    outputs.sort_by_key(|output| rank(output));
+    let mut address = options.base_address;
+    let mut segments: Vec<(types::ProgFlag, Vec<usize>)> = Vec::new();
+    for (index, output) in outputs.iter_mut().enumerate() {
+        let flags = output.prog_flags();
+        match segments.last_mut() {
+            Some((segment_flags, members)) if *segment_flags == flags => members.push(index),
+            last => {
+                if last.is_some() {
+                    address = align_up(address, page_size);
+                }
+                segments.push((flags, vec![index]));
+            }
+        }
+        output.addr = align_up(address, output.align);
+        address = output.addr + output.size;
+    }
+
+    let section_addresses: HashMap<(usize, usize), u64> = outputs
+        .iter()
+        .flat_map(|output| {
+            output
+                .inputs
+                .iter()
+                .map(move |&(object, index, offset)| ((object, index), output.addr + offset))
+        })
+        .collect();
+    let bss_addr = outputs
+        .iter()
+        .find(|output| output.name == ".bss")
+        .map_or(0, |output| output.addr);
+    let global_address = |symbol: &GlobalSymbol, name: &str| -> u64 {
+        match symbol.definition {
+            Definition::Section(object, index, value) => {
+                section_addresses[&(object, index)] + value
+            }
+            Definition::Absolute(value) => value,
+            Definition::Common(..) => bss_addr + common_offsets[name],
+        }
+    };
+    let addresses: HashMap<String, u64> = globals
+        .iter()
+        .map(|(name, symbol)| (name.clone(), global_address(symbol, name)))
+        .collect();
+
+    // Build the output file
+    let mut out = File::new();
+    out.header = first.header;
+    out.header.elftype = types::ElfFileType::Executable;
+    out.header.entry = match addresses.get(&options.entry) {
+        Some(&entry) => entry,
+        None => outputs
+            .iter()
+            .find(|output| output.flags & types::SHF_EXECINSTR.0 != 0)
+            .map_or(options.base_address, |output| output.addr),
+    };
+
+    let (ehsize, phentsize) = match out.header.class {
+        types::ElfClass::Format32 => (52, 32),
+        types::ElfClass::Format64 => (64, 56),
+    };
+    let headers_size = align_up(ehsize + phentsize * (segments.len() as u64 + 1), page_size);
+    let file_offset = |address: u64| address - options.base_address + headers_size;
+
+    out.sections.push(null_section());
+    for output in outputs.iter() {
+        let mut data = Vec::new();
+        if output.shtype != types::SectionType::Nobits {
+            data = vec![0u8; output.size as usize];
+            for &(object_index, index, offset) in output.inputs.iter() {
+                let object = &objects[object_index];
+                let section = &object.sections[index];
+                if section.shdr.shtype == types::SectionType::Nobits {
+                    continue;
+                }
+                let contents = object.relocate_section(
+                    section,
+                    output.addr + offset,
+                    |symbol| -> Result<u64, ParseError> {
+                        if symbol.bind == types::STB_LOCAL {
+                            return match symbol.shndx {
+                                types::SectionIndex::Index(index) => section_addresses
+                                    .get(&(object_index, index as usize))
+                                    .map(|address| address + symbol.value)
+                                    .ok_or_else(|| {
+                                        ParseError::InvalidFormat(Some(format!(
+                                            "Relocation in {} refers to {} in a discarded section",
+                                            section.shdr.name, symbol.name
+                                        )))
+                                    }),
+                                types::SectionIndex::Absolute => Ok(symbol.value),
+                                _ => Ok(0),
+                            };
+                        }
+                        Ok(addresses.get(&symbol.name).copied().unwrap_or(0))
+                    },
+                )?;
+                let start = offset as usize;
+                data[start..start + contents.len()].copy_from_slice(&contents);
+            }
+        }
+        out.sections.push(Section {
+            shdr: types::SectionHeader {
+                name: output.name.clone(),
+                shtype: output.shtype,
+                flags: types::SectionFlag(output.flags),
+                addr: output.addr,
+                offset: file_offset(output.addr),
+                size: output.size,
+                link: 0,
+                info: 0,
+                addralign: output.align,
+                entsize: 0,
+            },
+            data,
+        });
+    }
+
+    for (flags, members) in segments.iter() {
+        let start = outputs[members[0]].addr;
+        let end = members
+            .iter()
+            .map(|&index| outputs[index].addr + outputs[index].size)
+            .max()
+            .unwrap_or(start);
+        let file_end = members
+            .iter()
+            .filter(|&&index| outputs[index].shtype != types::SectionType::Nobits)
+            .map(|&index| outputs[index].addr + outputs[index].size)
+            .max()
+            .unwrap_or(start);
+        out.phdrs.push(types::ProgramHeader {
+            progtype: types::PT_LOAD,
+            offset: file_offset(start),
+            vaddr: start,
+            paddr: start,
+            filesz: file_end - start,
+            memsz: end - start,
+            flags: *flags,
+            align: page_size,
+        });
+    }
+    out.phdrs.push(types::ProgramHeader {
+        progtype: types::PT_GNU_STACK,
+        offset: 0,
+        vaddr: 0,
+        paddr: 0,
+        filesz: 0,
+        memsz: 0,
+        flags: types::ProgFlag(types::PF_R.0 | types::PF_W.0),
+        align: 16,
+    });
+
+    // Symbol table with the global symbols
+    let mut names: Vec<&String> = globals.keys().collect();
+    names.sort_by_key(|name| (addresses[*name], (*name).clone()));
+    let mut strtab = vec![0u8];
+    let mut symtab = io::Cursor::new(Vec::new());
+    out.write_symbol(&mut symtab, 0, 0, 0, 0, 0)?;
+    for name in names {
+        let symbol = &globals[name];
+        let shndx = match symbol.definition {
+            Definition::Section(object, index, _) => outputs
+                .iter()
+                .position(|output| {
+                    output
+                        .inputs
+                        .iter()
+                        .any(|&(o, i, _)| o == object && i == index)
+                })
+                .map_or(Ok(types::SHN_ABS), output_index)?,
+            Definition::Absolute(_) => types::SHN_ABS,
+            Definition::Common(..) => outputs
+                .iter()
+                .position(|output| output.name == ".bss")
+                .map_or(Ok(types::SHN_ABS), output_index)?,
+        };
+        let bind = if symbol.weak {
+            types::STB_WEAK
+        } else {
+            types::STB_GLOBAL
+        };
+        let symtype = if symbol.symtype == types::STT_COMMON {
+            types::STT_OBJECT
+        } else {
+            symbol.symtype
+        };
+        let name_offset = strtab.len() as u32;
+        strtab.extend_from_slice(name.as_bytes());
+        strtab.push(0);
+        out.write_symbol(
+            &mut symtab,
+            name_offset,
+            (bind.0 << 4) | symtype.0,
+            shndx,
+            addresses[name],
+            symbol.size,
+        )?;
+    }
+    let symtab_index = out.sections.len();
+    let entsize = match out.header.class {
+        types::ElfClass::Format32 => 16,
+        types::ElfClass::Format64 => 24,
+    };
+    out.sections.push(Section {
+        shdr: types::SectionHeader {
+            name: ".symtab".to_string(),
+            shtype: types::SectionType::Symtab,
+            flags: types::SHF_NONE,
+            addr: 0,
+            offset: 0,
+            size: symtab.get_ref().len() as u64,
+            link: symtab_index as u32 + 1,
+            info: 1,
+            addralign: 8,
+            entsize,
+        },
+        data: symtab.into_inner(),
+    });
+    out.sections.push(Section {
+        shdr: types::SectionHeader {
+            name: ".strtab".to_string(),
+            shtype: types::SectionType::Strtab,
+            flags: types::SHF_NONE,
+            addr: 0,
+            offset: 0,
+            size: strtab.len() as u64,
+            link: 0,
+            info: 0,
+            addralign: 1,
+            entsize: 0,
+        },
+        data: strtab,
+    });
+    out.sections.push(Section {
+        shdr: types::SectionHeader {
+            name: ".shstrtab".to_string(),
+            shtype: types::SectionType::Strtab,
+            ..null_section().shdr
+        },
+        data: Vec::new(),
+    });
+
+    Ok(out)
+}
+
+fn null_section() -> Section {
+    Section {
+        shdr: types::SectionHeader {
+            name: String::new(),
+            shtype: types::SectionType::Null,
+            flags: types::SHF_NONE,
+            addr: 0,
+            offset: 0,
+            size: 0,
+            link: 0,
+            info: 0,
+            addralign: 0,
+            entsize: 0,
+        },
+        data: Vec::new(),
+    }
+}
+
+/// Find the members of COMDAT groups whose signature was already seen in an earlier object
+fn discarded_sections(objects: &[File]) -> Result<HashSet<(usize, usize)>, ParseError> {
+    let mut signatures = HashSet::new();
+    let mut discarded = HashSet::new();
+    for (object_index, object) in objects.iter().enumerate() {
+        for group in object.get_section_groups()? {
+            if !group.flags.is_comdat() {
+                continue;
+            }
+            if !signatures.insert(group.signature.name.clone()) {
+                discarded.extend(
+                    group
+                        .members
+                        .iter()
+                        .map(|&member| (object_index, member as usize)),
+                );
+            }
+        }
+    }
+    Ok(discarded)
+}
+
+/// Build the global symbol table, reporting duplicate and missing definitions
+fn resolve_symbols<F>(
+    objects: &[File],
+    kept: &F,
+) -> Result<HashMap<String, GlobalSymbol>, ParseError>
+where
+    F: Fn(usize, usize) -> bool,
+{
+    let mut globals: HashMap<String, GlobalSymbol> = HashMap::new();
+    // Undefined references, and whether all of them are weak
+    let mut references: HashMap<String, bool> = HashMap::new();
+
+    for (object_index, object) in objects.iter().enumerate() {
+        let symtab = match object
+            .sections
+            .iter()
+            .find(|section| section.shdr.shtype == types::SectionType::Symtab)
+        {
+            Some(symtab) => symtab,
+            None => continue,
+        };
+        for symbol in object.get_symbols(symtab)? {
+            if symbol.bind == types::STB_LOCAL || symbol.name.is_empty() {
+                continue;
+            }
+            let weak = symbol.bind == types::STB_WEAK;
+            let definition = match symbol.shndx {
+                types::SectionIndex::Index(index) if kept(object_index, index as usize) => {
+                    Definition::Section(object_index, index as usize, symbol.value)
+                }
+                types::SectionIndex::Absolute => Definition::Absolute(symbol.value),
+                types::SectionIndex::Common => Definition::Common(symbol.size, symbol.value.max(1)),
+                _ => {
+                    let all_weak = references.entry(symbol.name.clone()).or_insert(true);
+                    *all_weak &= weak;
+                    continue;
+                }
+            };
+            let new = GlobalSymbol {
+                definition,
+                weak,
+                symtype: symbol.symtype,
+                size: symbol.size,
+            };
+
+            let existing = match globals.get_mut(&symbol.name) {
+                Some(existing) => existing,
+                None => {
+                    globals.insert(symbol.name.clone(), new);
+                    continue;
+                }
+            };
+            let existing_common = matches!(existing.definition, Definition::Common(..));
+            let new_common = matches!(new.definition, Definition::Common(..));
+            if let (Definition::Common(size, align), Definition::Common(new_size, new_align)) =
+                (existing.definition.clone(), &new.definition)
+            {
+                existing.definition =
+                    Definition::Common(size.max(*new_size), align.max(*new_align));
+                existing.size = size.max(*new_size);
+            } else if new_common {
+                // A definition takes precedence over common symbols
+            } else if existing.weak || existing_common {
+                if !new.weak || existing_common {
+                    *existing = new;
+                }
+            } else if !new.weak {
+                return Err(ParseError::InvalidFormat(Some(format!(
+                    "Multiple definition of {}",
+                    symbol.name
+                ))));
+            }
+        }
+    }
+
+    for (name, all_weak) in references {
+        if !all_weak && !globals.contains_key(&name) {
+            return Err(ParseError::InvalidFormat(Some(format!(
+                "Undefined symbol {}",
+                name
+            ))));
+        }
+    }
+    Ok(globals)
+}
+
+impl File {
+    /// Append a symbol table entry in this file's format
+    fn write_symbol<T: io::Write>(
+        &self,
+        io_symtab: &mut T,
+        name: u32,
+        info: u8,
+        shndx: u16,
+        value: u64,
+        size: u64,
+    ) -> Result<(), ParseError> {
+        match self.header.class {
+            types::ElfClass::Format32 => {
+                write_u32!(self, io_symtab, name)?;
+                write_u32!(self, io_symtab, value as u32)?;
+                write_u32!(self, io_symtab, size as u32)?;
+                io_symtab.write_all(&[info, 0])?;
+                write_u16!(self, io_symtab, shndx)?;
+            }
+            types::ElfClass::Format64 => {
+                write_u32!(self, io_symtab, name)?;
+                io_symtab.write_all(&[info, 0])?;
+                write_u16!(self, io_symtab, shndx)?;
+                write_u64!(self, io_symtab, value)?;
+                write_u64!(self, io_symtab, size)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{link, output_index, LinkOptions};
+    use crate::File;
+    use std::path::PathBuf;
+
+    fn open(name: &str) -> File {
+        File::open_path(PathBuf::from("tests/samples").join(name)).unwrap()
+    }
+
+    fn symbol(file: &File, name: &str) -> u64 {
+        let symtab = file.get_section(".symtab").unwrap();
+        file.get_symbols(symtab)
+            .unwrap()
+            .into_iter()
+            .find(|symbol| symbol.name == name)
+            .unwrap()
+            .value
+    }
+
+    #[test]
+    fn test_link_x86_64() {
+        let objects = vec![open("link_a.o"), open("link_b.o")];
+        let out = link(&objects, &LinkOptions::default()).unwrap();
+
+        let start = symbol(&out, "_start");
+        assert_eq!(start, 0x40_0000);
+        assert_eq!(out.header.entry, start);
+        // The strong definition in link_b.o wins over the weak one in link_a.o
+        let weak_default = symbol(&out, "weak_default");
+        assert!(weak_default > symbol(&out, "helper"));
+        // Only the first copy of the COMDAT function is kept
+        let text = out.get_section(".text").unwrap();
+        assert_eq!(text.data.iter().filter(|&&b| b == 0xb8).count(), 2);
+
+        // call helper
+        let helper = symbol(&out, "helper");
+        let rel = i32::from_le_bytes([text.data[1], text.data[2], text.data[3], text.data[4]]);
+        assert_eq!(start as i64 + 5 + rel as i64, helper as i64);
+
+        // Missing weak symbol resolves to zero, and common symbols land in .bss
+        let data = out.get_section(".data").unwrap();
+        assert_eq!(data.data[0..8], [0; 8]);
+        let bss = out.get_section(".bss").unwrap();
+        let buffer = symbol(&out, "buffer");
+        assert!(buffer >= bss.shdr.addr && buffer + 64 <= bss.shdr.addr + bss.shdr.size);
+        assert_eq!(buffer % 16, 0);
+
+        // Text, rodata and data+bss segments
+        assert_eq!(out.phdrs.len(), 4);
+        assert_eq!(
+            out.phdrs[2].memsz,
+            bss.shdr.addr + bss.shdr.size - data.shdr.addr
+        );
+
+        // The written image maps the code at its address
+        let mut image = std::io::Cursor::new(Vec::new());
+        out.write_stream(&mut image).unwrap();
+        let image = image.into_inner();
+        let written = File::open_stream(&mut std::io::Cursor::new(&image)).unwrap();
+        assert_eq!(written.header.entry, start);
+        assert_eq!(written.phdrs, out.phdrs);
+        let phdr = written
+            .phdrs
+            .iter()
+            .find(|phdr| phdr.vaddr <= start && start < phdr.vaddr + phdr.filesz)
+            .unwrap();
+        let offset = (start - phdr.vaddr + phdr.offset) as usize;
+        assert_eq!(image[offset..offset + text.data.len()], text.data[..]);
+    }
+
+    #[test]
+    fn test_link_aarch64() {
+        let options = LinkOptions {
+            base_address: 0x8_0000,
+            ..Default::default()
+        };
+        let out = link(&[open("link_arm64.o")], &options).unwrap();
+        let text = out.get_section(".text").unwrap();
+        let data = out.get_section(".data").unwrap();
+        let word = |i: usize| {
+            u32::from_le_bytes([
+                text.data[i],
+                text.data[i + 1],
+                text.data[i + 2],
+                text.data[i + 3],
+            ])
+        };
+        // adrp x0, value
+        let page = ((word(0) >> 29) & 0x3) | (((word(0) >> 5) & 0x7_ffff) << 2);
+        assert_eq!(0x8_0000 + ((page as u64) << 12), data.shdr.addr & !0xfff);
+        // add x0, x0, :lo12:value and ldr x1, [x0, :lo12:value]
+        assert_eq!((word(4) >> 10) & 0xfff, (data.shdr.addr & 0xfff) as u32);
+        assert_eq!((word(8) >> 10) & 0xfff, (data.shdr.addr & 0xfff) as u32 / 8);
+        // b _start
+        assert_eq!(word(0x14) & 0x03ff_ffff, (-5i32 as u32) & 0x03ff_ffff);
+        assert_eq!(data.data, 0x8_0000u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_link_errors() {
+        // Undefined helper and counter
+        assert!(link(&[open("link_a.o")], &LinkOptions::default()).is_err());
+        // Multiple definitions of helper
+        let objects = vec![open("link_a.o"), open("link_b.o"), open("link_b.o")];
+        assert!(link(&objects, &LinkOptions::default()).is_err());
+        // link_c.o calls a local function in its copy of shared_inline,
+        // which is discarded for the copy in link_a.o
+        let objects = vec![open("link_a.o"), open("link_b.o"), open("link_c.o")];
+        assert!(link(&objects, &LinkOptions::default()).is_err());
+        let objects = vec![open("link_c.o"), open("link_a.o"), open("link_b.o")];
+        assert!(link(&objects, &LinkOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_output_index() {
+        assert_eq!(output_index(0).unwrap(), 1);
+        assert_eq!(output_index(0xfefe).unwrap(), 0xfeff);
+        // Indices from SHN_LORESERVE on would be read back as special sections
+        assert!(output_index(0xfeff).is_err());
+        assert!(output_index(0xffff).is_err());
+    }
+}
@@ -0,0 +1,870 @@
+use crate::types;
+use crate::{File, ParseError};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::io;
+
+/// Default load bias for position independent 64-bit files, as used by Linux on x86_64
+const DEFAULT_BIAS_64: u64 = 0x5555_5555_4000;
+/// Default load bias for position independent 32-bit files, as used by Linux on i386
+const DEFAULT_BIAS_32: u64 = 0x5655_5000;
+
+/// Address space of the process a file is loaded into.
+///
+/// Implementations typically back an emulator's guest memory.
+pub trait Memory {
+    /// Map a zero-filled region with the given permissions.
+    ///
+    /// `address` and `size` are multiples of the page size.
+    fn map(&mut self, address: u64, size: u64, flags: types::ProgFlag) -> Result<(), ParseError>;
+
+    /// Write bytes to a mapped region, regardless of its permissions.
+    fn write(&mut self, address: u64, data: &[u8]) -> Result<(), ParseError>;
+
+    /// Change the permissions of a mapped region.
+    ///
+    /// `address` and `size` are multiples of the page size.
+    fn protect(
+        &mut self,
+        address: u64,
+        size: u64,
+        flags: types::ProgFlag,
+    ) -> Result<(), ParseError>;
+}
+
+/// Callbacks used to resolve dynamic relocations that depend on the rest of the process.
+pub trait Resolver {
+    /// Get the address of a symbol that is not defined by the loaded file.
+    fn resolve_symbol(&mut self, _name: &str) -> Option<u64> {
+        None
+    }
+
+    /// Run the IFUNC resolver at `address` and return the address of the
+    /// implementation it selects.
+    fn call_ifunc(&mut self, _address: u64) -> Result<u64, ParseError> {
+        Err(ParseError::NotImplemented)
+    }
+}
+
+/// Options for [File::load].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// Load bias for ET_DYN files. A default is chosen if None. Must be None
+    /// or zero for ET_EXEC files.
+    pub bias: Option<u64>,
+    /// Page size used to align segments
+    pub page_size: u64,
+    /// Where to place the initial thread's static TLS area, if the file has a
+    /// PT_TLS segment. No TLS area is created if None.
+    pub tls_address: Option<u64>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            bias: None,
+            page_size: 0x1000,
+            tls_address: None,
+        }
+    }
+}
+
+/// Thread-local storage layout of a loaded file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Tls {
+    /// Address of the TLS initialization image
+    pub image: u64,
+    /// Size of the initialized part of the TLS block
+    pub filesz: u64,
+    /// Total size of the TLS block
+    pub memsz: u64,
+    /// Alignment of the TLS block
+    pub align: u64,
+    /// Offset of the TLS block from the thread pointer
+    pub offset: i64,
+    /// Thread pointer of the initial thread, if a TLS area was created
+    pub thread_pointer: Option<u64>,
+}
+
+/// The result of loading a file into memory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadedImage {
+    /// Difference between load addresses and the addresses in the file
+    pub bias: u64,
+    /// Entry point address
+    pub entry: u64,
+    /// Requested program interpreter from PT_INTERP, which is not loaded
+    pub interpreter: Option<String>,
+    /// TLS layout, if the file has a PT_TLS segment
+    pub tls: Option<Tls>,
+    /// Auxiliary vector entries describing the image, terminated by AT_NULL.
+    /// AT_BASE is zero; callers loading an interpreter should update it.
+    pub auxv: Vec<(u64, u64)>,
+}
+
+/// How a dynamic relocation type computes its value. B is the load bias, S
+/// the symbol value, A the addend.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum DynamicOperation {
+    None,
+    /// B + A
+    Relative,
+    /// S + A
+    Absolute,
+    /// S, for GOT and PLT entries
+    Symbol,
+    /// Result of calling the IFUNC resolver at B + A
+    Irelative,
+    /// TLS module ID
+    TlsModule,
+    /// Offset of the symbol in its module's TLS block, minus a machine specific bias
+    TlsDtpOffset(u64),
+    /// Offset of the symbol from the thread pointer
+    TlsTpOffset,
+}
+
+fn page_down(value: u64, page_size: u64) -> u64 {
+    value & !(page_size - 1)
+}
+
+fn page_up(value: u64, page_size: u64) -> u64 {
+    page_down(value + page_size - 1, page_size)
+}
+
+fn align_up(value: u64, align: u64) -> u64 {
+    if align <= 1 {
+        value
+    } else {
+        value.div_ceil(align) * align
+    }
+}
+
+impl File {
+    /// Map an ET_EXEC or ET_DYN file into memory, as the kernel and dynamic
+    /// linker would for a process image.
+    ///
+    /// PT_LOAD segments are mapped page-aligned with the permissions from
+    /// their flags, and the parts of segments beyond their file size, such
+    /// as .bss, are left zero-filled. Dynamic relocations are then applied:
+    /// symbols defined by the file resolve to their own definition, other
+    /// symbols go through [Resolver::resolve_symbol] and IFUNCs through
+    /// [Resolver::call_ifunc]. Finally the PT_GNU_RELRO region is made read-only.
+    ///
+    /// Dynamic relocations are supported for x86_64, AArch64 and RISC-V, as
+    /// are packed DT_RELR relative relocations for all machines. The
+    /// file is treated as the only TLS module, with module ID 1 and its TLS
+    /// block in the static TLS area of the initial thread. The program
+    /// interpreter is not loaded.
+    pub fn load<M: Memory, R: Resolver>(
+        &self,
+        memory: &mut M,
+        resolver: &mut R,
+        options: &LoadOptions,
+    ) -> Result<LoadedImage, ParseError> {
+        let page_size = options.page_size;
+        if page_size == 0 || !page_size.is_power_of_two() {
+            return Err(ParseError::InvalidFormat(Some(format!(
+                "Invalid page size {:#x}",
+                page_size
+            ))));
+        }
+        let bias = match (self.header.elftype, options.bias) {
+            (types::ElfFileType::Executable, None | Some(0)) => 0,
+            (types::ElfFileType::Executable, Some(_)) => {
+                return Err(ParseError::InvalidFormat(Some(
+                    "ET_EXEC files cannot be loaded with a bias".to_string(),
+                )))
+            }
+            (types::ElfFileType::SharedLibrary, Some(bias)) => bias,
+            (types::ElfFileType::SharedLibrary, None) => match self.header.class {
+                types::ElfClass::Format32 => DEFAULT_BIAS_32,
+                types::ElfClass::Format64 => DEFAULT_BIAS_64,
+            },
+            _ => {
+                return Err(ParseError::InvalidFormat(Some(format!(
+                    "Cannot load {} files",
+                    self.header.elftype
+                ))))
+            }
+        };
+        if bias % page_size != 0 {
+            return Err(ParseError::InvalidFormat(Some(format!(
+                "Load bias {:#x} is not page aligned",
+                bias
+            ))));
+        }
+
+        self.map_segments(memory, bias, page_size)?;
+        let tls = self.setup_tls(memory, bias, options)?;
+        self.apply_dynamic_relocations(memory, resolver, bias, tls.as_ref())?;
+
+        for phdr in self
+            .phdrs
+            .iter()
+            .filter(|phdr| phdr.progtype == types::PT_GNU_RELRO)
+        {
+            let start = page_down(phdr.vaddr + bias, page_size);
+            let end = page_down(phdr.vaddr + bias + phdr.memsz, page_size);
+            if end > start {
+                memory.protect(start, end - start, types::PF_R)?;
+            }
+        }
+
+        let interpreter = match self
+            .phdrs
+            .iter()
+            .find(|phdr| phdr.progtype == types::PT_INTERP)
+        {
+            Some(phdr) => {
+                let data = self.get_segment_data(phdr);
+                let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+                Some(String::from_utf8(data[..end].to_vec())?)
+            }
+            None => None,
+        };
+
+        let entry = self.header.entry.wrapping_add(bias);
+        let (ehsize, phentsize) = match self.header.class {
+            types::ElfClass::Format32 => (52, 32),
+            types::ElfClass::Format64 => (64, 56),
+        };
+        // Without PT_PHDR, assume the program headers follow the ELF header
+        // in the first segment, as linkers lay them out
+        let phdr_address = match self
+            .phdrs
+            .iter()
+            .find(|phdr| phdr.progtype == types::PT_PHDR)
+        {
+            Some(phdr) => phdr.vaddr + bias,
+            None => self
+                .phdrs
+                .iter()
+                .find(|phdr| phdr.progtype == types::PT_LOAD && phdr.offset == 0)
+                .map_or(0, |phdr| phdr.vaddr + bias + ehsize),
+        };
+        let auxv = vec![
+            (types::AT_PHDR, phdr_address),
+            (types::AT_PHENT, phentsize),
+            (types::AT_PHNUM, self.phdrs.len() as u64),
+            (types::AT_PAGESZ, page_size),
+            (types::AT_BASE, 0),
+            (types::AT_FLAGS, 0),
+            (types::AT_ENTRY, entry),
+            (types::AT_NULL, 0),
+        ];
+
+        Ok(LoadedImage {
+            bias,
+            entry,
+            interpreter,
+            tls,
+            auxv,
+        })
+    }
+
+    fn map_segments<M: Memory>(
+        &self,
+        memory: &mut M,
+        bias: u64,
+        page_size: u64,
+    ) -> Result<(), ParseError> {
+        let mut segments: Vec<&types::ProgramHeader> = self
+            .phdrs
+            .iter()
+            .filter(|phdr| phdr.progtype == types::PT_LOAD && phdr.memsz > 0)
+            .collect();
+        segments.sort_by_key(|phdr| phdr.vaddr);
+
+        let mut mapped_end = 0;
+        let mut last_flags = types::PF_NONE;
+        for phdr in segments {
+            let address = phdr.vaddr + bias;
+            let mut start = page_down(address, page_size);
+            let end = page_up(address + phdr.memsz, page_size);
+            if start < mapped_end {
+                // Segments sharing a page get the union of their permissions
+                let shared_end = mapped_end.min(end);
+                memory.protect(
+                    start,
+                    shared_end - start,
+                    types::ProgFlag(last_flags.0 | phdr.flags.0),
+                )?;
+                start = shared_end;
+            }
+            if end > start {
+                memory.map(start, end - start, phdr.flags)?;
+            }
+            memory.write(address, &self.get_segment_data(phdr))?;
+            mapped_end = mapped_end.max(end);
+            last_flags = phdr.flags;
+        }
+        Ok(())
+    }
+
+    fn setup_tls<M: Memory>(
+        &self,
+        memory: &mut M,
+        bias: u64,
+        options: &LoadOptions,
+    ) -> Result<Option<Tls>, ParseError> {
+        let phdr = match self
+            .phdrs
+            .iter()
+            .find(|phdr| phdr.progtype == types::PT_TLS)
+        {
+            Some(phdr) => phdr,
+            None => return Ok(None),
+        };
+        let align = phdr.align.max(1);
+        let pointer_size = match self.header.class {
+            types::ElfClass::Format32 => 4,
+            types::ElfClass::Format64 => 8,
+        };
+        // Offset of the TLS block from the thread pointer, and the size of
+        // the area in front of the thread pointer
+        let (offset, below_tp) = match self.header.cpu_architecture {
+            // Variant II: the block ends at the thread pointer, which points
+            // to a thread control block starting with a pointer to itself
+            types::ElfCpuArchitecture::EM_X86_64 | types::ElfCpuArchitecture::EM_386 => {
+                let size = align_up(phdr.memsz, align);
+                (-(size as i64), size)
+            }
+            // Variant I with a 16 byte thread control block
+            types::ElfCpuArchitecture::EM_AARCH64 | types::ElfCpuArchitecture::EM_ARM => {
+                (align_up(2 * pointer_size, align) as i64, 0)
+            }
+            // The thread pointer points to the start of the block
+            _ => (0, 0),
+        };
+
+        let thread_pointer = match options.tls_address {
+            Some(address) => {
+                let area = align_up(address, align);
+                let tp = area + below_tp;
+                let block = tp.wrapping_add(offset as u64);
+                let end = (block + phdr.memsz).max(tp + pointer_size);
+                let start = page_down(area, options.page_size);
+                memory.map(
+                    start,
+                    page_up(end, options.page_size) - start,
+                    types::ProgFlag(types::PF_R.0 | types::PF_W.0),
+                )?;
+                let image = self.get_segment_data(phdr);
+                memory.write(block, &image)?;
+                if below_tp > 0 {
+                    memory.write(tp, &self.encode_address(tp))?;
+                }
+                Some(tp)
+            }
+            None => None,
+        };
+
+        Ok(Some(Tls {
+            image: phdr.vaddr + bias,
+            filesz: phdr.filesz,
+            memsz: phdr.memsz,
+            align,
+            offset,
+            thread_pointer,
+        }))
+    }
+
+    fn dynamic_operation(&self, rtype: u32) -> Option<DynamicOperation> {
+        use types::ElfCpuArchitecture::*;
+        let operation = match (self.header.cpu_architecture, rtype) {
+            (EM_X86_64, types::R_X86_64_NONE) => DynamicOperation::None,
+            (EM_X86_64, types::R_X86_64_RELATIVE) => DynamicOperation::Relative,
+            (EM_X86_64, types::R_X86_64_64) => DynamicOperation::Absolute,
+            (EM_X86_64, types::R_X86_64_GLOB_DAT) => DynamicOperation::Symbol,
+            (EM_X86_64, types::R_X86_64_JUMP_SLOT) => DynamicOperation::Symbol,
+            (EM_X86_64, types::R_X86_64_IRELATIVE) => DynamicOperation::Irelative,
+            (EM_X86_64, types::R_X86_64_DTPMOD64) => DynamicOperation::TlsModule,
+            (EM_X86_64, types::R_X86_64_DTPOFF64) => DynamicOperation::TlsDtpOffset(0),
+            (EM_X86_64, types::R_X86_64_TPOFF64) => DynamicOperation::TlsTpOffset,
+
+            (EM_AARCH64, types::R_AARCH64_NONE) => DynamicOperation::None,
+            (EM_AARCH64, types::R_AARCH64_RELATIVE) => DynamicOperation::Relative,
+            (EM_AARCH64, types::R_AARCH64_ABS64) => DynamicOperation::Absolute,
+            (EM_AARCH64, types::R_AARCH64_GLOB_DAT) => DynamicOperation::Absolute,
+            (EM_AARCH64, types::R_AARCH64_JUMP_SLOT) => DynamicOperation::Absolute,
+            (EM_AARCH64, types::R_AARCH64_IRELATIVE) => DynamicOperation::Irelative,
+            (EM_AARCH64, types::R_AARCH64_TLS_DTPMOD) => DynamicOperation::TlsModule,
+            (EM_AARCH64, types::R_AARCH64_TLS_DTPREL) => DynamicOperation::TlsDtpOffset(0),
+            (EM_AARCH64, types::R_AARCH64_TLS_TPREL) => DynamicOperation::TlsTpOffset,
+
+            // RISC-V biases DTV pointers by 0x800 to make better use of
+            // signed 12 bit immediates
+            (EM_RISCV, types::R_RISCV_NONE) => DynamicOperation::None,
+            (EM_RISCV, types::R_RISCV_RELATIVE) => DynamicOperation::Relative,
+            (EM_RISCV, types::R_RISCV_32) => DynamicOperation::Absolute,
+            (EM_RISCV, types::R_RISCV_64) => DynamicOperation::Absolute,
+            (EM_RISCV, types::R_RISCV_JUMP_SLOT) => DynamicOperation::Symbol,
+            (EM_RISCV, types::R_RISCV_IRELATIVE) => DynamicOperation::Irelative,
+            (EM_RISCV, types::R_RISCV_TLS_DTPMOD32) => DynamicOperation::TlsModule,
+            (EM_RISCV, types::R_RISCV_TLS_DTPMOD64) => DynamicOperation::TlsModule,
+            (EM_RISCV, types::R_RISCV_TLS_DTPREL32) => DynamicOperation::TlsDtpOffset(0x800),
+            (EM_RISCV, types::R_RISCV_TLS_DTPREL64) => DynamicOperation::TlsDtpOffset(0x800),
+            (EM_RISCV, types::R_RISCV_TLS_TPREL32) => DynamicOperation::TlsTpOffset,
+            (EM_RISCV, types::R_RISCV_TLS_TPREL64) => DynamicOperation::TlsTpOffset,
+            _ => return None,
+        };
+        Some(operation)
+    }
+
+    /// Read the contents of the file at a virtual address range covered by a PT_LOAD segment
+    pub(crate) fn read_virtual(&self, address: u64, size: u64) -> Result<Vec<u8>, ParseError> {
+        let error = || {
+            ParseError::InvalidFormat(Some(format!(
+                "Address range {:#x}+{:#x} is not in a loadable segment",
+                address, size
+            )))
+        };
+        let end = address.checked_add(size).ok_or_else(error)?;
+        let phdr = self
+            .phdrs
+            .iter()
+            .filter(|phdr| phdr.progtype == types::PT_LOAD)
+            .find(|phdr| address >= phdr.vaddr && end <= phdr.vaddr.saturating_add(phdr.filesz))
+            .ok_or_else(error)?;
+        let start = (address - phdr.vaddr) as usize;
+        let range = start..start + size as usize;
+        match self.get_segment_bytes(phdr) {
+            Some(data) => data.get(range).map(<[u8]>::to_vec),
+            None => self.get_segment_data(phdr).get(range).map(<[u8]>::to_vec),
+        }
+        .ok_or_else(error)
+    }
+
+    /// Decode the offsets of a DT_RELR table.
+    ///
+    /// Even entries are the offset of a relocation. Odd entries are bitmaps
+    /// of the relocations in the words that follow the last relocated
+    /// offset, the lowest bit marking the entry as a bitmap.
+    fn decode_relr(&self, data: &[u8]) -> Vec<u64> {
+        let word_size = self.encode_address(0).len();
+        let bits = word_size as u64 * 8 - 1;
+        let mut offsets = Vec::new();
+        let mut next = 0u64;
+        for entry in data.chunks_exact(word_size) {
+            let entry = self.decode_address(entry);
+            if entry & 1 == 0 {
+                offsets.push(entry);
+                next = entry.wrapping_add(word_size as u64);
+            } else {
+                offsets.extend(
+                    (0..bits)
+                        .filter(|bit| (entry >> (bit + 1)) & 1 != 0)
+                        .map(|bit| next.wrapping_add(bit * word_size as u64)),
+                );
+                next = next.wrapping_add(bits * word_size as u64);
+            }
+        }
+        offsets
+    }
+
+    fn apply_dynamic_relocations<M: Memory, R: Resolver>(
+        &self,
+        memory: &mut M,
+        resolver: &mut R,
+        bias: u64,
+        tls: Option<&Tls>,
+    ) -> Result<(), ParseError> {
+        let dynamic = self.get_dynamic()?;
+        let value_of = |tag: types::DynTag| {
+            dynamic
+                .iter()
+                .find(|entry| entry.d_tag == tag)
+                .map(|entry| entry.d_val)
+        };
+        if value_of(types::DT_REL).is_some() || value_of(types::DT_PLTREL) == Some(types::DT_REL.0)
+        {
+            return Err(ParseError::InvalidFormat(Some(
+                "DT_REL dynamic relocations are not supported".to_string(),
+            )));
+        }
+
+        // Packed relative relocations, with addends in the relocated words
+        if let (Some(address), Some(size)) = (value_of(types::DT_RELR), value_of(types::DT_RELRSZ))
+        {
+            let word_size = self.encode_address(0).len() as u64;
+            for offset in self.decode_relr(&self.read_virtual(address, size)?) {
+                let addend = self.decode_address(&self.read_virtual(offset, word_size)?);
+                memory.write(
+                    offset.wrapping_add(bias),
+                    &self.encode_address(bias.wrapping_add(addend)),
+                )?;
+            }
+        }
+
+        let mut relocations = Vec::new();
+        for (table, size) in [
+            (types::DT_RELA, types::DT_RELASZ),
+            (types::DT_JMPREL, types::DT_PLTRELSZ),
+        ] {
+            if let (Some(address), Some(size)) = (value_of(table), value_of(size)) {
+                let data = self.read_virtual(address, size)?;
+                relocations.extend(self.parse_relocations(&data, true)?);
+            }
+        }
+        if relocations.is_empty() {
+            return Ok(());
+        }
+
+        let symbols = match self
+            .sections
+            .iter()
+            .find(|section| section.shdr.shtype == types::SectionType::Dynsym)
+        {
+            Some(section) => self.get_symbols(section)?,
+            None => match value_of(types::DT_SYMTAB) {
+                // Only the symbols that relocations refer to are needed
+                Some(address) => {
+                    let count = relocations
+                        .iter()
+                        .map(|relocation| relocation.symbol as u64 + 1)
+                        .max()
+                        .unwrap_or(0);
+                    self.read_dynamic_symbols(address, count)?
+                }
+                None => Vec::new(),
+            },
+        };
+
+        // IRELATIVE relocations are applied last, so that resolvers can use
+        // data that is relocated by the others
+        relocations.sort_by_key(|relocation| {
+            self.dynamic_operation(relocation.rtype) == Some(DynamicOperation::Irelative)
+        });
+
+        for relocation in relocations {
+            let operation =
+                self.dynamic_operation(relocation.rtype)
+                    .ok_or(ParseError::InvalidFormat(Some(format!(
+                        "Unsupported {} dynamic relocation type {}",
+                        self.header.cpu_architecture, relocation.rtype
+                    ))))?;
+            let addend = relocation.addend.unwrap_or(0) as u64;
+            let symbol = match relocation.symbol {
+                0 => None,
+                index => Some(
+                    symbols
+                        .get(index as usize)
+                        .ok_or(ParseError::InvalidFormat(Some(format!(
+                            "Invalid dynamic symbol index {}",
+                            index
+                        ))))?,
+                ),
+            };
+            let tls_symbol = || -> Result<u64, ParseError> {
+                match symbol {
+                    None => Ok(0),
+                    Some(symbol) if symbol.shndx.is_defined() => Ok(symbol.value),
+                    Some(symbol) => Err(ParseError::InvalidFormat(Some(format!(
+                        "TLS symbol {} is not defined by this file",
+                        symbol.name
+                    )))),
+                }
+            };
+
+            let value = match operation {
+                DynamicOperation::None => continue,
+                DynamicOperation::Relative => bias.wrapping_add(addend),
+                DynamicOperation::Absolute => self
+                    .resolve_dynamic_symbol(symbol, resolver, bias)?
+                    .wrapping_add(addend),
+                DynamicOperation::Symbol => self.resolve_dynamic_symbol(symbol, resolver, bias)?,
+                DynamicOperation::Irelative => resolver.call_ifunc(bias.wrapping_add(addend))?,
+                DynamicOperation::TlsModule => 1,
+                DynamicOperation::TlsDtpOffset(dtv_bias) => {
+                    tls_symbol()?.wrapping_add(addend).wrapping_sub(dtv_bias)
+                }
+                DynamicOperation::TlsTpOffset => {
+                    let tls = tls.ok_or(ParseError::InvalidFormat(Some(
+                        "TLS relocation in a file without PT_TLS".to_string(),
+                    )))?;
+                    (tls.offset as u64)
+                        .wrapping_add(tls_symbol()?)
+                        .wrapping_add(addend)
+                }
+            };
+            memory.write(
+                relocation.offset.wrapping_add(bias),
+                &self.encode_address(value),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Read the first `count` entries of the dynamic symbol table at `address`,
+    /// for files whose section headers have been stripped.
+    fn read_dynamic_symbols(
+        &self,
+        address: u64,
+        count: u64,
+    ) -> Result<Vec<types::Symbol>, ParseError> {
+        let entsize = match self.header.class {
+            types::ElfClass::Format32 => 16,
+            types::ElfClass::Format64 => 24,
+        };
+        let size = count
+            .checked_mul(entsize)
+            .ok_or(ParseError::InvalidFormat(Some(format!(
+                "{} dynamic symbols do not fit in memory",
+                count
+            ))))?;
+        let data = self.read_virtual(address, size)?;
+        let strtab = self.get_dynamic_strtab()?;
+        let mut io_data = io::Cursor::new(&data);
+        let mut symbols = Vec::new();
+        for _ in 0..count {
+            self.parse_symbol(&mut io_data, &mut symbols, &strtab, &[])?;
+        }
+        Ok(symbols)
+    }
+
+    fn resolve_dynamic_symbol<R: Resolver>(
+        &self,
+        symbol: Option<&types::Symbol>,
+        resolver: &mut R,
+        bias: u64,
+    ) -> Result<u64, ParseError> {
+        let symbol = match symbol {
+            Some(symbol) => symbol,
+            None => return Ok(0),
+        };
+        if symbol.shndx.is_defined() {
+            let address = symbol.value.wrapping_add(bias);
+            if symbol.symtype == types::STT_GNU_IFUNC {
+                return resolver.call_ifunc(address);
+            }
+            return Ok(address);
+        }
+        match resolver.resolve_symbol(&symbol.name) {
+            Some(address) => Ok(address),
+            None if symbol.bind == types::STB_WEAK => Ok(0),
+            None => Err(ParseError::InvalidFormat(Some(format!(
+                "Undefined symbol {}",
+                symbol.name
+            )))),
+        }
+    }
+
+    /// Decode an address in the file's word size and byte order
+    fn decode_address(&self, data: &[u8]) -> u64 {
+        match (self.header.class, self.header.endianness) {
+            (types::ElfClass::Format32, types::ElfEndianness::Lsb) => {
+                u64::from(LittleEndian::read_u32(data))
+            }
+            (types::ElfClass::Format32, types::ElfEndianness::Msb) => {
+                u64::from(BigEndian::read_u32(data))
+            }
+            (types::ElfClass::Format64, types::ElfEndianness::Lsb) => LittleEndian::read_u64(data),
+            (types::ElfClass::Format64, types::ElfEndianness::Msb) => BigEndian::read_u64(data),
+        }
+    }
+
+    /// Encode an address in the file's word size and byte order
+    fn encode_address(&self, value: u64) -> Vec<u8> {
+        match (self.header.class, self.header.endianness) {
+            (types::ElfClass::Format32, types::ElfEndianness::Lsb) => {
+                (value as u32).to_le_bytes().to_vec()
+            }
+            (types::ElfClass::Format32, types::ElfEndianness::Msb) => {
+                (value as u32).to_be_bytes().to_vec()
+            }
+            (types::ElfClass::Format64, types::ElfEndianness::Lsb) => value.to_le_bytes().to_vec(),
+            (types::ElfClass::Format64, types::ElfEndianness::Msb) => value.to_be_bytes().to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LoadOptions, Memory, Resolver};
+    use crate::types;
+    use crate::{File, ParseError};
+    use byteorder::{ByteOrder, LittleEndian};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    /// Sparse page-granular memory
+    #[derive(Default)]
+    struct TestMemory {
+        pages: HashMap<u64, (Vec<u8>, types::ProgFlag)>,
+    }
+
+    impl TestMemory {
+        fn read(&self, address: u64, size: usize) -> Vec<u8> {
+            (address..address + size as u64)
+                .map(|a| self.pages[&(a & !0xfff)].0[(a & 0xfff) as usize])
+                .collect()
+        }
+
+        fn read_u64(&self, address: u64) -> u64 {
+            LittleEndian::read_u64(&self.read(address, 8))
+        }
+    }
+
+    impl Memory for TestMemory {
+        fn map(
+            &mut self,
+            address: u64,
+            size: u64,
+            flags: types::ProgFlag,
+        ) -> Result<(), ParseError> {
+            for page in (address..address + size).step_by(0x1000) {
+                self.pages.insert(page, (vec![0; 0x1000], flags));
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, address: u64, data: &[u8]) -> Result<(), ParseError> {
+            for (a, byte) in (address..).zip(data) {
+                let page = self
+                    .pages
+                    .get_mut(&(a & !0xfff))
+                    .ok_or(ParseError::InvalidFormat(None))?;
+                page.0[(a & 0xfff) as usize] = *byte;
+            }
+            Ok(())
+        }
+
+        fn protect(
+            &mut self,
+            address: u64,
+            size: u64,
+            flags: types::ProgFlag,
+        ) -> Result<(), ParseError> {
+            for page in (address..address + size).step_by(0x1000) {
+                self.pages
+                    .get_mut(&page)
+                    .ok_or(ParseError::InvalidFormat(None))?
+                    .1 = flags;
+            }
+            Ok(())
+        }
+    }
+
+    struct TestResolver;
+
+    impl Resolver for TestResolver {
+        fn resolve_symbol(&mut self, name: &str) -> Option<u64> {
+            match name {
+                "external_counter" => Some(0x7000_0000),
+                "external_function" => Some(0x7000_1000),
+                "__tls_get_addr" => Some(0x7000_2000),
+                _ => None,
+            }
+        }
+
+        fn call_ifunc(&mut self, address: u64) -> Result<u64, ParseError> {
+            Ok(address + 0x10_0000)
+        }
+    }
+
+    #[test]
+    fn test_load_shared_object() {
+        let file = File::open_path(PathBuf::from("tests/samples/libloader.so")).unwrap();
+        let mut memory = TestMemory::default();
+        let options = LoadOptions {
+            bias: Some(0x10_0000),
+            tls_address: Some(0x80_0000),
+            ..Default::default()
+        };
+        let image = file.load(&mut memory, &mut TestResolver, &options).unwrap();
+        assert_eq!(image.entry, 0x10_104e);
+        assert_eq!(image.interpreter, None);
+        assert!(image.auxv.contains(&(types::AT_PHDR, 0x10_0040)));
+        assert!(image.auxv.contains(&(types::AT_ENTRY, 0x10_104e)));
+        // The ELF header and program headers are mapped with the first segment
+        assert_eq!(memory.read(0x10_0000, 4), b"\x7fELF");
+        assert_eq!(
+            LittleEndian::read_u32(&memory.read(0x10_0040, 4)),
+            file.phdrs[0].progtype.0
+        );
+
+        // Text is executable, and the RELRO part of the data segment was made read-only
+        assert_eq!(memory.pages[&0x10_1000].1, types::ProgFlag(5));
+        assert_eq!(memory.pages[&0x10_3000].1, types::PF_R);
+        assert_eq!(memory.pages[&0x10_4000].1, types::ProgFlag(6));
+
+        // RELATIVE, R_X86_64_64 and IRELATIVE
+        assert_eq!(memory.read_u64(0x10_4008), 0x10_4000);
+        assert_eq!(memory.read_u64(0x10_4018), 0x10_4004);
+        assert_eq!(memory.read_u64(0x10_4010), 0x20_1046);
+        // GLOB_DAT, and JUMP_SLOT to an external function and to an IFUNC
+        assert_eq!(memory.read_u64(0x10_3fe0), 0x7000_0000);
+        assert_eq!(memory.read_u64(0x10_3fd0), 0x7000_1000);
+        assert_eq!(memory.read_u64(0x10_3fc8), 0x20_1046);
+
+        // TLS: 0x50 byte block below the thread pointer
+        let tls = image.tls.unwrap();
+        assert_eq!(tls.offset, -0x50);
+        let tp = tls.thread_pointer.unwrap();
+        assert_eq!(tp, 0x80_0050);
+        assert_eq!(memory.read_u64(tp), tp);
+        assert_eq!(memory.read(0x80_0000, 4), vec![42, 0, 0, 0]);
+        assert_eq!(memory.read_u64(0x10_3fe8), -0x50i64 as u64);
+        assert_eq!(memory.read_u64(0x10_3ff0), 1);
+        assert_eq!(memory.read_u64(0x10_3ff8), 0x10);
+    }
+
+    #[test]
+    fn test_load_without_sections() {
+        // Symbols are found through DT_SYMTAB once the section headers are gone
+        let mut file = File::open_path(PathBuf::from("tests/samples/libloader.so")).unwrap();
+        file.sections.clear();
+        let mut memory = TestMemory::default();
+        let options = LoadOptions {
+            bias: Some(0x10_0000),
+            tls_address: Some(0x80_0000),
+            ..Default::default()
+        };
+        file.load(&mut memory, &mut TestResolver, &options).unwrap();
+        assert_eq!(memory.read_u64(0x10_4010), 0x20_1046);
+        assert_eq!(memory.read_u64(0x10_3fe0), 0x7000_0000);
+        assert_eq!(memory.read_u64(0x10_3fd0), 0x7000_1000);
+        assert_eq!(memory.read_u64(0x10_3fc8), 0x20_1046);
+        assert_eq!(memory.read_u64(0x10_3ff8), 0x10);
+    }
+
+    #[test]
+    fn test_load_executable() {
+        let file = File::open_path(PathBuf::from("tests/samples/x86_64_firmware")).unwrap();
+        let mut memory = TestMemory::default();
+        let image = file
+            .load(&mut memory, &mut TestResolver, &LoadOptions::default())
+            .unwrap();
+        assert_eq!(image.bias, 0);
+        let bss = file.get_section(".bss").unwrap();
+        assert_eq!(
+            memory.read(bss.shdr.addr, bss.shdr.size as usize),
+            vec![0; bss.shdr.size as usize]
+        );
+        let data = file.get_section(".data").unwrap();
+        assert_eq!(memory.read(data.shdr.addr, data.data.len()), data.data);
+
+        let options = LoadOptions {
+            bias: Some(0x1000),
+            ..Default::default()
+        };
+        assert!(file.load(&mut memory, &mut TestResolver, &options).is_err());
+    }
+
+    #[test]
+    fn test_load_relr() {
+        let file = File::open_path(PathBuf::from("tests/samples/librelr.so")).unwrap();
+        let mut memory = TestMemory::default();
+        let options = LoadOptions {
+            bias: Some(0x10_0000),
+            ..Default::default()
+        };
+        file.load(&mut memory, &mut TestResolver, &options).unwrap();
+        // int *lonely = &values[5], and int *pointers[100] with pointers[i] = &values[i],
+        // spread over several bitmap entries
+        let values = 0x10_2340;
+        assert_eq!(memory.read_u64(0x10_2000), values + 5 * 4);
+        for i in 0..100 {
+            assert_eq!(memory.read_u64(0x10_2020 + i * 8), values + i * 4);
+        }
+    }
+}
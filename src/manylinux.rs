@@ -0,0 +1,283 @@
+use crate::types;
+use crate::{File, ParseError};
+use std::cmp::Ordering;
+
+/// Version name prefixes that policies place an upper bound on.
+const VERSIONED_PREFIXES: &[&str] = &["GLIBC", "GLIBCXX", "CXXABI", "GCC"];
+
+/// Libraries that every policy allows to be linked against dynamically.
+const BASE_LIBRARIES: &[&str] = &[
+    "libgcc_s.so.1",
+    "libstdc++.so.6",
+    "libm.so.6",
+    "libdl.so.2",
+    "librt.so.1",
+    "libc.so.6",
+    "libutil.so.1",
+    "libpthread.so.0",
+    "libresolv.so.2",
+    "libX11.so.6",
+    "libXext.so.6",
+    "libXrender.so.1",
+    "libICE.so.6",
+    "libSM.so.6",
+    "libGL.so.1",
+    "libgobject-2.0.so.0",
+    "libgthread-2.0.so.0",
+    "libglib-2.0.so.0",
+    "ld-linux-x86-64.so.2",
+    "ld-linux-aarch64.so.1",
+    "ld-linux.so.2",
+    "ld64.so.2",
+    "ld64.so.1",
+];
+
+/// A symbol version split into its prefix and numeric components,
+/// e.g. "GLIBC_2.17" is ("GLIBC", [2, 17]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionName {
+    /// Name before the last underscore
+    pub prefix: String,
+    /// Numeric version components, empty for versions like "GLIBC_PRIVATE"
+    pub version: Vec<u32>,
+}
+
+impl VersionName {
+    /// Split a version name, returning None if it has no `PREFIX_` part.
+    pub fn parse(name: &str) -> Option<VersionName> {
+        let split = name.rfind('_')?;
+        let (prefix, version) = (&name[..split], &name[split + 1..]);
+        let version = version
+            .split('.')
+            .map(|part| part.parse::<u32>())
+            .collect::<Result<Vec<u32>, _>>()
+            .unwrap_or_default();
+        Some(VersionName {
+            prefix: prefix.to_string(),
+            version,
+        })
+    }
+
+    fn compare(&self, other: &[u32]) -> Ordering {
+        // Missing components count as zero, so 2.17 == 2.17.0
+        let len = self.version.len().max(other.len());
+        (0..len)
+            .map(|i| {
+                let a = self.version.get(i).copied().unwrap_or(0);
+                let b = other.get(i).copied().unwrap_or(0);
+                a.cmp(&b)
+            })
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl std::fmt::Display for VersionName {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let version: Vec<String> = self.version.iter().map(|v| v.to_string()).collect();
+        write!(f, "{}_{}", self.prefix, version.join("."))
+    }
+}
+
+/// A manylinux platform policy, modelled on the policies used by auditwheel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManylinuxPolicy {
+    /// Platform tag, e.g. "manylinux2014"
+    pub name: String,
+    /// Newest allowed version per version prefix
+    pub symbol_versions: Vec<(String, Vec<u32>)>,
+    /// Libraries that may appear in DT_NEEDED
+    pub libraries: Vec<String>,
+}
+
+impl ManylinuxPolicy {
+    fn new(name: &str, versions: &[(&str, &[u32])], libraries: &[&str]) -> ManylinuxPolicy {
+        ManylinuxPolicy {
+            name: name.to_string(),
+            symbol_versions: versions
+                .iter()
+                .map(|(prefix, version)| (prefix.to_string(), version.to_vec()))
+                .collect(),
+            libraries: libraries.iter().map(|lib| lib.to_string()).collect(),
+        }
+    }
+
+    /// The manylinux2014 (glibc 2.17, GCC 4.8) policy
+    pub fn manylinux2014() -> ManylinuxPolicy {
+        let mut libraries = BASE_LIBRARIES.to_vec();
+        libraries.push("libnsl.so.1");
+        ManylinuxPolicy::new(
+            "manylinux2014",
+            &[
+                ("GLIBC", &[2, 17]),
+                ("CXXABI", &[1, 3, 7]),
+                ("GLIBCXX", &[3, 4, 19]),
+                ("GCC", &[4, 8, 0]),
+            ],
+            &libraries,
+        )
+    }
+
+    /// The manylinux_2_28 (glibc 2.28, GCC 8) policy
+    pub fn manylinux_2_28() -> ManylinuxPolicy {
+        ManylinuxPolicy::new(
+            "manylinux_2_28",
+            &[
+                ("GLIBC", &[2, 28]),
+                ("CXXABI", &[1, 3, 11]),
+                ("GLIBCXX", &[3, 4, 25]),
+                ("GCC", &[7, 0, 0]),
+            ],
+            BASE_LIBRARIES,
+        )
+    }
+
+    /// Returns true if a required symbol version is allowed by this policy.
+    ///
+    /// Versions with a prefix the policy does not restrict are allowed, but
+    /// non-numeric versions of restricted prefixes such as GLIBC_PRIVATE are not.
+    pub fn allows_version(&self, version: &VersionName) -> bool {
+        match self
+            .symbol_versions
+            .iter()
+            .find(|(prefix, _)| *prefix == version.prefix)
+        {
+            Some((_, max)) => {
+                !version.version.is_empty() && version.compare(max) != Ordering::Greater
+            }
+            None => true,
+        }
+    }
+}
+
+/// A dynamic symbol whose required version a policy does not allow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OffendingSymbol {
+    /// Symbol name
+    pub name: String,
+    /// Required version, e.g. "GLIBC_2.34"
+    pub version: String,
+    /// Library the version is required from
+    pub library: Option<String>,
+}
+
+/// Result of checking a file against a [ManylinuxPolicy].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyReport {
+    /// Name of the policy that was checked
+    pub policy: String,
+    /// DT_NEEDED libraries the policy does not allow
+    pub offending_libraries: Vec<String>,
+    /// Symbols requiring versions newer than the policy allows
+    pub offending_symbols: Vec<OffendingSymbol>,
+}
+
+impl PolicyReport {
+    /// Returns true if the file conforms to the policy
+    pub fn conforms(&self) -> bool {
+        self.offending_libraries.is_empty() && self.offending_symbols.is_empty()
+    }
+}
+
+impl File {
+    /// Get the newest required version for each of GLIBC, GLIBCXX, CXXABI and GCC.
+    ///
+    /// Versions come from the SHT_GNU_VERNEED section; prefixes that are not
+    /// required at all are left out.
+    pub fn get_required_versions(&self) -> Result<Vec<VersionName>, ParseError> {
+        let mut newest: Vec<VersionName> = Vec::new();
+        for need in self.get_version_needs()? {
+            for aux in need.versions {
+                let version = match VersionName::parse(&aux.name) {
+                    Some(version) => version,
+                    None => continue,
+                };
+                if !VERSIONED_PREFIXES.contains(&version.prefix.as_str())
+                    || version.version.is_empty()
+                {
+                    continue;
+                }
+                match newest.iter_mut().find(|v| v.prefix == version.prefix) {
+                    Some(current) => {
+                        if version.compare(&current.version) == Ordering::Greater {
+                            *current = version;
+                        }
+                    }
+                    None => newest.push(version),
+                }
+            }
+        }
+        Ok(newest)
+    }
+
+    /// Check this file against a manylinux policy, like `auditwheel show`.
+    pub fn check_manylinux_policy(
+        &self,
+        policy: &ManylinuxPolicy,
+    ) -> Result<PolicyReport, ParseError> {
+        let offending_libraries = self
+            .get_needed()?
+            .into_iter()
+            .filter(|lib| !policy.libraries.contains(lib))
+            .collect();
+
+        let mut offending_symbols = Vec::new();
+        if let Some(dynsym) = self
+            .sections
+            .iter()
+            .find(|section| section.shdr.shtype == types::SectionType::Dynsym)
+        {
+            let symbols = self.get_symbols(dynsym)?;
+            let versions = self.get_symbol_versions()?;
+            for (sym, version) in symbols.iter().zip(versions.iter()) {
+                let version = match version {
+                    Some(version) if version.file.is_some() => version,
+                    _ => continue,
+                };
+                let allowed = VersionName::parse(&version.name)
+                    .is_none_or(|name| policy.allows_version(&name));
+                if !allowed {
+                    offending_symbols.push(OffendingSymbol {
+                        name: sym.name.clone(),
+                        version: version.name.clone(),
+                        library: version.file.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(PolicyReport {
+            policy: policy.name.clone(),
+            offending_libraries,
+            offending_symbols,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ManylinuxPolicy, VersionName};
+    use crate::File;
+
+    #[test]
+    fn test_manylinux_policy() {
+        let file = File::open_path("tests/samples/x86_64_hardened").unwrap();
+        let required = file.get_required_versions().unwrap();
+        assert_eq!(required.len(), 1);
+        assert_eq!(required[0].to_string(), "GLIBC_2.34");
+
+        let report = file
+            .check_manylinux_policy(&ManylinuxPolicy::manylinux_2_28())
+            .unwrap();
+        assert!(!report.conforms());
+        assert!(report.offending_libraries.is_empty());
+        assert_eq!(report.offending_symbols.len(), 1);
+        assert_eq!(report.offending_symbols[0].name, "__libc_start_main");
+        assert_eq!(report.offending_symbols[0].version, "GLIBC_2.34");
+
+        let policy = ManylinuxPolicy::manylinux2014();
+        assert!(policy.allows_version(&VersionName::parse("GLIBCXX_3.4.19").unwrap()));
+        assert!(!policy.allows_version(&VersionName::parse("GLIBC_2.17.1").unwrap()));
+        assert!(!policy.allows_version(&VersionName::parse("GLIBC_PRIVATE").unwrap()));
+    }
+}
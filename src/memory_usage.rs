@@ -0,0 +1,255 @@
+use crate::types;
+use crate::File;
+
+/// A named range of target memory with a fixed capacity, like a MEMORY
+/// entry of a GNU ld linker script.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryRegion {
+    /// Region name, e.g. "FLASH"
+    pub name: String,
+    /// Start address
+    pub origin: u64,
+    /// Capacity in bytes
+    pub length: u64,
+}
+
+impl MemoryRegion {
+    pub fn new<T: Into<String>>(name: T, origin: u64, length: u64) -> MemoryRegion {
+        MemoryRegion {
+            name: name.into(),
+            origin,
+            length,
+        }
+    }
+
+    /// End of the region, clamped to the end of the address space
+    fn end(&self) -> u64 {
+        self.origin.saturating_add(self.length)
+    }
+
+    fn overlap(&self, start: u64, size: u64) -> u64 {
+        let end = start.saturating_add(size).min(self.end());
+        let start = start.max(self.origin);
+        end.saturating_sub(start)
+    }
+}
+
+/// Where an allocated section lives on the target.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// Contents are stored and used at the same address, like .text and .rodata
+    Flash,
+    /// Contents are stored at the load address and copied to the run address
+    /// at startup, like .data
+    FlashAndRam,
+    /// No stored contents, only run-time memory, like .bss
+    Ram,
+}
+
+/// The placement of one allocated section.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SectionPlacement {
+    /// Section name
+    pub name: String,
+    /// Run-time address (VMA)
+    pub vma: u64,
+    /// Load address (LMA), from the `paddr` of the containing PT_LOAD segment
+    pub lma: u64,
+    /// Section size
+    pub size: u64,
+    /// Whether the section takes up flash, RAM or both
+    pub placement: Placement,
+}
+
+/// How much of a memory region is used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionUsage {
+    /// The region
+    pub region: MemoryRegion,
+    /// Bytes used within the region
+    pub used: u64,
+    /// Bytes placed past the end of the region, from sections that start inside it
+    pub overflow: u64,
+    /// Names of the sections that occupy the region
+    pub sections: Vec<String>,
+}
+
+impl RegionUsage {
+    /// Returns true if the region cannot hold everything placed in it
+    pub fn is_overflowing(&self) -> bool {
+        self.overflow > 0
+    }
+
+    /// Bytes still available in the region
+    pub fn free(&self) -> u64 {
+        self.region.length.saturating_sub(self.used)
+    }
+
+    /// Percentage of the region in use
+    pub fn percent_used(&self) -> f64 {
+        if self.region.length == 0 {
+            return 100.0;
+        }
+        (self.used + self.overflow) as f64 * 100.0 / self.region.length as f64
+    }
+}
+
+/// Flash and RAM usage of a firmware image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Bytes stored in non-volatile memory: all allocated sections with contents
+    pub flash: u64,
+    /// Bytes of run-time read/write memory: writable sections and .bss
+    pub ram: u64,
+    /// Placement of every allocated section
+    pub sections: Vec<SectionPlacement>,
+    /// Usage of the requested regions
+    pub regions: Vec<RegionUsage>,
+}
+
+impl MemoryUsage {
+    /// Regions whose capacity is exceeded
+    pub fn overflowing(&self) -> impl Iterator<Item = &RegionUsage> {
+        self.regions.iter().filter(|usage| usage.is_overflowing())
+    }
+}
+
+impl std::fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "Memory region         Used Size  Region Size  %age Used")?;
+        for usage in self.regions.iter() {
+            writeln!(
+                f,
+                "{:>13}: {:>12} B {:>10} B {:>9.2}%",
+                usage.region.name,
+                usage.used + usage.overflow,
+                usage.region.length,
+                usage.percent_used()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl File {
+    /// Compute flash and RAM usage, and the usage of each of the given regions.
+    ///
+    /// Sections occupy their run-time address range; sections with contents
+    /// whose load address differs from their run-time address also occupy
+    /// their load address range.
+    pub fn memory_usage(&self, regions: &[MemoryRegion]) -> MemoryUsage {
+        let mut sections = Vec::new();
+        for section in self.sections.iter() {
+            let shdr = &section.shdr;
+            if shdr.flags.0 & types::SHF_ALLOC.0 == 0 || shdr.size == 0 {
+                continue;
+            }
+            let lma = self.get_section_lma(section);
+            let placement = if shdr.shtype == types::SectionType::Nobits {
+                Placement::Ram
+            } else if lma != shdr.addr || shdr.flags.0 & types::SHF_WRITE.0 != 0 {
+                Placement::FlashAndRam
+            } else {
+                Placement::Flash
+            };
+            sections.push(SectionPlacement {
+                name: shdr.name.clone(),
+                vma: shdr.addr,
+                lma,
+                size: shdr.size,
+                placement,
+            });
+        }
+
+        let flash = sections
+            .iter()
+            .filter(|s| s.placement != Placement::Ram)
+            .map(|s| s.size)
+            .sum();
+        let ram = sections
+            .iter()
+            .filter(|s| s.placement != Placement::Flash)
+            .map(|s| s.size)
+            .sum();
+
+        let regions = regions
+            .iter()
+            .map(|region| {
+                let mut usage = RegionUsage {
+                    region: region.clone(),
+                    used: 0,
+                    overflow: 0,
+                    sections: Vec::new(),
+                };
+                for section in sections.iter() {
+                    let mut ranges = vec![section.vma];
+                    if section.placement != Placement::Ram && section.lma != section.vma {
+                        ranges.push(section.lma);
+                    }
+                    for start in ranges {
+                        let inside = region.overlap(start, section.size);
+                        let starts_inside = start >= region.origin && start < region.end();
+                        if inside == 0 && !starts_inside {
+                            continue;
+                        }
+                        usage.used += inside;
+                        if starts_inside {
+                            usage.overflow += section.size - inside;
+                        }
+                        if !usage.sections.contains(&section.name) {
+                            usage.sections.push(section.name.clone());
+                        }
+                    }
+                }
+                usage
+            })
+            .collect();
+
+        MemoryUsage {
+            flash,
+            ram,
+            sections,
+            regions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryRegion, Placement};
+    use crate::File;
+
+    #[test]
+    fn test_memory_usage() {
+        let file = File::open_path("tests/samples/x86_64_firmware").unwrap();
+        let regions = [
+            MemoryRegion::new("FLASH", 0x0800_0000, 64 * 1024),
+            MemoryRegion::new("RAM", 0x2000_0000, 8 * 1024),
+        ];
+        let usage = file.memory_usage(&regions);
+
+        let data = usage.sections.iter().find(|s| s.name == ".data").unwrap();
+        assert_eq!(data.placement, Placement::FlashAndRam);
+        assert_eq!(data.vma, 0x2000_0000);
+        assert_eq!(data.lma, 0x0800_0049);
+        let bss = usage.sections.iter().find(|s| s.name == ".bss").unwrap();
+        assert_eq!(bss.placement, Placement::Ram);
+
+        assert_eq!(usage.flash, 0x3f + 0x9 + 0x44);
+        assert_eq!(usage.ram, 0x44 + 0x100);
+        assert_eq!(usage.regions[0].used, usage.flash);
+        assert_eq!(usage.regions[1].used, usage.ram);
+        assert_eq!(usage.overflowing().count(), 0);
+
+        let tiny = [MemoryRegion::new("RAM", 0x2000_0000, 0x80)];
+        let usage = file.memory_usage(&tiny);
+        let overflowing: Vec<_> = usage.overflowing().collect();
+        assert_eq!(overflowing.len(), 1);
+        assert_eq!(overflowing[0].overflow, 0x60 + 0x100 - 0x80);
+
+        // Regions reaching past the end of the address space are clamped to it
+        let everything = [MemoryRegion::new("ALL", 0x1000, u64::MAX)];
+        let usage = file.memory_usage(&everything);
+        assert_eq!(usage.regions[0].used, usage.flash + 0x44 + 0x100);
+    }
+}
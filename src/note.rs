@@ -0,0 +1,71 @@
+use crate::types;
+use crate::{File, ParseError, Section};
+use std::io;
+
+/// A single entry of an SHT_NOTE section or PT_NOTE segment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Note {
+    /// Name of the note owner, e.g. "GNU"
+    pub name: String,
+    /// Note type, interpreted relative to the owner name
+    pub ntype: u32,
+    /// Note descriptor
+    pub desc: Vec<u8>,
+}
+
+fn align_up(value: u64, align: u64) -> u64 {
+    (value + align - 1) & !(align - 1)
+}
+
+impl File {
+    /// Parse the notes in an SHT_NOTE section.
+    pub fn get_notes(&self, section: &Section) -> Result<Vec<Note>, ParseError> {
+        if section.shdr.shtype != types::SectionType::Note {
+            return Ok(Vec::new());
+        }
+        self.parse_notes(&section.data, section.shdr.addralign)
+    }
+
+    /// Parse the notes in a PT_NOTE or PT_GNU_PROPERTY segment.
+    pub fn get_segment_notes(&self, phdr: &types::ProgramHeader) -> Result<Vec<Note>, ParseError> {
+        self.parse_notes(&self.get_segment_data(phdr), phdr.align)
+    }
+
+    /// Parse a sequence of notes.
+    ///
+    /// Notes are 4-byte aligned, except in sections or segments with 8-byte
+    /// alignment where the name and descriptor are padded to 8 bytes.
+    pub(crate) fn parse_notes(&self, data: &[u8], align: u64) -> Result<Vec<Note>, ParseError> {
+        let align = if align == 8 { 8 } else { 4 };
+        let mut notes = Vec::new();
+        let mut io_section = io::Cursor::new(data);
+        while io_section.position() + 12 <= data.len() as u64 {
+            let namesz = read_u32!(self, io_section)? as u64;
+            let descsz = read_u32!(self, io_section)? as u64;
+            let ntype = read_u32!(self, io_section)?;
+
+            let name_start = io_section.position();
+            let desc_start = align_up(name_start + namesz, align);
+            let desc_end = desc_start + descsz;
+            if desc_end > data.len() as u64 {
+                return Err(ParseError::InvalidFormat(Some(
+                    "Note extends past end of data".to_string(),
+                )));
+            }
+
+            let name_end = (name_start + namesz) as usize;
+            let name = data[name_start as usize..name_end]
+                .iter()
+                .take_while(|&&b| b != 0)
+                .map(|&b| b as char)
+                .collect();
+            notes.push(Note {
+                name,
+                ntype,
+                desc: data[desc_start as usize..desc_end as usize].to_vec(),
+            });
+            io_section.set_position(align_up(desc_end, align));
+        }
+        Ok(notes)
+    }
+}
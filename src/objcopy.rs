@@ -0,0 +1,398 @@
+use crate::types;
+use crate::{File, ParseError, Section};
+
+/// Number of data bytes per Intel HEX or S-record line
+const RECORD_SIZE: usize = 16;
+
+/// Options for exporting the loadable contents of a file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Byte used to fill gaps between sections in flat binaries
+    pub fill: u8,
+    /// Only export these sections, if not empty (like `objcopy -j`)
+    pub only_sections: Vec<String>,
+    /// Never export these sections (like `objcopy -R`)
+    pub remove_sections: Vec<String>,
+}
+
+/// A flat memory image, as produced by `objcopy -O binary`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlatImage {
+    /// Physical address of the first byte
+    pub base_address: u64,
+    /// Image contents
+    pub data: Vec<u8>,
+}
+
+impl ExportOptions {
+    fn includes(&self, section: &Section) -> bool {
+        let name = &section.shdr.name;
+        (self.only_sections.is_empty() || self.only_sections.contains(name))
+            && !self.remove_sections.contains(name)
+    }
+}
+
+impl File {
+    /// The exported chunks: (physical address, contents) of the file
+    /// contents of every PT_LOAD segment, sorted by physical address.
+    ///
+    /// Only the selected allocated sections are taken from each segment, so
+    /// headers and padding in the segment are left out. Files without section
+    /// headers export whole segments, and files without segments fall back to
+    /// the sections at their own addresses. Files built in memory have no
+    /// segment contents, so exporting segments without sections is an error.
+    fn export_chunks(&self, options: &ExportOptions) -> Result<Vec<(u64, &[u8])>, ParseError> {
+        let selected = |section: &&Section| {
+            section.shdr.flags.0 & types::SHF_ALLOC.0 != 0
+                && section.shdr.shtype != types::SectionType::Nobits
+                && !section.data.is_empty()
+                && options.includes(section)
+        };
+        let loads: Vec<&types::ProgramHeader> = self
+            .phdrs
+            .iter()
+            .filter(|phdr| phdr.progtype == types::PT_LOAD && phdr.filesz != 0)
+            .collect();
+        let mut chunks: Vec<(u64, &[u8])> = Vec::new();
+        if loads.is_empty() {
+            chunks.extend(
+                self.sections
+                    .iter()
+                    .filter(selected)
+                    .map(|section| (section.shdr.addr, section.data.as_slice())),
+            );
+        } else if self.sections.len() <= 1 {
+            for phdr in loads {
+                let data = self.get_segment_bytes(phdr).ok_or_else(|| {
+                    ParseError::InvalidFormat(Some(format!(
+                        "No contents for the segment at {:#x}",
+                        phdr.vaddr
+                    )))
+                })?;
+                chunks.push((phdr.paddr, data));
+            }
+        } else {
+            for phdr in loads {
+                let end = phdr.offset.saturating_add(phdr.filesz);
+                chunks.extend(
+                    self.sections
+                        .iter()
+                        .filter(selected)
+                        .filter(|section| {
+                            section.shdr.offset >= phdr.offset && section.shdr.offset < end
+                        })
+                        .map(|section| {
+                            let len = (end - section.shdr.offset).min(section.data.len() as u64);
+                            (
+                                phdr.paddr + (section.shdr.offset - phdr.offset),
+                                &section.data[..len as usize],
+                            )
+                        }),
+                );
+            }
+        }
+        chunks.sort_by_key(|&(address, _)| address);
+        Ok(chunks)
+    }
+
+    /// Build a flat binary image of the loadable contents, like `objcopy -O binary`.
+    ///
+    /// Segment contents are placed by physical address, starting at the lowest
+    /// one, and gaps between them are filled with [ExportOptions::fill].
+    pub fn to_binary(&self, options: &ExportOptions) -> Result<FlatImage, ParseError> {
+        let chunks = self.export_chunks(options)?;
+        let base_address = chunks.first().map_or(0, |&(address, _)| address);
+        let end = chunks
+            .iter()
+            .map(|&(address, data)| address + data.len() as u64)
+            .max()
+            .unwrap_or(base_address);
+        let mut data = vec![options.fill; (end - base_address) as usize];
+        for (address, contents) in chunks {
+            let start = (address - base_address) as usize;
+            data[start..start + contents.len()].copy_from_slice(contents);
+        }
+        Ok(FlatImage { base_address, data })
+    }
+
+    /// Export the loadable contents in Intel HEX format, like `objcopy -O ihex`.
+    ///
+    /// Addresses above 4GiB cannot be represented and are reported as errors.
+    pub fn to_ihex(&self, options: &ExportOptions) -> Result<String, ParseError> {
+        let mut out = String::new();
+        let mut upper: Option<u64> = None;
+        for (address, contents) in self.export_chunks(options)? {
+            if address + contents.len() as u64 > 0x1_0000_0000 {
+                return Err(ParseError::InvalidFormat(Some(format!(
+                    "Address {:#x} does not fit in Intel HEX",
+                    address
+                ))));
+            }
+            let mut offset = 0;
+            while offset < contents.len() {
+                let current = address + offset as u64;
+                if upper != Some(current >> 16) {
+                    upper = Some(current >> 16);
+                    push_ihex_record(&mut out, 0, 0x04, &((current >> 16) as u16).to_be_bytes());
+                }
+                // Records must not cross a 64KiB boundary
+                let boundary = (0x1_0000 - (current & 0xffff)) as usize;
+                let len = RECORD_SIZE.min(contents.len() - offset).min(boundary);
+                push_ihex_record(
+                    &mut out,
+                    current as u16,
+                    0x00,
+                    &contents[offset..offset + len],
+                );
+                offset += len;
+            }
+        }
+        if self.header.entry != 0 && self.header.entry <= 0xffff_ffff {
+            push_ihex_record(&mut out, 0, 0x05, &(self.header.entry as u32).to_be_bytes());
+        }
+        push_ihex_record(&mut out, 0, 0x01, &[]);
+        Ok(out)
+    }
+
+    /// Export the loadable contents as Motorola S-records, like `objcopy -O srec`.
+    ///
+    /// The smallest address size that fits every address is used (S1, S2 or S3).
+    pub fn to_srec(&self, options: &ExportOptions) -> Result<String, ParseError> {
+        let chunks = self.export_chunks(options)?;
+        let max_address = chunks
+            .iter()
+            .map(|&(address, data)| address + data.len() as u64)
+            .chain(std::iter::once(self.header.entry))
+            .max()
+            .unwrap_or(0);
+        let (data_type, end_type, address_len) = if max_address <= 0x1_0000 {
+            (1, 9, 2)
+        } else if max_address <= 0x100_0000 {
+            (2, 8, 3)
+        } else if max_address <= 0x1_0000_0000 {
+            (3, 7, 4)
+        } else {
+            return Err(ParseError::InvalidFormat(Some(format!(
+                "Address {:#x} does not fit in S-records",
+                max_address
+            ))));
+        };
+
+        let mut out = String::new();
+        push_srec_record(&mut out, 0, 0, 2, b"elf");
+        let mut count = 0u64;
+        for (address, contents) in chunks {
+            for (i, line) in contents.chunks(RECORD_SIZE).enumerate() {
+                let current = address + (i * RECORD_SIZE) as u64;
+                push_srec_record(&mut out, data_type, current, address_len, line);
+                count += 1;
+            }
+        }
+        if count <= 0xffff {
+            push_srec_record(&mut out, 5, count, 2, &[]);
+        } else if count <= 0xff_ffff {
+            push_srec_record(&mut out, 6, count, 3, &[]);
+        }
+        push_srec_record(&mut out, end_type, self.header.entry, address_len, &[]);
+        Ok(out)
+    }
+}
+
+fn push_ihex_record(out: &mut String, address: u16, record_type: u8, data: &[u8]) {
+    let mut bytes = vec![data.len() as u8];
+    bytes.extend_from_slice(&address.to_be_bytes());
+    bytes.push(record_type);
+    bytes.extend_from_slice(data);
+    let sum = bytes.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
+    bytes.push(sum.wrapping_neg());
+
+    out.push(':');
+    for b in bytes {
+        out.push_str(&format!("{:02X}", b));
+    }
+    out.push('\n');
+}
+
+fn push_srec_record(
+    out: &mut String,
+    record_type: u8,
+    address: u64,
+    address_len: usize,
+    data: &[u8],
+) {
+    let address_bytes = address.to_be_bytes();
+    let mut bytes = vec![(address_len + data.len() + 1) as u8];
+    bytes.extend_from_slice(&address_bytes[8 - address_len..]);
+    bytes.extend_from_slice(data);
+    let sum = bytes.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
+    bytes.push(!sum);
+
+    out.push_str(&format!("S{}", record_type));
+    for b in bytes {
+        out.push_str(&format!("{:02X}", b));
+    }
+    out.push('\n');
+}
+
+/// Wrap a raw binary into a minimal executable, like `objcopy -I binary`.
+///
+/// The result has a single loadable, executable and writable section named
+/// `section_name` at `load_address`, covered by one PT_LOAD segment, and uses
+/// the class, endianness and machine of `header`. The entry point is set to
+/// the load address.
+pub fn binary_to_elf(
+    data: &[u8],
+    load_address: u64,
+    section_name: &str,
+    header: &types::FileHeader,
+) -> File {
+    let ehsize: u64 = match header.class {
+        types::ElfClass::Format32 => 52 + 32,
+        types::ElfClass::Format64 => 64 + 56,
+    };
+    // Keep the file offset congruent to the address modulo the page size
+    let page = 0x1000;
+    let offset = ehsize.div_ceil(page) * page + (load_address % page);
+
+    let mut file = File::new();
+    file.header = *header;
+    file.header.elftype = types::ElfFileType::Executable;
+    file.header.entry = load_address;
+    file.phdrs.push(types::ProgramHeader {
+        progtype: types::PT_LOAD,
+        offset,
+        vaddr: load_address,
+        paddr: load_address,
+        filesz: data.len() as u64,
+        memsz: data.len() as u64,
+        flags: types::ProgFlag(types::PF_R.0 | types::PF_W.0 | types::PF_X.0),
+        align: page,
+    });
+    file.sections.push(Section {
+        shdr: types::SectionHeader {
+            name: String::new(),
+            shtype: types::SectionType::Null,
+            flags: types::SHF_NONE,
+            addr: 0,
+            offset: 0,
+            size: 0,
+            link: 0,
+            info: 0,
+            addralign: 0,
+            entsize: 0,
+        },
+        data: Vec::new(),
+    });
+    file.sections.push(Section {
+        shdr: types::SectionHeader {
+            name: section_name.to_string(),
+            shtype: types::SectionType::Progbits,
+            flags: types::SectionFlag(
+                types::SHF_ALLOC.0 | types::SHF_WRITE.0 | types::SHF_EXECINSTR.0,
+            ),
+            addr: load_address,
+            offset,
+            size: data.len() as u64,
+            link: 0,
+            info: 0,
+            addralign: 1,
+            entsize: 0,
+        },
+        data: data.to_vec(),
+    });
+    file.sections.push(Section {
+        shdr: types::SectionHeader {
+            name: ".shstrtab".to_string(),
+            shtype: types::SectionType::Strtab,
+            flags: types::SHF_NONE,
+            addr: 0,
+            offset: 0,
+            size: 0,
+            link: 0,
+            info: 0,
+            addralign: 1,
+            entsize: 0,
+        },
+        data: Vec::new(),
+    });
+    file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{binary_to_elf, ExportOptions};
+    use crate::File;
+    use std::io;
+
+    #[test]
+    fn test_export_firmware() {
+        let file = File::open_path("tests/samples/x86_64_firmware").unwrap();
+        let options = ExportOptions {
+            fill: 0xff,
+            ..Default::default()
+        };
+        let image = file.to_binary(&options).unwrap();
+        assert_eq!(image.base_address, 0x0800_0000);
+        // .text, .rodata and the .data initializers stored right after them
+        assert_eq!(image.data.len(), 0x49 + 0x44);
+        assert_eq!(image.data[0x3f], 0xff);
+        let data = file.get_section(".data").unwrap();
+        assert_eq!(&image.data[0x49..], &data.data[..]);
+
+        let ihex = file.to_ihex(&options).unwrap();
+        let lines: Vec<&str> = ihex.lines().collect();
+        assert_eq!(lines[0], ":020000040800F2");
+        assert_eq!(*lines.last().unwrap(), ":00000001FF");
+
+        let srec = file.to_srec(&options).unwrap();
+        assert!(srec.lines().nth(1).unwrap().starts_with("S3150800000"));
+        assert!(srec.lines().last().unwrap().starts_with("S705080000"));
+
+        let only_data = ExportOptions {
+            only_sections: vec![".data".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            file.to_binary(&only_data).unwrap().base_address,
+            0x0800_0049
+        );
+
+        // Without section headers, whole segments are exported
+        let mut stripped = File::open_path("tests/samples/x86_64_firmware").unwrap();
+        stripped.sections.clear();
+        let stripped_image = stripped.to_binary(&options).unwrap();
+        assert_eq!(stripped_image.base_address, 0x0800_0000);
+        assert_eq!(stripped_image.data[..0x3f], image.data[..0x3f]);
+        assert_eq!(stripped_image.data[0x49..], image.data[0x49..]);
+    }
+
+    #[test]
+    fn test_binary_to_elf() {
+        let mut header = File::open_path("tests/samples/x86_64_firmware")
+            .unwrap()
+            .header;
+        header.flags = 0x0500_0400;
+        let file = binary_to_elf(&[1, 2, 3, 4], 0x2000_0010, ".data", &header);
+        // Built in memory, the segment contents come from the section
+        assert_eq!(
+            file.to_binary(&ExportOptions::default()).unwrap().data,
+            vec![1, 2, 3, 4]
+        );
+        let mut bare = binary_to_elf(&[1, 2, 3, 4], 0x2000_0010, ".data", &header);
+        bare.sections.clear();
+        assert!(bare.to_binary(&ExportOptions::default()).is_err());
+        let mut buffer = io::Cursor::new(Vec::new());
+        file.write_stream(&mut buffer).unwrap();
+        buffer.set_position(0);
+
+        let file = File::open_stream(&mut buffer).unwrap();
+        assert_eq!(file.header.entry, 0x2000_0010);
+        assert_eq!(file.header.flags, 0x0500_0400);
+        assert_eq!(file.get_section(".data").unwrap().data, vec![1, 2, 3, 4]);
+        assert_eq!(file.get_segment_data(&file.phdrs[0]), vec![1, 2, 3, 4]);
+        assert_eq!(
+            file.to_binary(&ExportOptions::default()).unwrap().data,
+            vec![1, 2, 3, 4]
+        );
+    }
+}
@@ -0,0 +1,679 @@
+use crate::dwarf::Reader;
+use crate::types;
+use crate::{File, ParseError, Section};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::collections::HashMap;
+use std::io;
+
+/// A relocation entry from an SHT_REL or SHT_RELA section.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Relocation {
+    /// Offset of the storage unit to relocate, relative to the start of the
+    /// target section in relocatable objects, or a virtual address otherwise
+    pub offset: u64,
+    /// Index of the referenced symbol in the associated symbol table
+    pub symbol: u32,
+    /// Machine specific relocation type
+    pub rtype: u32,
+    /// Explicit addend for SHT_RELA entries. SHT_REL entries store the addend
+    /// in the relocated field instead.
+    pub addend: Option<i64>,
+}
+
+impl std::fmt::Display for Relocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Relocation: Offset: {:#010x} Type: {} Symbol: {}",
+            self.offset, self.rtype, self.symbol
+        )?;
+        if let Some(addend) = self.addend {
+            write!(f, " Addend: {:#x}", addend)?;
+        }
+        Ok(())
+    }
+}
+
+/// How a relocation type computes its value and stores it in the relocated field.
+///
+/// S is the symbol value, A the addend, P the address of the field and Z the
+/// symbol size.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Operation {
+    /// Leave the field unchanged
+    None,
+    /// Store S + A in a field of the given width
+    Absolute(usize),
+    /// Store S + A in a field of the given width, zero extended on use
+    Unsigned(usize),
+    /// Store S + A in a field of the given width, sign extended on use
+    Signed(usize),
+    /// Store S + A in a field of the given width, truncating it
+    Set(usize),
+    /// Store S + A - P in a field of the given width
+    Relative(usize),
+    /// Store Z + A in a field of the given width
+    Size(usize),
+    /// Add S + A to a field of the given width
+    Add(usize),
+    /// Subtract S + A from a field of the given width
+    Sub(usize),
+    /// Store S + A in the low 6 bits of a byte
+    Set6,
+    /// Subtract S + A from the low 6 bits of a byte
+    Sub6,
+    /// Store S + A in a ULEB128 field, keeping its encoded length
+    SetUleb128,
+    /// Subtract S + A from a ULEB128 field, keeping its encoded length
+    SubUleb128,
+    /// Store (S + A - P) >> 2 in the 26 bit immediate of an AArch64 branch
+    Aarch64Branch26,
+    /// Store (S + A - P) >> 2 in the 19 bit immediate of an AArch64 conditional branch
+    Aarch64Branch19,
+    /// Store the page offset Page(S + A) - Page(P) in an AArch64 ADRP instruction
+    Aarch64AdrpPage,
+    /// Store (S + A) & 0xfff, scaled down by the given shift, in the 12 bit
+    /// immediate of an AArch64 ADD or load/store instruction
+    Aarch64Lo12(u32),
+    /// Store (S + A - P) >> 2 in the 24 bit immediate of an ARM branch
+    ArmBranch24,
+    /// Store S + A - P in the low 31 bits of a word
+    Prel31,
+}
+
+/// How a value must fit in the relocated field.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Overflow {
+    /// The field holds a signed value
+    Signed,
+    /// The field holds an unsigned value
+    Unsigned,
+    /// The field holds either a signed or an unsigned value
+    Bitfield,
+}
+
+impl File {
+    /// Parse the entries of an SHT_REL or SHT_RELA section.
+    pub fn get_relocations(&self, section: &Section) -> Result<Vec<Relocation>, ParseError> {
+        let rela = match section.shdr.shtype {
+            types::SectionType::Rel => false,
+            types::SectionType::Rela => true,
+            _ => {
+                return Err(ParseError::InvalidFormat(Some(format!(
+                    "{} is not a relocation section",
+                    section.shdr.name
+                ))))
+            }
+        };
+
+        self.parse_relocations(&section.data, rela)
+    }
+
+    /// Parse a table of relocation entries, with explicit addends if `rela` is set.
+    pub(crate) fn parse_relocations(
+        &self,
+        data: &[u8],
+        rela: bool,
+    ) -> Result<Vec<Relocation>, ParseError> {
+        let entsize = match (self.header.class, rela) {
+            (types::ElfClass::Format32, false) => 8,
+            (types::ElfClass::Format32, true) => 12,
+            (types::ElfClass::Format64, false) => 16,
+            (types::ElfClass::Format64, true) => 24,
+        };
+        let mut relocations = Vec::new();
+        let mut io_section = io::Cursor::new(data);
+        while io_section.position() as usize + entsize <= data.len() {
+            let relocation = match self.header.class {
+                types::ElfClass::Format32 => {
+                    let offset = read_u32!(self, io_section)? as u64;
+                    let info = read_u32!(self, io_section)?;
+                    let addend = if rela {
+                        Some(read_u32!(self, io_section)? as i32 as i64)
+                    } else {
+                        None
+                    };
+                    Relocation {
+                        offset,
+                        symbol: info >> 8,
+                        rtype: info & 0xff,
+                        addend,
+                    }
+                }
+                types::ElfClass::Format64 => {
+                    let offset = read_u64!(self, io_section)?;
+                    let info = read_u64!(self, io_section)?;
+                    let addend = if rela {
+                        Some(read_u64!(self, io_section)? as i64)
+                    } else {
+                        None
+                    };
+                    Relocation {
+                        offset,
+                        symbol: (info >> 32) as u32,
+                        rtype: info as u32,
+                        addend,
+                    }
+                }
+            };
+            relocations.push(relocation);
+        }
+        Ok(relocations)
+    }
+
+    /// Get the SHT_REL and SHT_RELA sections that apply to a section.
+    pub fn get_relocation_sections(&self, target: &Section) -> Vec<&Section> {
+        let index = match self.section_index(target) {
+            Some(index) => index,
+            None => return Vec::new(),
+        };
+        self.sections
+            .iter()
+            .filter(|section| {
+                (section.shdr.shtype == types::SectionType::Rel
+                    || section.shdr.shtype == types::SectionType::Rela)
+                    && section.shdr.info as usize == index
+            })
+            .collect()
+    }
+
+    /// Get the contents of a section with its SHT_REL and SHT_RELA relocations applied.
+    ///
+    /// Sections are assumed to be loaded at the addresses in `addresses`,
+    /// keyed by section header index, and at their `sh_addr` otherwise. Both
+    /// symbol values and the addresses of relocated fields are computed
+    /// relative to these bases, so for relocatable objects this resolves
+    /// placeholders the same way a linker placing the sections there would.
+    /// Undefined symbols resolve to zero.
+    ///
+    /// Relocations are supported for x86_64, i386, AArch64, ARM and RISC-V.
+    /// Relocation types that cannot be applied statically, such as GOT or TLS
+    /// references, are reported as errors.
+    pub fn get_relocated_section_data(
+        &self,
+        section: &Section,
+        addresses: &HashMap<usize, u64>,
+    ) -> Result<Vec<u8>, ParseError> {
+        let index = self
+            .section_index(section)
+            .ok_or(ParseError::InvalidFormat(Some(
+                "Section does not belong to this file".to_string(),
+            )))?;
+        let base = |index: usize| -> u64 {
+            addresses.get(&index).copied().unwrap_or_else(|| {
+                self.sections
+                    .get(index)
+                    .map_or(0, |section| section.shdr.addr)
+            })
+        };
+        let section_base = base(index);
+        self.relocate_section(section, section_base, |symbol| {
+            Ok(match symbol.shndx {
+                types::SectionIndex::Index(index) => {
+                    let shdr_addr = self
+                        .sections
+                        .get(index as usize)
+                        .map_or(0, |section| section.shdr.addr);
+                    symbol
+                        .value
+                        .wrapping_sub(shdr_addr)
+                        .wrapping_add(base(index as usize))
+                }
+                types::SectionIndex::Absolute => symbol.value,
+                _ => 0,
+            })
+        })
+    }
+
+    /// Apply the relocations of a section loaded at `section_base`, using
+    /// `symbol_value` to compute the address of each referenced symbol.
+    pub(crate) fn relocate_section<F>(
+        &self,
+        section: &Section,
+        section_base: u64,
+        mut symbol_value: F,
+    ) -> Result<Vec<u8>, ParseError>
+    where
+        F: FnMut(&types::Symbol) -> Result<u64, ParseError>,
+    {
+        let mut data = section.data.clone();
+        for rel_section in self.get_relocation_sections(section) {
+            let symtab = self.sections.get(rel_section.shdr.link as usize).ok_or(
+                ParseError::InvalidFormat(Some(format!(
+                    "Invalid sh_link in {}",
+                    rel_section.shdr.name
+                ))),
+            )?;
+            let symbols = self.get_symbols(symtab)?;
+
+            for relocation in self.get_relocations(rel_section)? {
+                let operation = self.relocation_operation(relocation.rtype).ok_or(
+                    ParseError::InvalidFormat(Some(format!(
+                        "Unsupported {} relocation type {} in {}",
+                        self.header.cpu_architecture, relocation.rtype, rel_section.shdr.name
+                    ))),
+                )?;
+                let symbol =
+                    symbols
+                        .get(relocation.symbol as usize)
+                        .ok_or(ParseError::InvalidFormat(Some(format!(
+                            "Invalid symbol index {} in {}",
+                            relocation.symbol, rel_section.shdr.name
+                        ))))?;
+
+                let offset = if self.header.elftype == types::ElfFileType::RelocatableObject {
+                    relocation.offset
+                } else {
+                    relocation.offset.wrapping_sub(section.shdr.addr)
+                } as usize;
+                let s = symbol_value(symbol)?;
+                let p = section_base.wrapping_add(offset as u64);
+
+                let field = data.get_mut(offset..).unwrap_or_default();
+                if field.len() < operation_width(operation) {
+                    return Err(ParseError::InvalidFormat(Some(format!(
+                        "Relocation offset {:#x} out of bounds in {}",
+                        relocation.offset, rel_section.shdr.name
+                    ))));
+                }
+                let a = match relocation.addend {
+                    Some(addend) => addend as u64,
+                    None => self.implicit_addend(operation, field),
+                };
+                self.apply_operation(operation, field, s.wrapping_add(a), p, symbol.size, a)?;
+            }
+        }
+        Ok(data)
+    }
+
+    fn relocation_operation(&self, rtype: u32) -> Option<Operation> {
+        use types::ElfCpuArchitecture::*;
+        let operation = match (self.header.cpu_architecture, rtype) {
+            (EM_X86_64, types::R_X86_64_NONE) => Operation::None,
+            (EM_X86_64, types::R_X86_64_64) => Operation::Absolute(8),
+            (EM_X86_64, types::R_X86_64_PC32) => Operation::Relative(4),
+            (EM_X86_64, types::R_X86_64_PLT32) => Operation::Relative(4),
+            (EM_X86_64, types::R_X86_64_32) => Operation::Unsigned(4),
+            (EM_X86_64, types::R_X86_64_32S) => Operation::Signed(4),
+            (EM_X86_64, types::R_X86_64_16) => Operation::Absolute(2),
+            (EM_X86_64, types::R_X86_64_PC16) => Operation::Relative(2),
+            (EM_X86_64, types::R_X86_64_8) => Operation::Absolute(1),
+            (EM_X86_64, types::R_X86_64_PC8) => Operation::Relative(1),
+            (EM_X86_64, types::R_X86_64_PC64) => Operation::Relative(8),
+            (EM_X86_64, types::R_X86_64_SIZE32) => Operation::Size(4),
+            (EM_X86_64, types::R_X86_64_SIZE64) => Operation::Size(8),
+
+            (EM_386, types::R_386_NONE) => Operation::None,
+            (EM_386, types::R_386_32) => Operation::Absolute(4),
+            (EM_386, types::R_386_PC32) => Operation::Relative(4),
+            (EM_386, types::R_386_PLT32) => Operation::Relative(4),
+            (EM_386, types::R_386_16) => Operation::Absolute(2),
+            (EM_386, types::R_386_PC16) => Operation::Relative(2),
+            (EM_386, types::R_386_8) => Operation::Absolute(1),
+            (EM_386, types::R_386_PC8) => Operation::Relative(1),
+
+            (EM_AARCH64, types::R_AARCH64_NONE) => Operation::None,
+            (EM_AARCH64, types::R_AARCH64_ABS64) => Operation::Absolute(8),
+            (EM_AARCH64, types::R_AARCH64_ABS32) => Operation::Absolute(4),
+            (EM_AARCH64, types::R_AARCH64_ABS16) => Operation::Absolute(2),
+            (EM_AARCH64, types::R_AARCH64_PREL64) => Operation::Relative(8),
+            (EM_AARCH64, types::R_AARCH64_PREL32) => Operation::Relative(4),
+            (EM_AARCH64, types::R_AARCH64_PREL16) => Operation::Relative(2),
+            (EM_AARCH64, types::R_AARCH64_ADR_PREL_PG_HI21) => Operation::Aarch64AdrpPage,
+            (EM_AARCH64, types::R_AARCH64_ADD_ABS_LO12_NC) => Operation::Aarch64Lo12(0),
+            (EM_AARCH64, types::R_AARCH64_LDST8_ABS_LO12_NC) => Operation::Aarch64Lo12(0),
+            (EM_AARCH64, types::R_AARCH64_CONDBR19) => Operation::Aarch64Branch19,
+            (EM_AARCH64, types::R_AARCH64_JUMP26) => Operation::Aarch64Branch26,
+            (EM_AARCH64, types::R_AARCH64_CALL26) => Operation::Aarch64Branch26,
+            (EM_AARCH64, types::R_AARCH64_LDST16_ABS_LO12_NC) => Operation::Aarch64Lo12(1),
+            (EM_AARCH64, types::R_AARCH64_LDST32_ABS_LO12_NC) => Operation::Aarch64Lo12(2),
+            (EM_AARCH64, types::R_AARCH64_LDST64_ABS_LO12_NC) => Operation::Aarch64Lo12(3),
+            (EM_AARCH64, types::R_AARCH64_LDST128_ABS_LO12_NC) => Operation::Aarch64Lo12(4),
+
+            (EM_ARM, types::R_ARM_NONE) => Operation::None,
+            (EM_ARM, types::R_ARM_V4BX) => Operation::None,
+            (EM_ARM, types::R_ARM_ABS32) => Operation::Absolute(4),
+            (EM_ARM, types::R_ARM_TARGET1) => Operation::Absolute(4),
+            (EM_ARM, types::R_ARM_REL32) => Operation::Relative(4),
+            (EM_ARM, types::R_ARM_ABS16) => Operation::Absolute(2),
+            (EM_ARM, types::R_ARM_ABS8) => Operation::Absolute(1),
+            (EM_ARM, types::R_ARM_CALL) => Operation::ArmBranch24,
+            (EM_ARM, types::R_ARM_JUMP24) => Operation::ArmBranch24,
+            (EM_ARM, types::R_ARM_PREL31) => Operation::Prel31,
+
+            (EM_RISCV, types::R_RISCV_NONE) => Operation::None,
+            (EM_RISCV, types::R_RISCV_RELAX) => Operation::None,
+            (EM_RISCV, types::R_RISCV_32) => Operation::Absolute(4),
+            (EM_RISCV, types::R_RISCV_64) => Operation::Absolute(8),
+            (EM_RISCV, types::R_RISCV_32_PCREL) => Operation::Relative(4),
+            (EM_RISCV, types::R_RISCV_ADD8) => Operation::Add(1),
+            (EM_RISCV, types::R_RISCV_ADD16) => Operation::Add(2),
+            (EM_RISCV, types::R_RISCV_ADD32) => Operation::Add(4),
+            (EM_RISCV, types::R_RISCV_ADD64) => Operation::Add(8),
+            (EM_RISCV, types::R_RISCV_SUB8) => Operation::Sub(1),
+            (EM_RISCV, types::R_RISCV_SUB16) => Operation::Sub(2),
+            (EM_RISCV, types::R_RISCV_SUB32) => Operation::Sub(4),
+            (EM_RISCV, types::R_RISCV_SUB64) => Operation::Sub(8),
+            (EM_RISCV, types::R_RISCV_SET6) => Operation::Set6,
+            (EM_RISCV, types::R_RISCV_SUB6) => Operation::Sub6,
+            (EM_RISCV, types::R_RISCV_SET8) => Operation::Set(1),
+            (EM_RISCV, types::R_RISCV_SET16) => Operation::Set(2),
+            (EM_RISCV, types::R_RISCV_SET32) => Operation::Set(4),
+            (EM_RISCV, types::R_RISCV_SET_ULEB128) => Operation::SetUleb128,
+            (EM_RISCV, types::R_RISCV_SUB_ULEB128) => Operation::SubUleb128,
+            _ => return None,
+        };
+        Some(operation)
+    }
+
+    /// The addend stored in the relocated field of an SHT_REL entry
+    fn implicit_addend(&self, operation: Operation, field: &[u8]) -> u64 {
+        match operation {
+            Operation::Absolute(width) | Operation::Signed(width) | Operation::Relative(width) => {
+                sign_extend(self.read_field(field, width), width as u32 * 8)
+            }
+            Operation::Unsigned(width) => self.read_field(field, width),
+            Operation::ArmBranch24 => sign_extend(self.read_field(field, 4) & 0xff_ffff, 24) << 2,
+            Operation::Prel31 => sign_extend(self.read_field(field, 4) & 0x7fff_ffff, 31),
+            _ => 0,
+        }
+    }
+
+    fn apply_operation(
+        &self,
+        operation: Operation,
+        field: &mut [u8],
+        value: u64,
+        p: u64,
+        size: u64,
+        addend: u64,
+    ) -> Result<(), ParseError> {
+        match operation {
+            Operation::None => {}
+            Operation::Absolute(width) => {
+                let value = self.check_fits(value, width as u32 * 8, Overflow::Bitfield)?;
+                self.write_field(field, width, value);
+            }
+            Operation::Unsigned(width) => {
+                let value = self.check_fits(value, width as u32 * 8, Overflow::Unsigned)?;
+                self.write_field(field, width, value);
+            }
+            Operation::Signed(width) => {
+                let value = self.check_fits(value, width as u32 * 8, Overflow::Signed)?;
+                self.write_field(field, width, value);
+            }
+            Operation::Set(width) => self.write_field(field, width, value),
+            Operation::Relative(width) => {
+                let value =
+                    self.check_fits(value.wrapping_sub(p), width as u32 * 8, Overflow::Signed)?;
+                self.write_field(field, width, value);
+            }
+            Operation::Size(width) => {
+                let value = self.check_fits(
+                    size.wrapping_add(addend),
+                    width as u32 * 8,
+                    Overflow::Bitfield,
+                )?;
+                self.write_field(field, width, value);
+            }
+            Operation::Add(width) => {
+                let old = self.read_field(field, width);
+                self.write_field(field, width, old.wrapping_add(value));
+            }
+            Operation::Sub(width) => {
+                let old = self.read_field(field, width);
+                self.write_field(field, width, old.wrapping_sub(value));
+            }
+            Operation::Set6 => field[0] = (field[0] & 0xc0) | (value as u8 & 0x3f),
+            Operation::Sub6 => {
+                field[0] = (field[0] & 0xc0) | (field[0].wrapping_sub(value as u8) & 0x3f)
+            }
+            Operation::SetUleb128 => write_uleb128_in_place(field, value)?,
+            Operation::SubUleb128 => {
+                let old = read_uleb128_in_place(field)?;
+                write_uleb128_in_place(field, old.wrapping_sub(value))?;
+            }
+            Operation::Aarch64Branch26 => {
+                let insn = self.read_field(field, 4);
+                let imm = (self.check_fits(value.wrapping_sub(p), 28, Overflow::Signed)? >> 2)
+                    & 0x03ff_ffff;
+                self.write_field(field, 4, (insn & !0x03ff_ffff) | imm);
+            }
+            Operation::Aarch64Branch19 => {
+                let insn = self.read_field(field, 4);
+                let imm =
+                    (self.check_fits(value.wrapping_sub(p), 21, Overflow::Signed)? >> 2) & 0x7_ffff;
+                self.write_field(field, 4, (insn & !(0x7_ffff << 5)) | (imm << 5));
+            }
+            Operation::Aarch64AdrpPage => {
+                let insn = self.read_field(field, 4);
+                let page = (value & !0xfff).wrapping_sub(p & !0xfff);
+                let imm = (self.check_fits(page, 33, Overflow::Signed)? >> 12) & 0x1f_ffff;
+                let immlo = (imm & 0x3) << 29;
+                let immhi = (imm >> 2) << 5;
+                self.write_field(
+                    field,
+                    4,
+                    (insn & !((0x3 << 29) | (0x7_ffff << 5))) | immlo | immhi,
+                );
+            }
+            Operation::Aarch64Lo12(shift) => {
+                let insn = self.read_field(field, 4);
+                let imm = (value & 0xfff) >> shift;
+                self.write_field(field, 4, (insn & !(0xfff << 10)) | (imm << 10));
+            }
+            Operation::ArmBranch24 => {
+                let insn = self.read_field(field, 4);
+                let imm = (self.check_fits(value.wrapping_sub(p), 26, Overflow::Signed)? >> 2)
+                    & 0xff_ffff;
+                self.write_field(field, 4, (insn & !0xff_ffff) | imm);
+            }
+            Operation::Prel31 => {
+                let old = self.read_field(field, 4);
+                let offset =
+                    self.check_fits(value.wrapping_sub(p), 31, Overflow::Signed)? & 0x7fff_ffff;
+                self.write_field(field, 4, (old & 0x8000_0000) | offset);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that a value fits in a field of `bits` bits as `overflow` allows.
+    fn check_fits(&self, value: u64, bits: u32, overflow: Overflow) -> Result<u64, ParseError> {
+        // Values wrap around the address space of 32-bit targets
+        let (value, address_bits) = match self.header.class {
+            types::ElfClass::Format32 => (sign_extend(value & 0xffff_ffff, 32), 32),
+            types::ElfClass::Format64 => (value, 64),
+        };
+        let signed = || sign_extend(value, bits) == value;
+        let unsigned = || value >> bits == 0;
+        let fits = bits >= address_bits
+            || match overflow {
+                Overflow::Signed => signed(),
+                Overflow::Unsigned => unsigned(),
+                Overflow::Bitfield => signed() || unsigned(),
+            };
+        if fits {
+            Ok(value)
+        } else {
+            Err(ParseError::InvalidFormat(Some(format!(
+                "Relocation value {:#x} does not fit in {} bits",
+                value, bits
+            ))))
+        }
+    }
+
+    fn read_field(&self, field: &[u8], width: usize) -> u64 {
+        match (self.header.endianness, width) {
+            (_, 1) => field[0] as u64,
+            (types::ElfEndianness::Lsb, 2) => LittleEndian::read_u16(field) as u64,
+            (types::ElfEndianness::Lsb, 4) => LittleEndian::read_u32(field) as u64,
+            (types::ElfEndianness::Lsb, _) => LittleEndian::read_u64(field),
+            (types::ElfEndianness::Msb, 2) => BigEndian::read_u16(field) as u64,
+            (types::ElfEndianness::Msb, 4) => BigEndian::read_u32(field) as u64,
+            (types::ElfEndianness::Msb, _) => BigEndian::read_u64(field),
+        }
+    }
+
+    fn write_field(&self, field: &mut [u8], width: usize, value: u64) {
+        match (self.header.endianness, width) {
+            (_, 1) => field[0] = value as u8,
+            (types::ElfEndianness::Lsb, 2) => LittleEndian::write_u16(field, value as u16),
+            (types::ElfEndianness::Lsb, 4) => LittleEndian::write_u32(field, value as u32),
+            (types::ElfEndianness::Lsb, _) => LittleEndian::write_u64(field, value),
+            (types::ElfEndianness::Msb, 2) => BigEndian::write_u16(field, value as u16),
+            (types::ElfEndianness::Msb, 4) => BigEndian::write_u32(field, value as u32),
+            (types::ElfEndianness::Msb, _) => BigEndian::write_u64(field, value),
+        }
+    }
+}
+
+/// Number of bytes an operation reads or writes, at minimum
+fn operation_width(operation: Operation) -> usize {
+    match operation {
+        Operation::None => 0,
+        Operation::Absolute(width)
+        | Operation::Unsigned(width)
+        | Operation::Signed(width)
+        | Operation::Set(width)
+        | Operation::Relative(width)
+        | Operation::Size(width)
+        | Operation::Add(width)
+        | Operation::Sub(width) => width,
+        Operation::Set6 | Operation::Sub6 | Operation::SetUleb128 | Operation::SubUleb128 => 1,
+        Operation::Aarch64Branch26
+        | Operation::Aarch64Branch19
+        | Operation::Aarch64AdrpPage
+        | Operation::Aarch64Lo12(_)
+        | Operation::ArmBranch24
+        | Operation::Prel31 => 4,
+    }
+}
+
+fn sign_extend(value: u64, bits: u32) -> u64 {
+    let shift = 64 - bits;
+    (((value << shift) as i64) >> shift) as u64
+}
+
+fn uleb128_len(field: &[u8]) -> Result<usize, ParseError> {
+    field
+        .iter()
+        .position(|&byte| byte & 0x80 == 0)
+        .map(|position| position + 1)
+        .ok_or(ParseError::InvalidFormat(Some(
+            "Unterminated ULEB128 relocation field".to_string(),
+        )))
+}
+
+fn read_uleb128_in_place(field: &[u8]) -> Result<u64, ParseError> {
+    let len = uleb128_len(field)?;
+    // LEB128 values have no byte order
+    Reader::new(&field[..len], types::ElfEndianness::Lsb).uleb128()
+}
+
+fn write_uleb128_in_place(field: &mut [u8], value: u64) -> Result<(), ParseError> {
+    let len = uleb128_len(field)?;
+    let mut value = value;
+    for (i, byte) in field[..len].iter_mut().enumerate() {
+        *byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if i + 1 < len {
+            *byte |= 0x80;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Operation;
+    use crate::types;
+    use crate::File;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_relocations() {
+        let file = File::open_path(PathBuf::from("tests/samples/stack_sizes.o")).unwrap();
+        let text = file.get_section(".text.small").unwrap();
+        let sections = file.get_relocation_sections(text);
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].shdr.name, ".rela.text.small");
+
+        let relocations = file.get_relocations(sections[0]).unwrap();
+        assert_eq!(relocations.len(), 1);
+        assert_eq!(relocations[0].offset, 0xa);
+        assert_eq!(relocations[0].rtype, 4);
+        assert_eq!(relocations[0].addend, Some(-4));
+        let symtab = file.get_section(".symtab").unwrap();
+        let symbols = file.get_symbols(symtab).unwrap();
+        assert_eq!(symbols[relocations[0].symbol as usize].name, "use");
+    }
+
+    #[test]
+    fn test_relocated_section_data() {
+        let file = File::open_path(PathBuf::from("tests/samples/stack_sizes.o")).unwrap();
+        let addresses = HashMap::from([(3, 0x1000), (11, 0x2000)]);
+        let text = file.get_section(".text.small").unwrap();
+        let data = file.get_relocated_section_data(text, &addresses).unwrap();
+        // call use: 0x2000 - 4 - 0x100a
+        assert_eq!(data[0xa..0xe], [0xf2, 0x0f, 0x00, 0x00]);
+        // The call target is out of reach of a 32-bit displacement
+        let addresses = HashMap::from([(3, 0x1000), (11, 0x1_0000_2000)]);
+        assert!(file.get_relocated_section_data(text, &addresses).is_err());
+
+        // SHT_REL with addends stored in the relocated fields
+        let file = File::open_path(PathBuf::from("tests/samples/reloc_arm.o")).unwrap();
+        let addresses = HashMap::from([(2, 0x8000), (4, 0x9000), (6, 0xa000)]);
+        let text = file.get_section(".text").unwrap();
+        let data = file.get_relocated_section_data(text, &addresses).unwrap();
+        assert_eq!(data[8..12], [0x00, 0x00, 0x00, 0xeb]);
+        let exidx = file.get_section(".ARM.exidx").unwrap();
+        let data = file.get_relocated_section_data(exidx, &addresses).unwrap();
+        assert_eq!(data[0..4], [0x00, 0xf0, 0xff, 0x7f]);
+        assert_eq!(data[8..12], [0x08, 0xf0, 0xff, 0x7f]);
+        // Index entries reach functions within +-1GB
+        let addresses = HashMap::from([(2, 0x8000), (4, 0x5000_0000), (6, 0xa000)]);
+        assert!(file.get_relocated_section_data(exidx, &addresses).is_err());
+
+        // Label differences through ADD/SUB pairs
+        let file = File::open_path(PathBuf::from("tests/samples/reloc_riscv.o")).unwrap();
+        let addresses = HashMap::from([(2, 0x1000), (4, 0x2000)]);
+        let data = file
+            .get_relocated_section_data(file.get_section(".data").unwrap(), &addresses)
+            .unwrap();
+        assert_eq!(
+            data,
+            [0x00, 0x10, 0, 0, 0, 0, 0, 0, 0x1c, 0, 0, 0, 0, 0, 0, 0]
+        );
+        // R_RISCV_CALL_PLT patches instruction pairs and is not supported
+        assert!(file
+            .get_relocated_section_data(file.get_section(".text").unwrap(), &addresses)
+            .is_err());
+    }
+
+    #[test]
+    fn test_x86_64_32_signedness() {
+        let mut file = File::new();
+        file.header.class = types::ElfClass::Format64;
+        let mut field = [0; 4];
+
+        // R_X86_64_32 zero extends, so only values below 4GB fit
+        let unsigned = Operation::Unsigned(4);
+        file.apply_operation(unsigned, &mut field, 0xffff_fff0, 0, 0, 0)
+            .unwrap();
+        assert_eq!(field, [0xf0, 0xff, 0xff, 0xff]);
+        assert!(file
+            .apply_operation(unsigned, &mut field, (-16i64) as u64, 0, 0, 0)
+            .is_err());
+
+        // R_X86_64_32S sign extends, so only values within +-2GB fit
+        let signed = Operation::Signed(4);
+        file.apply_operation(signed, &mut field, (-16i64) as u64, 0, 0, 0)
+            .unwrap();
+        assert_eq!(field, [0xf0, 0xff, 0xff, 0xff]);
+        assert!(file
+            .apply_operation(signed, &mut field, 0xffff_fff0, 0, 0, 0)
+            .is_err());
+    }
+}
@@ -0,0 +1,436 @@
+use crate::types;
+use crate::{File, ParseError};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// The file and memory footprint attributed to one item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SizeEntry {
+    /// Item name; synthetic items are in brackets, e.g. "[ELF Header]"
+    pub name: String,
+    /// Bytes occupied in the file
+    pub file_size: u64,
+    /// Bytes occupied in memory at run time
+    pub vm_size: u64,
+}
+
+/// Size attribution of a file, in the spirit of bloaty.
+///
+/// Every view accounts for the whole file: bytes that cannot be attributed
+/// to a segment, section, symbol or compile unit are reported under
+/// bracketed names such as "[Unmapped]" or "[section .text]".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SizeReport {
+    /// Total file size, derived from the headers
+    pub file_size: u64,
+    /// Total memory size of the loadable segments
+    pub vm_size: u64,
+    /// Attribution to PT_LOAD segments
+    pub segments: Vec<SizeEntry>,
+    /// Attribution to sections and the ELF headers
+    pub sections: Vec<SizeEntry>,
+    /// Attribution to symbols of allocated sections
+    pub symbols: Vec<SizeEntry>,
+    /// Attribution to compile units, based on STT_FILE symbols
+    pub compile_units: Vec<SizeEntry>,
+}
+
+/// The change in size of one item between two builds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SizeDelta {
+    /// Item name
+    pub name: String,
+    /// Change in file size
+    pub file_delta: i64,
+    /// Change in memory size
+    pub vm_delta: i64,
+}
+
+/// The changes between two [SizeReport]s, largest first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SizeReportDiff {
+    pub file_delta: i64,
+    pub vm_delta: i64,
+    pub segments: Vec<SizeDelta>,
+    pub sections: Vec<SizeDelta>,
+    pub symbols: Vec<SizeDelta>,
+    pub compile_units: Vec<SizeDelta>,
+}
+
+/// Accumulates sizes by name, keeping first-seen order.
+#[derive(Default)]
+struct Tally {
+    entries: Vec<SizeEntry>,
+    index: HashMap<String, usize>,
+}
+
+impl Tally {
+    fn add(&mut self, name: &str, file_size: u64, vm_size: u64) {
+        if file_size == 0 && vm_size == 0 {
+            return;
+        }
+        let index = match self.index.get(name) {
+            Some(&index) => index,
+            None => {
+                self.entries.push(SizeEntry {
+                    name: name.to_string(),
+                    file_size: 0,
+                    vm_size: 0,
+                });
+                self.index.insert(name.to_string(), self.entries.len() - 1);
+                self.entries.len() - 1
+            }
+        };
+        self.entries[index].file_size += file_size;
+        self.entries[index].vm_size += vm_size;
+    }
+
+    fn finish(mut self) -> Vec<SizeEntry> {
+        self.entries.sort_by(|a, b| {
+            (b.vm_size.max(b.file_size))
+                .cmp(&a.vm_size.max(a.file_size))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        self.entries
+    }
+}
+
+/// Bytes of `[start, end)` not covered by any of the (sorted) ranges.
+fn uncovered(start: u64, end: u64, ranges: &[(u64, u64)]) -> u64 {
+    let mut covered = 0;
+    let mut cursor = start;
+    for &(s, e) in ranges.iter() {
+        let s = s.max(cursor);
+        let e = e.min(end);
+        if s < e {
+            covered += e - s;
+            cursor = e;
+        }
+    }
+    (end - start).saturating_sub(covered)
+}
+
+fn diff_entries(old: &[SizeEntry], new: &[SizeEntry]) -> Vec<SizeDelta> {
+    let old_by_name: HashMap<&str, &SizeEntry> = old
+        .iter()
+        .map(|entry| (entry.name.as_str(), entry))
+        .collect();
+    let new_names: HashSet<&str> = new.iter().map(|entry| entry.name.as_str()).collect();
+    let mut deltas: Vec<SizeDelta> = Vec::new();
+    for entry in new.iter() {
+        let previous = old_by_name.get(entry.name.as_str());
+        let (file, vm) = previous.map_or((0, 0), |o| (o.file_size, o.vm_size));
+        deltas.push(SizeDelta {
+            name: entry.name.clone(),
+            file_delta: entry.file_size as i64 - file as i64,
+            vm_delta: entry.vm_size as i64 - vm as i64,
+        });
+    }
+    for entry in old.iter() {
+        if !new_names.contains(entry.name.as_str()) {
+            deltas.push(SizeDelta {
+                name: entry.name.clone(),
+                file_delta: -(entry.file_size as i64),
+                vm_delta: -(entry.vm_size as i64),
+            });
+        }
+    }
+    deltas.retain(|delta| delta.file_delta != 0 || delta.vm_delta != 0);
+    deltas.sort_by(|a, b| {
+        (b.vm_delta.abs().max(b.file_delta.abs()))
+            .cmp(&a.vm_delta.abs().max(a.file_delta.abs()))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    deltas
+}
+
+impl SizeReport {
+    /// Compare this report (the old build) with another one (the new build).
+    pub fn diff(&self, new: &SizeReport) -> SizeReportDiff {
+        SizeReportDiff {
+            file_delta: new.file_size as i64 - self.file_size as i64,
+            vm_delta: new.vm_size as i64 - self.vm_size as i64,
+            segments: diff_entries(&self.segments, &new.segments),
+            sections: diff_entries(&self.sections, &new.sections),
+            symbols: diff_entries(&self.symbols, &new.symbols),
+            compile_units: diff_entries(&self.compile_units, &new.compile_units),
+        }
+    }
+}
+
+impl File {
+    /// Attribute the file and memory size of this file to its segments,
+    /// sections, symbols and compile units.
+    ///
+    /// Compile units are derived from the STT_FILE symbols of `.symtab`: local
+    /// symbols belong to the preceding STT_FILE symbol, and global symbols
+    /// belong to a file if they lie within the address range covered by that
+    /// file's local symbols in the same section. Everything else is "[unknown]".
+    pub fn size_report(&self) -> Result<SizeReport, ParseError> {
+        let (ehsize, phentsize, shentsize) = match self.header.class {
+            types::ElfClass::Format32 => (52u64, 32u64, 40u64),
+            types::ElfClass::Format64 => (64, 56, 64),
+        };
+        let phdrs_size = phentsize * self.phdrs.len() as u64;
+        let shdrs_size = shentsize * self.sections.len() as u64;
+
+        // File ranges of everything but the section header table, which
+        // conventionally sits at the end of the file
+        let mut ranges: Vec<(u64, u64)> = vec![(0, ehsize)];
+        let phoff = if self.phdrs.is_empty() { 0 } else { ehsize };
+        ranges.push((phoff, phoff + phdrs_size));
+        for section in self.sections.iter() {
+            if section.shdr.shtype != types::SectionType::Nobits {
+                ranges.push((section.shdr.offset, section.shdr.offset + section.shdr.size));
+            }
+        }
+        ranges.sort();
+        let contents_end = ranges
+            .iter()
+            .map(|&(_, end)| end)
+            .chain(self.phdrs.iter().map(|phdr| phdr.offset + phdr.filesz))
+            .max()
+            .unwrap_or(0);
+        let file_size = contents_end + shdrs_size;
+
+        let loads: Vec<&types::ProgramHeader> = self
+            .phdrs
+            .iter()
+            .filter(|phdr| phdr.progtype == types::PT_LOAD)
+            .collect();
+        let vm_size = loads.iter().map(|phdr| phdr.memsz).sum();
+
+        // Segments
+        let mut segments = Tally::default();
+        for (i, phdr) in loads.iter().enumerate() {
+            segments.add(
+                &format!("LOAD #{} [{}]", i, phdr.flags.to_string().replace(' ', "")),
+                phdr.filesz,
+                phdr.memsz,
+            );
+        }
+        let mut load_ranges: Vec<(u64, u64)> = loads
+            .iter()
+            .map(|phdr| (phdr.offset, phdr.offset + phdr.filesz))
+            .collect();
+        load_ranges.sort();
+        segments.add("[Unmapped]", uncovered(0, file_size, &load_ranges), 0);
+
+        // Sections
+        let mut sections = Tally::default();
+        sections.add("[ELF Header]", ehsize, 0);
+        sections.add("[Program Headers]", phdrs_size, 0);
+        sections.add("[Section Headers]", shdrs_size, 0);
+        for section in self.sections.iter().skip(1) {
+            let file = if section.shdr.shtype == types::SectionType::Nobits {
+                0
+            } else {
+                section.shdr.size
+            };
+            let vm = if section.shdr.flags.0 & types::SHF_ALLOC.0 != 0 {
+                section.shdr.size
+            } else {
+                0
+            };
+            sections.add(&section.shdr.name, file, vm);
+        }
+        sections.add("[Padding]", uncovered(0, contents_end, &ranges), 0);
+
+        // Symbols and compile units
+        let mut symbols = Tally::default();
+        let mut compile_units = Tally::default();
+        let symtab = self
+            .sections
+            .iter()
+            .find(|section| section.shdr.shtype == types::SectionType::Symtab)
+            .or_else(|| {
+                self.sections
+                    .iter()
+                    .find(|section| section.shdr.shtype == types::SectionType::Dynsym)
+            });
+        let all_symbols = match symtab {
+            Some(section) => self.get_symbols(section)?,
+            None => Vec::new(),
+        };
+
+        // Assign symbols to files and collect per-file address ranges per section
+        let mut current_file: Option<String> = None;
+        let mut owners: Vec<Option<String>> = Vec::with_capacity(all_symbols.len());
+        let mut file_spans: BTreeMap<(String, u32), (u64, u64)> = BTreeMap::new();
+        for sym in all_symbols.iter() {
+            if sym.symtype == types::STT_FILE {
+                current_file = Some(sym.name.clone()).filter(|name| !name.is_empty());
+                owners.push(None);
+                continue;
+            }
+            let owner = if sym.bind == types::STB_LOCAL {
+                current_file.clone()
+            } else {
+                None
+            };
+            if let (Some(file), types::SectionIndex::Index(shndx)) = (&owner, sym.shndx) {
+                let end = sym.value.saturating_add(sym.size);
+                let span = file_spans
+                    .entry((file.clone(), shndx))
+                    .or_insert((sym.value, end));
+                span.0 = span.0.min(sym.value);
+                span.1 = span.1.max(end);
+            }
+            owners.push(owner);
+        }
+        // File spans of each section, sorted by start address
+        let mut section_spans: HashMap<u32, Vec<(u64, u64, &str)>> = HashMap::new();
+        for ((name, shndx), &(start, end)) in file_spans.iter() {
+            section_spans
+                .entry(*shndx)
+                .or_default()
+                .push((start, end, name.as_str()));
+        }
+        for spans in section_spans.values_mut() {
+            spans.sort_unstable();
+        }
+
+        let mut seen = HashSet::new();
+        let mut per_section: HashMap<u32, u64> = HashMap::new();
+        for (sym, owner) in all_symbols.iter().zip(owners) {
+            let shndx = match sym.shndx {
+                types::SectionIndex::Index(shndx) => shndx,
+                _ => continue,
+            };
+            let section = match self.sections.get(shndx as usize) {
+                Some(section) => section,
+                None => continue,
+            };
+            if sym.size == 0
+                || sym.symtype == types::STT_SECTION
+                || section.shdr.flags.0 & types::SHF_ALLOC.0 == 0
+                || !seen.insert((shndx, sym.value))
+            {
+                continue;
+            }
+            let file = if section.shdr.shtype == types::SectionType::Nobits {
+                0
+            } else {
+                sym.size
+            };
+            symbols.add(&sym.name, file, sym.size);
+            *per_section.entry(shndx).or_insert(0) += sym.size;
+
+            // Otherwise use the file whose span starts closest before the symbol
+            let owner = owner.or_else(|| {
+                let spans = section_spans.get(&shndx)?;
+                let index = spans.partition_point(|&(start, _, _)| start <= sym.value);
+                spans[..index]
+                    .iter()
+                    .rev()
+                    .find(|&&(_, end, _)| sym.value < end)
+                    .map(|&(_, _, name)| name.to_string())
+            });
+            compile_units.add(owner.as_deref().unwrap_or("[unknown]"), file, sym.size);
+        }
+
+        // Whatever the symbols do not cover is attributed to the section itself
+        for (shndx, section) in self.sections.iter().enumerate().skip(1) {
+            let covered = per_section.get(&(shndx as u32)).copied().unwrap_or(0);
+            let vm = if section.shdr.flags.0 & types::SHF_ALLOC.0 != 0 {
+                section.shdr.size.saturating_sub(covered)
+            } else {
+                0
+            };
+            let file = if section.shdr.shtype == types::SectionType::Nobits {
+                0
+            } else {
+                section.shdr.size.saturating_sub(covered)
+            };
+            let name = format!("[section {}]", section.shdr.name);
+            symbols.add(&name, file, vm);
+            compile_units.add(&name, file, vm);
+        }
+        for extra in [
+            "[ELF Header]",
+            "[Program Headers]",
+            "[Section Headers]",
+            "[Padding]",
+        ] {
+            if let Some(entry) = sections.entries.iter().find(|entry| entry.name == extra) {
+                let (file, vm) = (entry.file_size, entry.vm_size);
+                symbols.add(extra, file, vm);
+                compile_units.add(extra, file, vm);
+            }
+        }
+
+        Ok(SizeReport {
+            file_size,
+            vm_size,
+            segments: segments.finish(),
+            sections: sections.finish(),
+            symbols: symbols.finish(),
+            compile_units: compile_units.finish(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SizeDelta;
+    use crate::File;
+
+    #[test]
+    fn test_size_report() {
+        let file = File::open_path("tests/samples/test1").unwrap();
+        let report = file.size_report().unwrap();
+        assert_eq!(report.file_size, 6784);
+        for view in [
+            &report.segments,
+            &report.sections,
+            &report.symbols,
+            &report.compile_units,
+        ] {
+            let total: u64 = view.iter().map(|entry| entry.file_size).sum();
+            assert_eq!(total, report.file_size);
+        }
+        let vm: u64 = report.sections.iter().map(|entry| entry.vm_size).sum();
+        assert_eq!(
+            vm,
+            report
+                .symbols
+                .iter()
+                .map(|entry| entry.vm_size)
+                .sum::<u64>()
+        );
+
+        // The 256KiB .bss array dominates the memory footprint
+        assert_eq!(report.symbols[0].name, "a");
+        assert_eq!(report.symbols[0].file_size, 0);
+        assert_eq!(report.symbols[0].vm_size, 0x40000);
+
+        let diff = report.diff(&report);
+        assert!(diff.sections.is_empty() && diff.symbols.is_empty());
+    }
+
+    #[test]
+    fn test_size_diff() {
+        // v2 adds abi_new and a versioned abi_add, and grows abi_counter
+        let old = File::open_path("tests/samples/libabi_v1.so").unwrap();
+        let new = File::open_path("tests/samples/libabi_v2.so").unwrap();
+        let diff = old.size_report().unwrap().diff(&new.size_report().unwrap());
+        assert_eq!(diff.file_delta, 187);
+        assert_eq!(diff.vm_delta, 320);
+        let delta = |deltas: &[SizeDelta], name: &str| {
+            deltas
+                .iter()
+                .find(|delta| delta.name == name)
+                .map(|delta| (delta.file_delta, delta.vm_delta))
+        };
+        assert_eq!(delta(&diff.sections, ".text"), Some((31, 31)));
+        assert_eq!(delta(&diff.sections, ".bss"), Some((0, 32)));
+        assert_eq!(delta(&diff.sections, ".init"), None);
+        assert_eq!(delta(&diff.symbols, "abi_new"), Some((20, 20)));
+        assert_eq!(delta(&diff.symbols, "abi_old"), Some((-3, -3)));
+        assert_eq!(delta(&diff.symbols, "abi_counter"), Some((0, 16)));
+        // Entries are sorted by the size of the change
+        assert!(diff.symbols.windows(2).all(|pair| {
+            pair[0].file_delta.abs().max(pair[0].vm_delta.abs())
+                >= pair[1].file_delta.abs().max(pair[1].vm_delta.abs())
+        }));
+    }
+}
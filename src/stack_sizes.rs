@@ -0,0 +1,188 @@
+use crate::dwarf::Reader;
+use crate::types;
+use crate::{File, ParseError, Section};
+use std::collections::HashMap;
+
+/// A function stack usage record from a `.stack_sizes` section.
+///
+/// These are emitted by LLVM with `-emit-stack-sizes` (or
+/// `-stack-size-section`) as pairs of function address and ULEB128 stack
+/// size in bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackSize {
+    /// Function address. In relocatable objects this is an offset into
+    /// [StackSize::section].
+    pub address: u64,
+    /// Section index the address is relative to, for relocatable objects
+    pub section: Option<u32>,
+    /// Stack size in bytes
+    pub size: u64,
+    /// The function symbol at the address, if one was found
+    pub function: Option<types::Symbol>,
+}
+
+impl File {
+    /// Decode all `.stack_sizes` sections and join them to function symbols.
+    ///
+    /// In relocatable objects the function addresses are resolved through the
+    /// relocations applied to each `.stack_sizes` section, since they are all
+    /// zero before linking.
+    pub fn get_stack_sizes(&self) -> Result<Vec<StackSize>, ParseError> {
+        let functions = self.get_function_symbols()?;
+        let mut stack_sizes = Vec::new();
+        for section in self
+            .sections
+            .iter()
+            .filter(|section| section.shdr.name == ".stack_sizes")
+        {
+            self.parse_stack_sizes(section, &functions, &mut stack_sizes)?;
+        }
+        Ok(stack_sizes)
+    }
+
+    fn get_function_symbols(&self) -> Result<Vec<types::Symbol>, ParseError> {
+        let symtab = self
+            .sections
+            .iter()
+            .find(|section| section.shdr.shtype == types::SectionType::Symtab)
+            .or_else(|| {
+                self.sections
+                    .iter()
+                    .find(|section| section.shdr.shtype == types::SectionType::Dynsym)
+            });
+        Ok(match symtab {
+            Some(symtab) => self
+                .get_symbols(symtab)?
+                .into_iter()
+                .filter(|symbol| symbol.symtype == types::STT_FUNC)
+                .collect(),
+            None => Vec::new(),
+        })
+    }
+
+    fn parse_stack_sizes(
+        &self,
+        section: &Section,
+        functions: &[types::Symbol],
+        stack_sizes: &mut Vec<StackSize>,
+    ) -> Result<(), ParseError> {
+        // Symbol and addend of each relocated address field, by offset
+        let mut relocations = HashMap::new();
+        for rel_section in self.get_relocation_sections(section) {
+            let symtab = self.sections.get(rel_section.shdr.link as usize).ok_or(
+                ParseError::InvalidFormat(Some(format!(
+                    "Invalid sh_link in {}",
+                    rel_section.shdr.name
+                ))),
+            )?;
+            let symbols = self.get_symbols(symtab)?;
+            for relocation in self.get_relocations(rel_section)? {
+                let symbol =
+                    symbols
+                        .get(relocation.symbol as usize)
+                        .ok_or(ParseError::InvalidFormat(Some(format!(
+                            "Invalid symbol index {} in {}",
+                            relocation.symbol, rel_section.shdr.name
+                        ))))?;
+                relocations
+                    .entry(relocation.offset)
+                    .or_insert((symbol.clone(), relocation.addend));
+            }
+        }
+
+        // Function symbols by address, in symbol table order
+        let mut by_address: HashMap<u64, Vec<&types::Symbol>> = HashMap::new();
+        for symbol in functions {
+            by_address
+                .entry(self.function_address(symbol))
+                .or_default()
+                .push(symbol);
+        }
+
+        let address_size = match self.header.class {
+            types::ElfClass::Format32 => 4,
+            types::ElfClass::Format64 => 8,
+        };
+        let mut reader = Reader::new(&section.data, self.header.endianness);
+        while !reader.is_empty() {
+            let offset = reader.offset as u64;
+            let value = reader.sized(address_size)?;
+            let size = reader.uleb128()?;
+
+            let (address, target) = match relocations.get(&offset) {
+                Some((symbol, addend)) => {
+                    // SHT_REL entries keep the addend in the field itself
+                    let addend = addend.unwrap_or(value as i64);
+                    let target = match symbol.shndx {
+                        types::SectionIndex::Index(index) => Some(index),
+                        _ => None,
+                    };
+                    (symbol.value.wrapping_add(addend as u64), target)
+                }
+                None => (value, None),
+            };
+            let function = by_address
+                .get(&address)
+                .and_then(|candidates| {
+                    candidates.iter().find(|symbol| {
+                        target.is_none_or(|index| symbol.shndx == types::SectionIndex::Index(index))
+                    })
+                })
+                .map(|&symbol| symbol.clone());
+            stack_sizes.push(StackSize {
+                address,
+                section: target,
+                size,
+                function,
+            });
+        }
+        Ok(())
+    }
+
+    /// The address of a function symbol, without the Thumb bit on ARM.
+    fn function_address(&self, symbol: &types::Symbol) -> u64 {
+        match self.header.cpu_architecture {
+            types::ElfCpuArchitecture::EM_ARM => symbol.value & !1,
+            _ => symbol.value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::File;
+    use std::path::PathBuf;
+
+    fn summary(path: &str) -> Vec<(String, u64, u64)> {
+        let file = File::open_path(PathBuf::from(path)).unwrap();
+        file.get_stack_sizes()
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.function.unwrap().name, entry.address, entry.size))
+            .collect()
+    }
+
+    #[test]
+    fn test_stack_sizes_relocatable() {
+        assert_eq!(
+            summary("tests/samples/stack_sizes.o"),
+            vec![
+                ("small".to_string(), 0, 24),
+                ("big".to_string(), 0, 520),
+                ("use".to_string(), 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stack_sizes_linked() {
+        assert_eq!(
+            summary("tests/samples/stack_sizes"),
+            vec![
+                ("small".to_string(), 0x401000, 24),
+                ("big".to_string(), 0x401020, 520),
+                ("use".to_string(), 0x401040, 0),
+            ]
+        );
+    }
+}
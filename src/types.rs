@@ -200,6 +200,7 @@ pub enum ElfCpuArchitecture {
     EM_TILEPRO = 188,
     EM_MICROBLAZE = 189,
     EM_TILEGX = 191,
+    EM_RISCV = 243,
 }
 
 /// Encapsulates the contents of the ELF File Header
@@ -224,6 +225,8 @@ pub struct FileHeader {
     pub cpu_architecture: ElfCpuArchitecture,
     /// Virtual address of program entry point
     pub entry: u64,
+    /// Processor-specific flags, named e_flags in C code
+    pub flags: u32,
 }
 
 impl fmt::Display for FileHeader {
@@ -298,6 +301,8 @@ pub const PT_GNU_EH_FRAME: ProgType = ProgType(0x6474_e550);
 pub const PT_GNU_STACK: ProgType = ProgType(0x6474_e551);
 /// Read-only after relocation
 pub const PT_GNU_RELRO: ProgType = ProgType(0x6474_e552);
+/// GNU property notes for linker and run-time loaders
+pub const PT_GNU_PROPERTY: ProgType = ProgType(0x6474_e553);
 
 impl fmt::Debug for ProgType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -319,6 +324,7 @@ impl fmt::Display for ProgType {
             PT_GNU_EH_FRAME => "GNU_EH_FRAME",
             PT_GNU_STACK => "GNU_STACK",
             PT_GNU_RELRO => "GNU_RELRO",
+            PT_GNU_PROPERTY => "GNU_PROPERTY",
             _ => "Unknown",
         };
         write!(f, "{}", str)
@@ -388,17 +394,17 @@ pub enum SectionType {
     /// Section data contains a minimal set of dynamic linking symbols. Named SHT_DYNSYM in C code.
     Dynsym = 11,
     /// Section data contains an array of constructors. Named SHT_INIT_ARRAY in C code.
-    InitArray = 12,
+    InitArray = 14,
     /// Section data contains an array of destructors. Named SHT_FINI_ARRAY in C code.
-    FiniArray = 13,
+    FiniArray = 15,
     /// Section data contains an array of pre-constructors. Named SHT_PREINIT_ARRAY in C code.
-    PreinitArray = 14,
+    PreinitArray = 16,
     /// Section group. Named SHT_GROUP in C code.
-    Group = 15,
+    Group = 17,
     /// Extended symbol table section index. Named SHT_SYMTAB_SHNDX in C code.
-    SymtabShndx = 16,
+    SymtabShndx = 18,
     /// Number of reserved SHT_* values. Named SHT_NUM in C code.
-    Num = 17,
+    Num = 19,
     /// Object attributes. Named SHT_GNU_ATTRIBUTES in C code.
     GnuAttributes = 0x6fff_fff5,
     /// GNU-style hash section. Named SHT_GNU_HASH in C code.
@@ -411,12 +417,19 @@ pub enum SectionType {
     GnuVerneed = 0x6fff_fffe,
     /// Version symbol table. Named SHT_GNU_VERSYM in C code.
     GnuVersym = 0x6fff_ffff,
-    /// Arm specific. Named SHT_ARM_EXIDX in C code.
-    ArmExidc = 0x7000_0001,
+    /// Arm exception index table. Named SHT_ARM_EXIDX in C code.
+    ArmExidx = 0x7000_0001,
     /// Arm specific. Named SHT_ARM_ATTRIBUTES in C code.
     ArmAttributes = 0x7000_0003,
 }
 
+impl SectionType {
+    /// Misspelled name of [SectionType::ArmExidx].
+    #[deprecated(note = "use SectionType::ArmExidx")]
+    #[allow(non_upper_case_globals)]
+    pub const ArmExidc: SectionType = SectionType::ArmExidx;
+}
+
 ///
 /// Wrapper type for SectionFlag
 ///
@@ -444,6 +457,8 @@ pub const SHF_OS_NONCONFORMING: SectionFlag = SectionFlag(256);
 pub const SHF_GROUP: SectionFlag = SectionFlag(512);
 /// Section hold thread-local data
 pub const SHF_TLS: SectionFlag = SectionFlag(1024);
+/// Section with compressed data
+pub const SHF_COMPRESSED: SectionFlag = SectionFlag(2048);
 
 impl fmt::Debug for SectionFlag {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -490,6 +505,138 @@ impl fmt::Display for SectionHeader {
     }
 }
 
+/// Represents the flag word at the start of an SHT_GROUP section
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct GroupFlag(pub u32);
+/// No group flags
+pub const GRP_NONE: GroupFlag = GroupFlag(0);
+/// Mark group as a COMDAT group
+pub const GRP_COMDAT: GroupFlag = GroupFlag(1);
+
+impl GroupFlag {
+    /// Returns true if this is a COMDAT group
+    pub fn is_comdat(&self) -> bool {
+        (self.0 & GRP_COMDAT.0) != 0
+    }
+}
+
+impl fmt::Debug for GroupFlag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+impl fmt::Display for GroupFlag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_comdat() {
+            write!(f, "COMDAT")
+        } else {
+            write!(f, "{:#x}", self.0)
+        }
+    }
+}
+
+/// Represents the compression algorithm of an SHF_COMPRESSED section
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct CompressionType(pub u32);
+/// zlib (deflate) compression. Named ELFCOMPRESS_ZLIB in C code.
+pub const ELFCOMPRESS_ZLIB: CompressionType = CompressionType(1);
+/// Zstandard compression. Named ELFCOMPRESS_ZSTD in C code.
+pub const ELFCOMPRESS_ZSTD: CompressionType = CompressionType(2);
+
+impl fmt::Debug for CompressionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+impl fmt::Display for CompressionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = match *self {
+            ELFCOMPRESS_ZLIB => "ZLIB",
+            ELFCOMPRESS_ZSTD => "ZSTD",
+            _ => "Unknown",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Encapsulates the header at the start of an SHF_COMPRESSED section
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CompressionHeader {
+    /// Compression algorithm
+    pub chtype: CompressionType,
+    /// Size of the uncompressed data
+    pub size: u64,
+    /// Alignment of the uncompressed data
+    pub addralign: u64,
+}
+
+/// Program header count stored in sh_info of the initial section header.
+/// Named PN_XNUM in C code.
+pub const PN_XNUM: u16 = 0xffff;
+
+/// Undefined section reference. Named SHN_UNDEF in C code.
+pub const SHN_UNDEF: u16 = 0;
+/// Start of the reserved section index range. Named SHN_LORESERVE in C code.
+pub const SHN_LORESERVE: u16 = 0xff00;
+/// Associated symbol is absolute. Named SHN_ABS in C code.
+pub const SHN_ABS: u16 = 0xfff1;
+/// Associated symbol is a common symbol. Named SHN_COMMON in C code.
+pub const SHN_COMMON: u16 = 0xfff2;
+/// Index is in the extra SHT_SYMTAB_SHNDX table. Named SHN_XINDEX in C code.
+pub const SHN_XINDEX: u16 = 0xffff;
+
+/// The section a symbol is defined relative to.
+///
+/// This is a field on [Symbol::shndx]. Symbols whose raw index is SHN_XINDEX
+/// are resolved through the SHT_SYMTAB_SHNDX section when the symbol table is
+/// parsed, so they show up as a regular [SectionIndex::Index].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SectionIndex {
+    /// Symbol is undefined. Named SHN_UNDEF in C code.
+    Undefined,
+    /// Symbol has an absolute value. Named SHN_ABS in C code.
+    Absolute,
+    /// Symbol is a common block that is not yet allocated. Named SHN_COMMON in C code.
+    Common,
+    /// Symbol is defined relative to the section with this index.
+    Index(u32),
+    /// Any other reserved (processor or OS specific) section index.
+    Reserved(u16),
+}
+
+impl SectionIndex {
+    /// Returns true if this refers to an actual section in the section header table
+    pub fn is_defined(&self) -> bool {
+        matches!(self, SectionIndex::Index(_))
+    }
+}
+
+impl From<u16> for SectionIndex {
+    fn from(shndx: u16) -> Self {
+        match shndx {
+            SHN_UNDEF => SectionIndex::Undefined,
+            SHN_ABS => SectionIndex::Absolute,
+            SHN_COMMON => SectionIndex::Common,
+            index if index < SHN_LORESERVE => SectionIndex::Index(index as u32),
+            reserved => SectionIndex::Reserved(reserved),
+        }
+    }
+}
+
+impl fmt::Display for SectionIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SectionIndex::Undefined => write!(f, "UND"),
+            SectionIndex::Absolute => write!(f, "ABS"),
+            SectionIndex::Common => write!(f, "COM"),
+            SectionIndex::Index(index) => write!(f, "{}", index),
+            SectionIndex::Reserved(index) => write!(f, "{:#x}", index),
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct SymbolType(pub u8);
 /// Unspecified symbol type
@@ -509,6 +656,12 @@ pub const STT_TLS: SymbolType = SymbolType(6);
 /// Indirect code object symbol
 pub const STT_GNU_IFUNC: SymbolType = SymbolType(10);
 
+impl fmt::Debug for SymbolType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
 impl fmt::Display for SymbolType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let str = match *self {
@@ -537,6 +690,12 @@ pub const STB_WEAK: SymbolBind = SymbolBind(2);
 /// Unique symbol
 pub const STB_GNU_UNIQUE: SymbolBind = SymbolBind(10);
 
+impl fmt::Debug for SymbolBind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
 impl fmt::Display for SymbolBind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let str = match *self {
@@ -561,6 +720,12 @@ pub const STV_HIDDEN: SymbolVis = SymbolVis(2);
 /// Protected visibility
 pub const STV_PROTECTED: SymbolVis = SymbolVis(3);
 
+impl fmt::Debug for SymbolVis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
 impl fmt::Display for SymbolVis {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let str = match *self {
@@ -574,7 +739,7 @@ impl fmt::Display for SymbolVis {
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Symbol {
     /// Symbol name
     pub name: String,
@@ -583,7 +748,7 @@ pub struct Symbol {
     /// Symbol size
     pub size: u64,
     /// Section index
-    pub shndx: u16,
+    pub shndx: SectionIndex,
     /// Symbol type
     pub symtype: SymbolType,
     /// Symbol binding
@@ -601,3 +766,524 @@ impl fmt::Display for Symbol {
         )
     }
 }
+
+/// Note type of a GNU ABI tag note
+pub const NT_GNU_ABI_TAG: u32 = 1;
+/// Note type of a GNU hardware capabilities note
+pub const NT_GNU_HWCAP: u32 = 2;
+/// Note type of a GNU build ID note
+pub const NT_GNU_BUILD_ID: u32 = 3;
+/// Note type of a gold linker version note
+pub const NT_GNU_GOLD_VERSION: u32 = 4;
+/// Note type of a GNU program property note
+pub const NT_GNU_PROPERTY_TYPE_0: u32 = 5;
+
+/// Stack size program property
+pub const GNU_PROPERTY_STACK_SIZE: u32 = 1;
+/// No copy relocation on protected data symbol program property
+pub const GNU_PROPERTY_NO_COPY_ON_PROTECTED: u32 = 2;
+/// x86 ISA needed program property
+pub const GNU_PROPERTY_X86_ISA_1_NEEDED: u32 = 0xc000_8002;
+/// x86 ISA used program property
+pub const GNU_PROPERTY_X86_ISA_1_USED: u32 = 0xc001_0002;
+/// x86 processor features program property
+pub const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xc000_0002;
+/// AArch64 processor features program property
+pub const GNU_PROPERTY_AARCH64_FEATURE_1_AND: u32 = 0xc000_0000;
+
+/// Represents the x86 processor features of GNU_PROPERTY_X86_FEATURE_1_AND
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct X86Feature1Flag(pub u32);
+/// Compatible with Indirect Branch Tracking
+pub const GNU_PROPERTY_X86_FEATURE_1_IBT: X86Feature1Flag = X86Feature1Flag(1);
+/// Compatible with Shadow Stack
+pub const GNU_PROPERTY_X86_FEATURE_1_SHSTK: X86Feature1Flag = X86Feature1Flag(2);
+
+impl X86Feature1Flag {
+    /// Returns true if all of the given feature bits are set
+    pub fn contains(&self, other: X86Feature1Flag) -> bool {
+        (self.0 & other.0) == other.0
+    }
+}
+
+impl fmt::Debug for X86Feature1Flag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+impl fmt::Display for X86Feature1Flag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names = Vec::new();
+        if self.contains(GNU_PROPERTY_X86_FEATURE_1_IBT) {
+            names.push("IBT");
+        }
+        if self.contains(GNU_PROPERTY_X86_FEATURE_1_SHSTK) {
+            names.push("SHSTK");
+        }
+        write!(f, "{}", names.join(", "))
+    }
+}
+
+/// Represents the x86 ISA levels of GNU_PROPERTY_X86_ISA_1_NEEDED and _USED
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct X86Isa1Flag(pub u32);
+/// x86-64 baseline
+pub const GNU_PROPERTY_X86_ISA_1_BASELINE: X86Isa1Flag = X86Isa1Flag(1);
+/// x86-64-v2
+pub const GNU_PROPERTY_X86_ISA_1_V2: X86Isa1Flag = X86Isa1Flag(2);
+/// x86-64-v3
+pub const GNU_PROPERTY_X86_ISA_1_V3: X86Isa1Flag = X86Isa1Flag(4);
+/// x86-64-v4
+pub const GNU_PROPERTY_X86_ISA_1_V4: X86Isa1Flag = X86Isa1Flag(8);
+
+impl X86Isa1Flag {
+    /// Returns true if all of the given ISA level bits are set
+    pub fn contains(&self, other: X86Isa1Flag) -> bool {
+        (self.0 & other.0) == other.0
+    }
+}
+
+impl fmt::Debug for X86Isa1Flag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+impl fmt::Display for X86Isa1Flag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names = Vec::new();
+        if self.contains(GNU_PROPERTY_X86_ISA_1_BASELINE) {
+            names.push("x86-64-baseline");
+        }
+        if self.contains(GNU_PROPERTY_X86_ISA_1_V2) {
+            names.push("x86-64-v2");
+        }
+        if self.contains(GNU_PROPERTY_X86_ISA_1_V3) {
+            names.push("x86-64-v3");
+        }
+        if self.contains(GNU_PROPERTY_X86_ISA_1_V4) {
+            names.push("x86-64-v4");
+        }
+        write!(f, "{}", names.join(", "))
+    }
+}
+
+/// Represents the AArch64 processor features of GNU_PROPERTY_AARCH64_FEATURE_1_AND
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Aarch64Feature1Flag(pub u32);
+/// Compatible with Branch Target Identification
+pub const GNU_PROPERTY_AARCH64_FEATURE_1_BTI: Aarch64Feature1Flag = Aarch64Feature1Flag(1);
+/// Compatible with Pointer Authentication
+pub const GNU_PROPERTY_AARCH64_FEATURE_1_PAC: Aarch64Feature1Flag = Aarch64Feature1Flag(2);
+
+impl Aarch64Feature1Flag {
+    /// Returns true if all of the given feature bits are set
+    pub fn contains(&self, other: Aarch64Feature1Flag) -> bool {
+        (self.0 & other.0) == other.0
+    }
+}
+
+impl fmt::Debug for Aarch64Feature1Flag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+impl fmt::Display for Aarch64Feature1Flag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names = Vec::new();
+        if self.contains(GNU_PROPERTY_AARCH64_FEATURE_1_BTI) {
+            names.push("BTI");
+        }
+        if self.contains(GNU_PROPERTY_AARCH64_FEATURE_1_PAC) {
+            names.push("PAC");
+        }
+        write!(f, "{}", names.join(", "))
+    }
+}
+
+/// Represents the tag of an entry in the dynamic section
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct DynTag(pub u64);
+/// Marks end of dynamic section
+pub const DT_NULL: DynTag = DynTag(0);
+/// Name of needed library
+pub const DT_NEEDED: DynTag = DynTag(1);
+/// Size in bytes of PLT relocs
+pub const DT_PLTRELSZ: DynTag = DynTag(2);
+/// Processor defined value
+pub const DT_PLTGOT: DynTag = DynTag(3);
+/// Address of symbol hash table
+pub const DT_HASH: DynTag = DynTag(4);
+/// Address of string table
+pub const DT_STRTAB: DynTag = DynTag(5);
+/// Address of symbol table
+pub const DT_SYMTAB: DynTag = DynTag(6);
+/// Address of Rela relocs
+pub const DT_RELA: DynTag = DynTag(7);
+/// Total size of Rela relocs
+pub const DT_RELASZ: DynTag = DynTag(8);
+/// Size of one Rela reloc
+pub const DT_RELAENT: DynTag = DynTag(9);
+/// Size of string table
+pub const DT_STRSZ: DynTag = DynTag(10);
+/// Size of one symbol table entry
+pub const DT_SYMENT: DynTag = DynTag(11);
+/// Address of init function
+pub const DT_INIT: DynTag = DynTag(12);
+/// Address of termination function
+pub const DT_FINI: DynTag = DynTag(13);
+/// Name of shared object
+pub const DT_SONAME: DynTag = DynTag(14);
+/// Library search path (deprecated)
+pub const DT_RPATH: DynTag = DynTag(15);
+/// Start symbol search here
+pub const DT_SYMBOLIC: DynTag = DynTag(16);
+/// Address of Rel relocs
+pub const DT_REL: DynTag = DynTag(17);
+/// Total size of Rel relocs
+pub const DT_RELSZ: DynTag = DynTag(18);
+/// Size of one Rel reloc
+pub const DT_RELENT: DynTag = DynTag(19);
+/// Type of reloc in PLT
+pub const DT_PLTREL: DynTag = DynTag(20);
+/// For debugging; unspecified
+pub const DT_DEBUG: DynTag = DynTag(21);
+/// Reloc might modify .text
+pub const DT_TEXTREL: DynTag = DynTag(22);
+/// Address of PLT relocs
+pub const DT_JMPREL: DynTag = DynTag(23);
+/// Process relocations of object
+pub const DT_BIND_NOW: DynTag = DynTag(24);
+/// Array with addresses of init fct
+pub const DT_INIT_ARRAY: DynTag = DynTag(25);
+/// Array with addresses of fini fct
+pub const DT_FINI_ARRAY: DynTag = DynTag(26);
+/// Size in bytes of DT_INIT_ARRAY
+pub const DT_INIT_ARRAYSZ: DynTag = DynTag(27);
+/// Size in bytes of DT_FINI_ARRAY
+pub const DT_FINI_ARRAYSZ: DynTag = DynTag(28);
+/// Library search path
+pub const DT_RUNPATH: DynTag = DynTag(29);
+/// Flags for the object being loaded
+pub const DT_FLAGS: DynTag = DynTag(30);
+/// Array with addresses of preinit fct
+pub const DT_PREINIT_ARRAY: DynTag = DynTag(32);
+/// Size in bytes of DT_PREINIT_ARRAY
+pub const DT_PREINIT_ARRAYSZ: DynTag = DynTag(33);
+/// Address of SYMTAB_SHNDX section
+pub const DT_SYMTAB_SHNDX: DynTag = DynTag(34);
+/// Total size of Relr relocs
+pub const DT_RELRSZ: DynTag = DynTag(35);
+/// Address of Relr relocs
+pub const DT_RELR: DynTag = DynTag(36);
+/// Size of one Relr reloc
+pub const DT_RELRENT: DynTag = DynTag(37);
+/// GNU-style hash table
+pub const DT_GNU_HASH: DynTag = DynTag(0x6fff_fef5);
+/// Count of R_*_RELATIVE Rela relocs
+pub const DT_RELACOUNT: DynTag = DynTag(0x6fff_fff9);
+/// Count of R_*_RELATIVE Rel relocs
+pub const DT_RELCOUNT: DynTag = DynTag(0x6fff_fffa);
+/// State flags, see DF_1_* below
+pub const DT_FLAGS_1: DynTag = DynTag(0x6fff_fffb);
+/// Address of version symbol table
+pub const DT_VERSYM: DynTag = DynTag(0x6fff_fff0);
+/// Address of version definition table
+pub const DT_VERDEF: DynTag = DynTag(0x6fff_fffc);
+/// Number of version definitions
+pub const DT_VERDEFNUM: DynTag = DynTag(0x6fff_fffd);
+/// Address of table with needed versions
+pub const DT_VERNEED: DynTag = DynTag(0x6fff_fffe);
+/// Number of needed versions
+pub const DT_VERNEEDNUM: DynTag = DynTag(0x6fff_ffff);
+
+impl fmt::Debug for DynTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+impl fmt::Display for DynTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = match *self {
+            DT_NULL => "NULL",
+            DT_NEEDED => "NEEDED",
+            DT_PLTRELSZ => "PLTRELSZ",
+            DT_PLTGOT => "PLTGOT",
+            DT_HASH => "HASH",
+            DT_STRTAB => "STRTAB",
+            DT_SYMTAB => "SYMTAB",
+            DT_RELA => "RELA",
+            DT_RELASZ => "RELASZ",
+            DT_RELAENT => "RELAENT",
+            DT_STRSZ => "STRSZ",
+            DT_SYMENT => "SYMENT",
+            DT_INIT => "INIT",
+            DT_FINI => "FINI",
+            DT_SONAME => "SONAME",
+            DT_RPATH => "RPATH",
+            DT_SYMBOLIC => "SYMBOLIC",
+            DT_REL => "REL",
+            DT_RELSZ => "RELSZ",
+            DT_RELENT => "RELENT",
+            DT_PLTREL => "PLTREL",
+            DT_DEBUG => "DEBUG",
+            DT_TEXTREL => "TEXTREL",
+            DT_JMPREL => "JMPREL",
+            DT_BIND_NOW => "BIND_NOW",
+            DT_INIT_ARRAY => "INIT_ARRAY",
+            DT_FINI_ARRAY => "FINI_ARRAY",
+            DT_INIT_ARRAYSZ => "INIT_ARRAYSZ",
+            DT_FINI_ARRAYSZ => "FINI_ARRAYSZ",
+            DT_RUNPATH => "RUNPATH",
+            DT_FLAGS => "FLAGS",
+            DT_PREINIT_ARRAY => "PREINIT_ARRAY",
+            DT_PREINIT_ARRAYSZ => "PREINIT_ARRAYSZ",
+            DT_SYMTAB_SHNDX => "SYMTAB_SHNDX",
+            DT_RELRSZ => "RELRSZ",
+            DT_RELR => "RELR",
+            DT_RELRENT => "RELRENT",
+            DT_GNU_HASH => "GNU_HASH",
+            DT_RELACOUNT => "RELACOUNT",
+            DT_RELCOUNT => "RELCOUNT",
+            DT_FLAGS_1 => "FLAGS_1",
+            DT_VERSYM => "VERSYM",
+            DT_VERDEF => "VERDEF",
+            DT_VERDEFNUM => "VERDEFNUM",
+            DT_VERNEED => "VERNEED",
+            DT_VERNEEDNUM => "VERNEEDNUM",
+            _ => "Unknown",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Object may use DF_ORIGIN
+pub const DF_ORIGIN: u64 = 0x1;
+/// Symbol resolutions starts here
+pub const DF_SYMBOLIC: u64 = 0x2;
+/// Object contains text relocations
+pub const DF_TEXTREL: u64 = 0x4;
+/// No lazy binding for this object
+pub const DF_BIND_NOW: u64 = 0x8;
+/// Module uses the static TLS model
+pub const DF_STATIC_TLS: u64 = 0x10;
+
+/// Set RTLD_NOW for this object
+pub const DF_1_NOW: u64 = 0x1;
+/// $ORIGIN must be handled
+pub const DF_1_ORIGIN: u64 = 0x80;
+/// Object is a position independent executable
+pub const DF_1_PIE: u64 = 0x0800_0000;
+
+/// No relocation
+pub const R_X86_64_NONE: u32 = 0;
+/// Direct 64 bit
+pub const R_X86_64_64: u32 = 1;
+/// PC relative 32 bit signed
+pub const R_X86_64_PC32: u32 = 2;
+/// 32 bit PLT address
+pub const R_X86_64_PLT32: u32 = 4;
+/// Copy symbol at runtime
+pub const R_X86_64_COPY: u32 = 5;
+/// Create GOT entry
+pub const R_X86_64_GLOB_DAT: u32 = 6;
+/// Create PLT entry
+pub const R_X86_64_JUMP_SLOT: u32 = 7;
+/// Adjust by program base
+pub const R_X86_64_RELATIVE: u32 = 8;
+/// Direct 32 bit zero extended
+pub const R_X86_64_32: u32 = 10;
+/// Direct 32 bit sign extended
+pub const R_X86_64_32S: u32 = 11;
+/// Direct 16 bit zero extended
+pub const R_X86_64_16: u32 = 12;
+/// 16 bit sign extended pc relative
+pub const R_X86_64_PC16: u32 = 13;
+/// Direct 8 bit sign extended
+pub const R_X86_64_8: u32 = 14;
+/// 8 bit sign extended pc relative
+pub const R_X86_64_PC8: u32 = 15;
+/// ID of module containing symbol
+pub const R_X86_64_DTPMOD64: u32 = 16;
+/// Offset in module's TLS block
+pub const R_X86_64_DTPOFF64: u32 = 17;
+/// Offset in initial TLS block
+pub const R_X86_64_TPOFF64: u32 = 18;
+/// PC relative 64 bit
+pub const R_X86_64_PC64: u32 = 24;
+/// Size of symbol plus 32-bit addend
+pub const R_X86_64_SIZE32: u32 = 32;
+/// Size of symbol plus 64-bit addend
+pub const R_X86_64_SIZE64: u32 = 33;
+/// Adjust indirectly by program base
+pub const R_X86_64_IRELATIVE: u32 = 37;
+
+/// No relocation
+pub const R_386_NONE: u32 = 0;
+/// Direct 32 bit
+pub const R_386_32: u32 = 1;
+/// PC relative 32 bit
+pub const R_386_PC32: u32 = 2;
+/// 32 bit PLT address
+pub const R_386_PLT32: u32 = 4;
+/// Direct 16 bit
+pub const R_386_16: u32 = 20;
+/// PC relative 16 bit
+pub const R_386_PC16: u32 = 21;
+/// Direct 8 bit
+pub const R_386_8: u32 = 22;
+/// PC relative 8 bit
+pub const R_386_PC8: u32 = 23;
+
+/// No relocation
+pub const R_AARCH64_NONE: u32 = 0;
+/// Direct 64 bit
+pub const R_AARCH64_ABS64: u32 = 257;
+/// Direct 32 bit
+pub const R_AARCH64_ABS32: u32 = 258;
+/// Direct 16 bit
+pub const R_AARCH64_ABS16: u32 = 259;
+/// PC relative 64 bit
+pub const R_AARCH64_PREL64: u32 = 260;
+/// PC relative 32 bit
+pub const R_AARCH64_PREL32: u32 = 261;
+/// PC relative 16 bit
+pub const R_AARCH64_PREL16: u32 = 262;
+/// Page relative address of an ADRP instruction
+pub const R_AARCH64_ADR_PREL_PG_HI21: u32 = 275;
+/// Low 12 bits of an address for an ADD instruction
+pub const R_AARCH64_ADD_ABS_LO12_NC: u32 = 277;
+/// Low 12 bits of an address for an 8 bit load or store
+pub const R_AARCH64_LDST8_ABS_LO12_NC: u32 = 278;
+/// PC relative 19 bit offset of a conditional branch
+pub const R_AARCH64_CONDBR19: u32 = 280;
+/// PC relative 26 bit offset of a B instruction
+pub const R_AARCH64_JUMP26: u32 = 282;
+/// PC relative 26 bit offset of a BL instruction
+pub const R_AARCH64_CALL26: u32 = 283;
+/// Low 12 bits of an address for a 16 bit load or store
+pub const R_AARCH64_LDST16_ABS_LO12_NC: u32 = 284;
+/// Low 12 bits of an address for a 32 bit load or store
+pub const R_AARCH64_LDST32_ABS_LO12_NC: u32 = 285;
+/// Low 12 bits of an address for a 64 bit load or store
+pub const R_AARCH64_LDST64_ABS_LO12_NC: u32 = 286;
+/// Low 12 bits of an address for a 128 bit load or store
+pub const R_AARCH64_LDST128_ABS_LO12_NC: u32 = 299;
+/// Copy symbol at runtime
+pub const R_AARCH64_COPY: u32 = 1024;
+/// Create GOT entry
+pub const R_AARCH64_GLOB_DAT: u32 = 1025;
+/// Create PLT entry
+pub const R_AARCH64_JUMP_SLOT: u32 = 1026;
+/// Adjust by program base
+pub const R_AARCH64_RELATIVE: u32 = 1027;
+/// ID of module containing symbol
+pub const R_AARCH64_TLS_DTPMOD: u32 = 1028;
+/// Offset in module's TLS block
+pub const R_AARCH64_TLS_DTPREL: u32 = 1029;
+/// Offset in initial TLS block
+pub const R_AARCH64_TLS_TPREL: u32 = 1030;
+/// Adjust indirectly by program base
+pub const R_AARCH64_IRELATIVE: u32 = 1032;
+
+/// No relocation
+pub const R_ARM_NONE: u32 = 0;
+/// Direct 32 bit
+pub const R_ARM_ABS32: u32 = 2;
+/// PC relative 32 bit
+pub const R_ARM_REL32: u32 = 3;
+/// Direct 16 bit
+pub const R_ARM_ABS16: u32 = 5;
+/// Direct 8 bit
+pub const R_ARM_ABS8: u32 = 8;
+/// PC relative 24 bit offset of a BL or BLX instruction
+pub const R_ARM_CALL: u32 = 28;
+/// PC relative 24 bit offset of a B or BL<cond> instruction
+pub const R_ARM_JUMP24: u32 = 29;
+/// Direct 32 bit, used for .init_array and similar
+pub const R_ARM_TARGET1: u32 = 38;
+/// Marker for BX instructions that may be rewritten for ARMv4
+pub const R_ARM_V4BX: u32 = 40;
+/// PC relative 31 bit, used for exception tables
+pub const R_ARM_PREL31: u32 = 42;
+
+/// No relocation
+pub const R_RISCV_NONE: u32 = 0;
+/// Direct 32 bit
+pub const R_RISCV_32: u32 = 1;
+/// Direct 64 bit
+pub const R_RISCV_64: u32 = 2;
+/// Adjust by program base
+pub const R_RISCV_RELATIVE: u32 = 3;
+/// Copy symbol at runtime
+pub const R_RISCV_COPY: u32 = 4;
+/// Create PLT entry
+pub const R_RISCV_JUMP_SLOT: u32 = 5;
+/// ID of module containing symbol, 32 bit
+pub const R_RISCV_TLS_DTPMOD32: u32 = 6;
+/// ID of module containing symbol, 64 bit
+pub const R_RISCV_TLS_DTPMOD64: u32 = 7;
+/// Offset in module's TLS block, 32 bit
+pub const R_RISCV_TLS_DTPREL32: u32 = 8;
+/// Offset in module's TLS block, 64 bit
+pub const R_RISCV_TLS_DTPREL64: u32 = 9;
+/// Offset in initial TLS block, 32 bit
+pub const R_RISCV_TLS_TPREL32: u32 = 10;
+/// Offset in initial TLS block, 64 bit
+pub const R_RISCV_TLS_TPREL64: u32 = 11;
+/// 8 bit label addition
+pub const R_RISCV_ADD8: u32 = 33;
+/// 16 bit label addition
+pub const R_RISCV_ADD16: u32 = 34;
+/// 32 bit label addition
+pub const R_RISCV_ADD32: u32 = 35;
+/// 64 bit label addition
+pub const R_RISCV_ADD64: u32 = 36;
+/// 8 bit label subtraction
+pub const R_RISCV_SUB8: u32 = 37;
+/// 16 bit label subtraction
+pub const R_RISCV_SUB16: u32 = 38;
+/// 32 bit label subtraction
+pub const R_RISCV_SUB32: u32 = 39;
+/// 64 bit label subtraction
+pub const R_RISCV_SUB64: u32 = 40;
+/// Instruction can be relaxed by the linker
+pub const R_RISCV_RELAX: u32 = 51;
+/// Lower 6 bits label subtraction
+pub const R_RISCV_SUB6: u32 = 52;
+/// Lower 6 bits set
+pub const R_RISCV_SET6: u32 = 53;
+/// 8 bit set
+pub const R_RISCV_SET8: u32 = 54;
+/// 16 bit set
+pub const R_RISCV_SET16: u32 = 55;
+/// 32 bit set
+pub const R_RISCV_SET32: u32 = 56;
+/// PC relative 32 bit
+pub const R_RISCV_32_PCREL: u32 = 57;
+/// Adjust indirectly by program base
+pub const R_RISCV_IRELATIVE: u32 = 58;
+/// ULEB128 set
+pub const R_RISCV_SET_ULEB128: u32 = 60;
+/// ULEB128 subtraction
+pub const R_RISCV_SUB_ULEB128: u32 = 61;
+
+/// End of auxiliary vector
+pub const AT_NULL: u64 = 0;
+/// Program headers of the program
+pub const AT_PHDR: u64 = 3;
+/// Size of a program header entry
+pub const AT_PHENT: u64 = 4;
+/// Number of program headers
+pub const AT_PHNUM: u64 = 5;
+/// System page size
+pub const AT_PAGESZ: u64 = 6;
+/// Base address of the interpreter
+pub const AT_BASE: u64 = 7;
+/// Flags
+pub const AT_FLAGS: u64 = 8;
+/// Entry point of the program
+pub const AT_ENTRY: u64 = 9;
@@ -0,0 +1,1095 @@
+use crate::coredump::{CoreDump, MappedFile, Thread};
+use crate::debug_info::Range;
+use crate::dwarf::Reader;
+use crate::eh_frame::{Cie, Fde, FrameTable};
+use crate::types;
+use crate::{File, ParseError};
+
+/// Maximum number of operations executed when evaluating a DWARF expression
+const MAX_EXPRESSION_OPS: usize = 10_000;
+
+/// Mask of the address bits of AArch64 pointers, used to strip pointer authentication codes
+const AARCH64_ADDRESS_MASK: u64 = 0x0000_ffff_ffff_ffff;
+
+/// How to compute the canonical frame address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CfaRule {
+    /// A register plus an offset
+    RegisterOffset(u16, i64),
+    /// The value of a DWARF expression
+    Expression(Vec<u8>),
+}
+
+/// How to recover the caller's value of a register.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegisterRule {
+    /// The value can not be recovered
+    Undefined,
+    /// The register was not modified
+    SameValue,
+    /// The value is saved at CFA plus an offset
+    Offset(i64),
+    /// The value is CFA plus an offset
+    ValOffset(i64),
+    /// The value is saved in another register
+    Register(u16),
+    /// The value is saved at the address computed by a DWARF expression
+    Expression(Vec<u8>),
+    /// The value is computed by a DWARF expression
+    ValExpression(Vec<u8>),
+}
+
+/// The rules to unwind a frame at a given address, from executing call frame instructions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnwindRow {
+    /// First address the row applies to
+    pub address: u64,
+    /// Rule for the canonical frame address
+    pub cfa: CfaRule,
+    /// Rules for the registers, sorted by DWARF register number. Registers
+    /// without a rule keep their value.
+    pub registers: Vec<(u16, RegisterRule)>,
+    /// Set if the return address is signed, for AArch64 pointer authentication
+    pub ra_signed: bool,
+}
+
+impl UnwindRow {
+    /// Get the rule for a register, if there is one.
+    pub fn register(&self, register: u16) -> Option<&RegisterRule> {
+        self.registers
+            .binary_search_by_key(&register, |&(number, _)| number)
+            .ok()
+            .map(|index| &self.registers[index].1)
+    }
+
+    fn set_register(&mut self, register: u16, rule: Option<RegisterRule>) {
+        match (
+            self.registers
+                .binary_search_by_key(&register, |&(number, _)| number),
+            rule,
+        ) {
+            (Ok(index), Some(rule)) => self.registers[index].1 = rule,
+            (Ok(index), None) => {
+                self.registers.remove(index);
+            }
+            (Err(index), Some(rule)) => self.registers.insert(index, (register, rule)),
+            (Err(_), None) => (),
+        }
+    }
+}
+
+/// Execute call frame instructions up to an address. The initial rules are
+/// needed by DW_CFA_restore, and are None while executing the CIE's
+/// instructions.
+fn execute_cfi(
+    row: &mut UnwindRow,
+    instructions: &[u8],
+    initial: Option<&UnwindRow>,
+    cie: &Cie,
+    target: u64,
+    endianness: types::ElfEndianness,
+) -> Result<(), ParseError> {
+    let mut reader = Reader::new(instructions, endianness);
+    let mut stack: Vec<UnwindRow> = Vec::new();
+    let code_align = cie.code_alignment_factor;
+    let data_align = cie.data_alignment_factor;
+    let restore = |row: &mut UnwindRow, register: u16| -> Result<(), ParseError> {
+        let initial = initial.ok_or_else(|| {
+            ParseError::InvalidFormat(Some("DW_CFA_restore in CIE instructions".to_string()))
+        })?;
+        row.set_register(register, initial.register(register).cloned());
+        Ok(())
+    };
+    while !reader.is_empty() {
+        let opcode = reader.u8()?;
+        let advance = match opcode >> 6 {
+            // DW_CFA_advance_loc
+            1 => Some(u64::from(opcode & 0x3f)),
+            // DW_CFA_offset
+            2 => {
+                let offset = reader.uleb128()? as i64 * data_align;
+                row.set_register(u16::from(opcode & 0x3f), Some(RegisterRule::Offset(offset)));
+                None
+            }
+            // DW_CFA_restore
+            3 => {
+                restore(row, u16::from(opcode & 0x3f))?;
+                None
+            }
+            _ => match opcode {
+                // DW_CFA_nop
+                0x00 => None,
+                // DW_CFA_set_loc
+                0x01 => {
+                    let address = reader.sized(cie.address_size)?;
+                    if address > target {
+                        return Ok(());
+                    }
+                    row.address = address;
+                    None
+                }
+                // DW_CFA_advance_loc1, DW_CFA_advance_loc2, DW_CFA_advance_loc4
+                0x02 => Some(u64::from(reader.u8()?)),
+                0x03 => Some(u64::from(reader.u16()?)),
+                0x04 => Some(u64::from(reader.u32()?)),
+                // DW_CFA_offset_extended
+                0x05 => {
+                    let register = reader.uleb128()? as u16;
+                    let offset = reader.uleb128()? as i64 * data_align;
+                    row.set_register(register, Some(RegisterRule::Offset(offset)));
+                    None
+                }
+                // DW_CFA_restore_extended
+                0x06 => {
+                    restore(row, reader.uleb128()? as u16)?;
+                    None
+                }
+                // DW_CFA_undefined
+                0x07 => {
+                    let register = reader.uleb128()? as u16;
+                    row.set_register(register, Some(RegisterRule::Undefined));
+                    None
+                }
+                // DW_CFA_same_value
+                0x08 => {
+                    let register = reader.uleb128()? as u16;
+                    row.set_register(register, Some(RegisterRule::SameValue));
+                    None
+                }
+                // DW_CFA_register
+                0x09 => {
+                    let register = reader.uleb128()? as u16;
+                    let other = reader.uleb128()? as u16;
+                    row.set_register(register, Some(RegisterRule::Register(other)));
+                    None
+                }
+                // DW_CFA_remember_state
+                0x0a => {
+                    stack.push(row.clone());
+                    None
+                }
+                // DW_CFA_restore_state, which keeps the current location
+                0x0b => {
+                    let saved = stack.pop().ok_or_else(|| {
+                        ParseError::InvalidFormat(Some(
+                            "DW_CFA_restore_state without saved state".to_string(),
+                        ))
+                    })?;
+                    let address = row.address;
+                    *row = saved;
+                    row.address = address;
+                    None
+                }
+                // DW_CFA_def_cfa
+                0x0c => {
+                    let register = reader.uleb128()? as u16;
+                    let offset = reader.uleb128()? as i64;
+                    row.cfa = CfaRule::RegisterOffset(register, offset);
+                    None
+                }
+                // DW_CFA_def_cfa_register
+                0x0d => {
+                    let register = reader.uleb128()? as u16;
+                    let offset = match row.cfa {
+                        CfaRule::RegisterOffset(_, offset) => offset,
+                        CfaRule::Expression(_) => 0,
+                    };
+                    row.cfa = CfaRule::RegisterOffset(register, offset);
+                    None
+                }
+                // DW_CFA_def_cfa_offset
+                0x0e => {
+                    let offset = reader.uleb128()? as i64;
+                    if let CfaRule::RegisterOffset(_, ref mut cfa_offset) = row.cfa {
+                        *cfa_offset = offset;
+                    }
+                    None
+                }
+                // DW_CFA_def_cfa_expression
+                0x0f => {
+                    let len = reader.uleb128()? as usize;
+                    row.cfa = CfaRule::Expression(reader.bytes(len)?.to_vec());
+                    None
+                }
+                // DW_CFA_expression, DW_CFA_val_expression
+                0x10 | 0x16 => {
+                    let register = reader.uleb128()? as u16;
+                    let len = reader.uleb128()? as usize;
+                    let expression = reader.bytes(len)?.to_vec();
+                    let rule = if opcode == 0x10 {
+                        RegisterRule::Expression(expression)
+                    } else {
+                        RegisterRule::ValExpression(expression)
+                    };
+                    row.set_register(register, Some(rule));
+                    None
+                }
+                // DW_CFA_offset_extended_sf
+                0x11 => {
+                    let register = reader.uleb128()? as u16;
+                    let offset = reader.sleb128()? * data_align;
+                    row.set_register(register, Some(RegisterRule::Offset(offset)));
+                    None
+                }
+                // DW_CFA_def_cfa_sf
+                0x12 => {
+                    let register = reader.uleb128()? as u16;
+                    let offset = reader.sleb128()? * data_align;
+                    row.cfa = CfaRule::RegisterOffset(register, offset);
+                    None
+                }
+                // DW_CFA_def_cfa_offset_sf
+                0x13 => {
+                    let offset = reader.sleb128()? * data_align;
+                    if let CfaRule::RegisterOffset(_, ref mut cfa_offset) = row.cfa {
+                        *cfa_offset = offset;
+                    }
+                    None
+                }
+                // DW_CFA_val_offset, DW_CFA_val_offset_sf
+                0x14 | 0x15 => {
+                    let register = reader.uleb128()? as u16;
+                    let offset = if opcode == 0x14 {
+                        reader.uleb128()? as i64
+                    } else {
+                        reader.sleb128()?
+                    };
+                    row.set_register(register, Some(RegisterRule::ValOffset(offset * data_align)));
+                    None
+                }
+                // DW_CFA_GNU_window_save, which is DW_CFA_AARCH64_negate_ra_state on AArch64
+                0x2d => {
+                    row.ra_signed = !row.ra_signed;
+                    None
+                }
+                // DW_CFA_GNU_args_size
+                0x2e => {
+                    reader.uleb128()?;
+                    None
+                }
+                // DW_CFA_GNU_negative_offset_extended
+                0x2f => {
+                    let register = reader.uleb128()? as u16;
+                    let offset = -(reader.uleb128()? as i64) * data_align;
+                    row.set_register(register, Some(RegisterRule::Offset(offset)));
+                    None
+                }
+                _ => {
+                    return Err(ParseError::InvalidFormat(Some(format!(
+                        "unknown call frame instruction {:#x}",
+                        opcode
+                    ))))
+                }
+            },
+        };
+        if let Some(delta) = advance {
+            let address = row.address.wrapping_add(delta * code_align);
+            if address > target {
+                return Ok(());
+            }
+            row.address = address;
+        }
+    }
+    Ok(())
+}
+
+/// Compute the unwind rules of an FDE at an address.
+///
+/// The CIE's initial instructions are executed, then the FDE's instructions
+/// up to the address.
+pub fn unwind_row(
+    cie: &Cie,
+    fde: &Fde,
+    address: u64,
+    endianness: types::ElfEndianness,
+) -> Result<UnwindRow, ParseError> {
+    let mut row = UnwindRow {
+        address: fde.initial_location,
+        cfa: CfaRule::RegisterOffset(0, 0),
+        registers: Vec::new(),
+        ra_signed: false,
+    };
+    execute_cfi(
+        &mut row,
+        &cie.initial_instructions,
+        None,
+        cie,
+        u64::MAX,
+        endianness,
+    )?;
+    row.address = fde.initial_location;
+    let initial = row.clone();
+    execute_cfi(
+        &mut row,
+        &fde.instructions,
+        Some(&initial),
+        cie,
+        address,
+        endianness,
+    )?;
+    Ok(row)
+}
+
+/// Evaluate a DWARF expression from call frame information.
+///
+/// Register values come from `registers`, indexed by DWARF register number,
+/// and memory is read through `read`. The initial value, if any, is pushed
+/// on the stack before evaluation.
+pub fn evaluate_expression<F: Fn(u64) -> Option<u64>>(
+    expression: &[u8],
+    initial: Option<u64>,
+    registers: &[Option<u64>],
+    read: F,
+    endianness: types::ElfEndianness,
+    address_size: u8,
+) -> Result<u64, ParseError> {
+    let mut reader = Reader::new(expression, endianness);
+    let mut stack: Vec<u64> = initial.into_iter().collect();
+    let underflow =
+        || ParseError::InvalidFormat(Some("DWARF expression stack underflow".to_string()));
+    let register = |number: u64| {
+        registers
+            .get(number as usize)
+            .copied()
+            .flatten()
+            .ok_or_else(|| {
+                ParseError::InvalidFormat(Some(format!("register {} is not available", number)))
+            })
+    };
+    let deref = |address: u64| {
+        read(address).ok_or_else(|| {
+            ParseError::InvalidFormat(Some(format!("memory at {:#x} is not available", address)))
+        })
+    };
+    let mut ops = 0;
+    while !reader.is_empty() {
+        ops += 1;
+        if ops > MAX_EXPRESSION_OPS {
+            return Err(ParseError::InvalidFormat(Some(
+                "DWARF expression does not terminate".to_string(),
+            )));
+        }
+        let opcode = reader.u8()?;
+        match opcode {
+            // DW_OP_addr
+            0x03 => stack.push(reader.sized(address_size)?),
+            // DW_OP_deref
+            0x06 => {
+                let address = stack.pop().ok_or_else(underflow)?;
+                stack.push(deref(address)?);
+            }
+            // DW_OP_const1u, DW_OP_const1s
+            0x08 => stack.push(u64::from(reader.u8()?)),
+            0x09 => stack.push(reader.u8()? as i8 as u64),
+            // DW_OP_const2u, DW_OP_const2s
+            0x0a => stack.push(u64::from(reader.u16()?)),
+            0x0b => stack.push(reader.u16()? as i16 as u64),
+            // DW_OP_const4u, DW_OP_const4s
+            0x0c => stack.push(u64::from(reader.u32()?)),
+            0x0d => stack.push(reader.u32()? as i32 as u64),
+            // DW_OP_const8u, DW_OP_const8s
+            0x0e | 0x0f => stack.push(reader.u64()?),
+            // DW_OP_constu, DW_OP_consts
+            0x10 => stack.push(reader.uleb128()?),
+            0x11 => stack.push(reader.sleb128()? as u64),
+            // DW_OP_dup
+            0x12 => stack.push(*stack.last().ok_or_else(underflow)?),
+            // DW_OP_drop
+            0x13 => {
+                stack.pop().ok_or_else(underflow)?;
+            }
+            // DW_OP_over
+            0x14 => {
+                let index = stack.len().checked_sub(2).ok_or_else(underflow)?;
+                stack.push(stack[index]);
+            }
+            // DW_OP_pick
+            0x15 => {
+                let depth = usize::from(reader.u8()?);
+                let index = stack.len().checked_sub(depth + 1).ok_or_else(underflow)?;
+                stack.push(stack[index]);
+            }
+            // DW_OP_swap
+            0x16 => {
+                let len = stack.len();
+                if len < 2 {
+                    return Err(underflow());
+                }
+                stack.swap(len - 1, len - 2);
+            }
+            // DW_OP_rot
+            0x17 => {
+                let len = stack.len();
+                if len < 3 {
+                    return Err(underflow());
+                }
+                stack[len - 3..].rotate_right(1);
+            }
+            // DW_OP_abs
+            0x19 => {
+                let value = stack.pop().ok_or_else(underflow)?;
+                stack.push((value as i64).wrapping_abs() as u64);
+            }
+            // DW_OP_neg
+            0x1f => {
+                let value = stack.pop().ok_or_else(underflow)?;
+                stack.push((value as i64).wrapping_neg() as u64);
+            }
+            // DW_OP_not
+            0x20 => {
+                let value = stack.pop().ok_or_else(underflow)?;
+                stack.push(!value);
+            }
+            // DW_OP_plus_uconst
+            0x23 => {
+                let value = stack.pop().ok_or_else(underflow)?;
+                stack.push(value.wrapping_add(reader.uleb128()?));
+            }
+            // Binary operations
+            0x1a..=0x1e | 0x21 | 0x22 | 0x24..=0x27 | 0x29..=0x2e => {
+                let b = stack.pop().ok_or_else(underflow)?;
+                let a = stack.pop().ok_or_else(underflow)?;
+                let division_by_zero = || {
+                    ParseError::InvalidFormat(Some("DWARF expression divides by zero".to_string()))
+                };
+                stack.push(match opcode {
+                    0x1a => a & b,
+                    0x1b => (a as i64)
+                        .checked_div(b as i64)
+                        .ok_or_else(division_by_zero)? as u64,
+                    0x1c => a.wrapping_sub(b),
+                    0x1d => a.checked_rem(b).ok_or_else(division_by_zero)?,
+                    0x1e => a.wrapping_mul(b),
+                    0x21 => a | b,
+                    0x22 => a.wrapping_add(b),
+                    0x24 => a.checked_shl(b as u32).unwrap_or(0),
+                    0x25 => a.checked_shr(b as u32).unwrap_or(0),
+                    0x26 => (a as i64).checked_shr(b as u32).unwrap_or((a as i64) >> 63) as u64,
+                    0x27 => a ^ b,
+                    0x29 => (a == b) as u64,
+                    0x2a => (a as i64 >= b as i64) as u64,
+                    0x2b => (a as i64 > b as i64) as u64,
+                    0x2c => (a as i64 <= b as i64) as u64,
+                    0x2d => ((a as i64) < b as i64) as u64,
+                    _ => (a != b) as u64,
+                });
+            }
+            // DW_OP_skip, DW_OP_bra
+            0x2f | 0x28 => {
+                let offset = reader.u16()? as i16;
+                let taken = opcode == 0x2f || stack.pop().ok_or_else(underflow)? != 0;
+                if taken {
+                    let target = reader.offset as i64 + i64::from(offset);
+                    if target < 0 || target as usize > expression.len() {
+                        return Err(ParseError::InvalidFormat(Some(
+                            "DWARF expression branches out of bounds".to_string(),
+                        )));
+                    }
+                    reader = Reader::new(expression, endianness);
+                    reader.bytes(target as usize)?;
+                }
+            }
+            // DW_OP_lit0 to DW_OP_lit31
+            0x30..=0x4f => stack.push(u64::from(opcode - 0x30)),
+            // DW_OP_breg0 to DW_OP_breg31
+            0x70..=0x8f => {
+                let value = register(u64::from(opcode - 0x70))?;
+                stack.push(value.wrapping_add(reader.sleb128()? as u64));
+            }
+            // DW_OP_bregx
+            0x92 => {
+                let value = register(reader.uleb128()?)?;
+                stack.push(value.wrapping_add(reader.sleb128()? as u64));
+            }
+            // DW_OP_deref_size
+            0x94 => {
+                let size = reader.u8()?;
+                let address = stack.pop().ok_or_else(underflow)?;
+                let value = deref(address)?;
+                let value = match (size, endianness) {
+                    (8, _) => value,
+                    (1..=7, types::ElfEndianness::Lsb) => value & ((1 << (size * 8)) - 1),
+                    (1..=7, types::ElfEndianness::Msb) => value >> ((8 - u32::from(size)) * 8),
+                    _ => {
+                        return Err(ParseError::InvalidFormat(Some(format!(
+                            "invalid DW_OP_deref_size size {}",
+                            size
+                        ))))
+                    }
+                };
+                stack.push(value);
+            }
+            // DW_OP_nop
+            0x96 => (),
+            _ => return Err(ParseError::NotImplemented),
+        }
+    }
+    stack.pop().ok_or_else(underflow)
+}
+
+/// Register conventions of an architecture.
+struct Architecture {
+    /// Index in the NT_PRSTATUS registers of each DWARF register
+    prstatus: &'static [usize],
+    /// Index in the NT_PRSTATUS registers of the program counter
+    pc: usize,
+    stack_pointer: u16,
+    frame_pointer: u16,
+    return_address: u16,
+    /// Set if calls save the return address in a register rather than on the stack
+    link_register: bool,
+}
+
+const X86_64: Architecture = Architecture {
+    // rax, rdx, rcx, rbx, rsi, rdi, rbp, rsp, r8-r15, rip
+    prstatus: &[10, 12, 11, 5, 13, 14, 4, 19, 9, 8, 7, 6, 3, 2, 1, 0, 16],
+    pc: 16,
+    stack_pointer: 7,
+    frame_pointer: 6,
+    return_address: 16,
+    link_register: false,
+};
+
+const AARCH64: Architecture = Architecture {
+    // x0-x30, sp
+    prstatus: &[
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+        25, 26, 27, 28, 29, 30, 31,
+    ],
+    pc: 32,
+    stack_pointer: 31,
+    frame_pointer: 29,
+    return_address: 30,
+    link_register: true,
+};
+
+impl Architecture {
+    fn get(arch: types::ElfCpuArchitecture) -> Option<&'static Architecture> {
+        match arch {
+            types::ElfCpuArchitecture::EM_X86_64 => Some(&X86_64),
+            types::ElfCpuArchitecture::EM_AARCH64 => Some(&AARCH64),
+            _ => None,
+        }
+    }
+}
+
+/// An executable or shared library mapped into a dumped process.
+#[derive(Clone, Debug)]
+pub struct Module {
+    /// Path of the file in the dumped process
+    pub path: String,
+    /// Addresses the file is mapped at
+    pub range: Range,
+    /// Difference between the addresses in the process and the addresses in the file
+    pub bias: u64,
+    eh_frame: Option<FrameTable>,
+    debug_frame: Option<FrameTable>,
+    /// (start, end, name) of function symbols, sorted by start
+    symbols: Vec<(u64, u64, String)>,
+}
+
+impl Module {
+    /// Load the unwind information and symbols of a file from its mappings.
+    ///
+    /// `mappings` are the NT_FILE entries of the file in the dumped process.
+    pub fn load(file: &File, mappings: &[MappedFile]) -> Result<Module, ParseError> {
+        let first = mappings
+            .iter()
+            .min_by_key(|mapping| mapping.file_offset)
+            .ok_or_else(|| ParseError::InvalidFormat(Some("file is not mapped".to_string())))?;
+        // Find the segment holding the start of the first mapping to relate
+        // process addresses to the addresses in the file. Segments are mapped
+        // from the start of the page holding their first byte.
+        let phdr = file
+            .phdrs
+            .iter()
+            .find(|phdr| {
+                let start = phdr.offset - phdr.offset % phdr.align.max(1);
+                phdr.progtype == types::PT_LOAD
+                    && start <= first.file_offset
+                    && first.file_offset < phdr.offset.saturating_add(phdr.filesz)
+            })
+            .ok_or_else(|| ParseError::InvalidFormat(Some("no loadable segment".to_string())))?;
+        let bias = first
+            .start
+            .wrapping_add(phdr.offset)
+            .wrapping_sub(first.file_offset)
+            .wrapping_sub(phdr.vaddr);
+
+        let mut symbols = Vec::new();
+        for section in file.sections.iter().filter(|section| {
+            section.shdr.shtype == types::SectionType::Symtab
+                || section.shdr.shtype == types::SectionType::Dynsym
+        }) {
+            symbols.extend(
+                file.get_symbols(section)?
+                    .into_iter()
+                    .filter(|symbol| {
+                        (symbol.symtype == types::STT_FUNC
+                            || symbol.symtype == types::STT_GNU_IFUNC)
+                            && symbol.value != 0
+                            && symbol.size != 0
+                    })
+                    .map(|symbol| (symbol.value, symbol.value + symbol.size, symbol.name)),
+            );
+        }
+        symbols.sort();
+        symbols.dedup_by_key(|symbol| symbol.0);
+
+        Ok(Module {
+            path: first.path.clone(),
+            range: Range {
+                begin: mappings
+                    .iter()
+                    .map(|mapping| mapping.start)
+                    .min()
+                    .unwrap_or(0),
+                end: mappings
+                    .iter()
+                    .map(|mapping| mapping.end)
+                    .max()
+                    .unwrap_or(0),
+            },
+            bias,
+            eh_frame: file.get_eh_frame()?,
+            debug_frame: file.get_debug_frame()?,
+            symbols,
+        })
+    }
+
+    /// Get the name of the function containing a process address.
+    pub fn symbolize(&self, address: u64) -> Option<&str> {
+        let address = address.wrapping_sub(self.bias);
+        let index = self
+            .symbols
+            .partition_point(|&(start, _, _)| start <= address);
+        self.symbols[..index]
+            .iter()
+            .rev()
+            .find(|&&(start, end, _)| start <= address && address < end)
+            .map(|(_, _, name)| name.as_str())
+    }
+
+    /// Find the CIE and FDE covering a process address, preferring `.eh_frame`.
+    pub fn find_fde(&self, address: u64) -> Option<(&Cie, &Fde)> {
+        let address = address.wrapping_sub(self.bias);
+        self.eh_frame
+            .iter()
+            .chain(self.debug_frame.iter())
+            .find_map(|table| {
+                let fde = table.find_fde(address)?;
+                Some((table.cie(fde)?, fde))
+            })
+    }
+}
+
+/// How a stack frame was found.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UnwindMethod {
+    /// From the thread's registers
+    Initial,
+    /// By executing call frame information
+    Cfi,
+    /// By following the frame pointer chain
+    FramePointer,
+}
+
+/// A frame of an unwound stack.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackFrame {
+    /// Program counter. For caller frames, this is the return address.
+    pub pc: u64,
+    /// Canonical frame address, if the frame could be unwound
+    pub cfa: Option<u64>,
+    /// Name of the function containing the program counter
+    pub function: Option<String>,
+    /// Path of the module containing the program counter
+    pub module: Option<String>,
+    /// How the frame was found
+    pub method: UnwindMethod,
+}
+
+/// Options for unwinding stacks.
+#[derive(Clone, Debug)]
+pub struct UnwindOptions {
+    /// Maximum number of frames to unwind
+    pub max_frames: usize,
+    /// Use call frame information. If unset, only frame pointers are used.
+    pub use_cfi: bool,
+}
+
+impl Default for UnwindOptions {
+    fn default() -> Self {
+        UnwindOptions {
+            max_frames: 256,
+            use_cfi: true,
+        }
+    }
+}
+
+/// Caller register state from unwinding one frame.
+struct Step {
+    cfa: u64,
+    pc: u64,
+    registers: Vec<Option<u64>>,
+}
+
+impl CoreDump {
+    /// Load the unwind information and symbols of the files mapped into the process.
+    ///
+    /// `resolve` opens the file for a path in the dumped process, e.g. under
+    /// a sysroot. Files it returns None for are skipped.
+    pub fn load_modules<F: FnMut(&str) -> Option<File>>(
+        &self,
+        mut resolve: F,
+    ) -> Result<Vec<Module>, ParseError> {
+        let mapped = self.mapped_files()?;
+        let mut paths: Vec<&str> = mapped.iter().map(|mapping| mapping.path.as_str()).collect();
+        paths.sort_unstable();
+        paths.dedup();
+        let mut modules = Vec::new();
+        for path in paths {
+            let file = match resolve(path) {
+                Some(file) => file,
+                None => continue,
+            };
+            let mappings: Vec<MappedFile> = mapped
+                .iter()
+                .filter(|mapping| mapping.path == path)
+                .cloned()
+                .collect();
+            modules.push(Module::load(&file, &mappings)?);
+        }
+        modules.sort_by_key(|module| module.range.begin);
+        Ok(modules)
+    }
+
+    /// Unwind the stack of a thread.
+    ///
+    /// Frames are unwound with the call frame information of the module
+    /// containing the program counter, falling back to the frame pointer
+    /// chain if there is none. Only x86_64 and AArch64 are supported.
+    pub fn unwind(
+        &self,
+        thread: &Thread,
+        modules: &[Module],
+        options: &UnwindOptions,
+    ) -> Result<Vec<StackFrame>, ParseError> {
+        let arch = Architecture::get(self.file.header.cpu_architecture)
+            .ok_or(ParseError::NotImplemented)?;
+        let mut registers: Vec<Option<u64>> = arch
+            .prstatus
+            .iter()
+            .map(|&index| thread.registers.get(index).copied())
+            .collect();
+        let mut pc = thread
+            .registers
+            .get(arch.pc)
+            .copied()
+            .ok_or_else(|| ParseError::InvalidFormat(Some("missing registers".to_string())))?;
+        let mut method = UnwindMethod::Initial;
+        let mut frames = Vec::new();
+        while frames.len() < options.max_frames && pc != 0 {
+            // Return addresses point after the call, which may be past the end of the function
+            let lookup = if method == UnwindMethod::Initial {
+                pc
+            } else {
+                pc.wrapping_sub(1)
+            };
+            let module = modules.iter().find(|module| module.range.contains(lookup));
+            let mut next_method = UnwindMethod::Cfi;
+            let step = match module.and_then(|module| module.find_fde(lookup)) {
+                Some((cie, fde)) if options.use_cfi => {
+                    let address = lookup.wrapping_sub(module.unwrap().bias);
+                    self.unwind_cfi(arch, cie, fde, address, &registers)
+                }
+                _ => None,
+            }
+            .or_else(|| {
+                next_method = UnwindMethod::FramePointer;
+                self.unwind_frame_pointer(arch, &registers)
+            });
+            frames.push(StackFrame {
+                pc,
+                cfa: step.as_ref().map(|step| step.cfa),
+                function: module
+                    .and_then(|module| module.symbolize(lookup))
+                    .map(str::to_string),
+                module: module.map(|module| module.path.clone()),
+                method,
+            });
+            let step = match step {
+                Some(step) => step,
+                None => break,
+            };
+            // The stack grows down, so a caller's stack pointer can not be lower
+            let sp = registers[arch.stack_pointer as usize];
+            let next_sp = step.registers[arch.stack_pointer as usize];
+            if next_sp < sp || (next_sp == sp && step.pc == pc) {
+                break;
+            }
+            pc = step.pc;
+            registers = step.registers;
+            method = next_method;
+        }
+        Ok(frames)
+    }
+
+    fn unwind_cfi(
+        &self,
+        arch: &Architecture,
+        cie: &Cie,
+        fde: &Fde,
+        address: u64,
+        registers: &[Option<u64>],
+    ) -> Option<Step> {
+        let endianness = self.file.header.endianness;
+        let row = unwind_row(cie, fde, address, endianness).ok()?;
+        let read = |address| self.read_pointer(address);
+        let evaluate = |expression: &[u8], initial| {
+            evaluate_expression(expression, initial, registers, read, endianness, 8).ok()
+        };
+        let cfa = match row.cfa {
+            CfaRule::RegisterOffset(register, offset) => registers
+                .get(register as usize)
+                .copied()??
+                .wrapping_add(offset as u64),
+            CfaRule::Expression(ref expression) => evaluate(expression, None)?,
+        };
+        let mut caller = registers.to_vec();
+        caller[arch.stack_pointer as usize] = Some(cfa);
+        for &(register, ref rule) in &row.registers {
+            let value = match *rule {
+                RegisterRule::Undefined => None,
+                RegisterRule::SameValue => registers.get(register as usize).copied().flatten(),
+                RegisterRule::Offset(offset) => read(cfa.wrapping_add(offset as u64)),
+                RegisterRule::ValOffset(offset) => Some(cfa.wrapping_add(offset as u64)),
+                RegisterRule::Register(other) => registers.get(other as usize).copied().flatten(),
+                RegisterRule::Expression(ref expression) => {
+                    evaluate(expression, Some(cfa)).and_then(read)
+                }
+                RegisterRule::ValExpression(ref expression) => evaluate(expression, Some(cfa)),
+            };
+            if let Some(slot) = caller.get_mut(register as usize) {
+                *slot = value;
+            }
+        }
+        let column = cie.return_address_register as usize;
+        let mut pc = match row.register(column as u16) {
+            Some(_) => caller.get(column).copied()??,
+            // Without a rule, the return address is still in its register
+            None => registers.get(column).copied()??,
+        };
+        if row.ra_signed && arch.link_register {
+            pc &= AARCH64_ADDRESS_MASK;
+        }
+        Some(Step {
+            cfa,
+            pc,
+            registers: caller,
+        })
+    }
+
+    fn unwind_frame_pointer(&self, arch: &Architecture, registers: &[Option<u64>]) -> Option<Step> {
+        let fp = registers[arch.frame_pointer as usize]?;
+        if fp == 0 {
+            return None;
+        }
+        // Both architectures save the caller's frame pointer and the return
+        // address in a frame record at the frame pointer
+        let cfa = fp.checked_add(16)?;
+        let pc = self.read_pointer(fp.checked_add(8)?)?;
+        let mut caller = registers.to_vec();
+        caller[arch.frame_pointer as usize] = Some(self.read_pointer(fp)?);
+        caller[arch.stack_pointer as usize] = Some(cfa);
+        if arch.link_register {
+            caller[arch.return_address as usize] = Some(pc);
+        }
+        Some(Step {
+            cfa,
+            pc,
+            registers: caller,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn unwind_sample(use_cfi: bool) -> Vec<StackFrame> {
+        let core = CoreDump::open_path("tests/samples/core/core").unwrap();
+        let modules = core
+            .load_modules(|path| {
+                if path.ends_with("/crash") {
+                    File::open_path("tests/samples/core/crash").ok()
+                } else {
+                    None
+                }
+            })
+            .unwrap();
+        assert_eq!(modules.len(), 1);
+        unwind_modules(&core, &modules, use_cfi)
+    }
+
+    fn unwind_modules(core: &CoreDump, modules: &[Module], use_cfi: bool) -> Vec<StackFrame> {
+        let threads = core.threads().unwrap();
+        let options = UnwindOptions {
+            use_cfi,
+            ..Default::default()
+        };
+        core.unwind(&threads[0], modules, &options).unwrap()
+    }
+
+    #[test]
+    fn test_unwind_cfi() {
+        let frames = unwind_sample(true);
+        let names: Vec<Option<&str>> = frames
+            .iter()
+            .take(5)
+            .map(|frame| frame.function.as_deref())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                Some("inner"),
+                Some("inner"),
+                Some("inner"),
+                Some("outer"),
+                Some("main")
+            ]
+        );
+        let offsets: Vec<u64> = frames
+            .iter()
+            .take(5)
+            .map(|frame| frame.pc & 0xfff)
+            .collect();
+        assert_eq!(offsets, vec![0x134, 0x147, 0x147, 0x157, 0x162]);
+        assert_eq!(frames[0].method, UnwindMethod::Initial);
+        assert!(frames[1..5]
+            .iter()
+            .all(|frame| frame.method == UnwindMethod::Cfi));
+        assert!(frames[..5]
+            .windows(2)
+            .all(|pair| pair[0].cfa.unwrap() < pair[1].cfa.unwrap()));
+        // libc was not loaded, so its frames have no names
+        assert!(frames[5..].iter().all(|frame| frame.function.is_none()));
+    }
+
+    #[test]
+    fn test_unwind_sysroot() {
+        // The sysroot libc only keeps the sections needed for unwinding
+        let core = CoreDump::open_path("tests/samples/core/core").unwrap();
+        let modules = core
+            .load_modules(|path| {
+                if path.ends_with("/crash") {
+                    return File::open_path("tests/samples/core/crash").ok();
+                }
+                let path = Path::new("tests/samples/core/sysroot").join(&path[1..]);
+                File::open_path(path).ok()
+            })
+            .unwrap();
+        assert_eq!(modules.len(), 2);
+        let libc = &modules[1];
+        assert!(libc.path.ends_with("/libc.so.6"));
+        assert_eq!(libc.bias, libc.range.begin);
+
+        let frames = unwind_modules(&core, &modules, true);
+        assert_eq!(frames.len(), 8);
+        assert!(frames[1..]
+            .iter()
+            .all(|frame| frame.method == UnwindMethod::Cfi));
+        // __libc_start_call_main is a local symbol, so it is not in .dynsym
+        let names: Vec<Option<&str>> = frames[4..]
+            .iter()
+            .map(|frame| frame.function.as_deref())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                Some("main"),
+                None,
+                Some("__libc_start_main"),
+                Some("_start")
+            ]
+        );
+        assert!(frames[5..7]
+            .iter()
+            .all(|frame| libc.range.contains(frame.pc)));
+        // The return address of _start is undefined, ending the stack
+        assert_eq!(frames[7].cfa, None);
+    }
+
+    #[test]
+    fn test_unwind_aarch64() {
+        // leaf signs its return address and keeps it in x30, mid saves a
+        // frame record, and outer has no call frame information
+        let core = CoreDump::open_path("tests/samples/core/aarch64/core").unwrap();
+        let modules = core
+            .load_modules(|_| File::open_path("tests/samples/core/aarch64/libframes.so").ok())
+            .unwrap();
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].bias, 0x55_0000_0000);
+
+        let frames = unwind_modules(&core, &modules, true);
+        let summary: Vec<(u64, Option<&str>, UnwindMethod)> = frames
+            .iter()
+            .map(|frame| (frame.pc, frame.function.as_deref(), frame.method))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0x55_0000_0214, Some("leaf"), UnwindMethod::Initial),
+                (0x55_0000_022c, Some("mid"), UnwindMethod::Cfi),
+                (0x55_0000_024c, Some("outer"), UnwindMethod::Cfi),
+                (0x55_0000_024c, Some("outer"), UnwindMethod::FramePointer),
+            ]
+        );
+        let cfas: Vec<Option<u64>> = frames.iter().map(|frame| frame.cfa).collect();
+        assert_eq!(
+            cfas,
+            vec![
+                Some(0x7f_ffff_ff00),
+                Some(0x7f_ffff_ff20),
+                Some(0x7f_ffff_ff30),
+                Some(0x7f_ffff_ff40)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unwind_frame_pointer() {
+        let frames = unwind_sample(false);
+        assert_eq!(frames[0].function.as_deref(), Some("inner"));
+        assert!(frames[1..]
+            .iter()
+            .all(|frame| frame.method == UnwindMethod::FramePointer));
+        // The faulting function had not set up its frame yet, so its caller is skipped
+        let names: Vec<Option<&str>> = frames
+            .iter()
+            .skip(1)
+            .take(3)
+            .map(|frame| frame.function.as_deref())
+            .collect();
+        assert_eq!(names, vec![Some("inner"), Some("outer"), Some("main")]);
+    }
+
+    #[test]
+    fn test_expression() {
+        // DW_OP_breg7 8, DW_OP_lit3, DW_OP_plus, DW_OP_deref
+        let expression = [0x77, 0x08, 0x33, 0x22, 0x06];
+        let mut registers = vec![None; 8];
+        registers[7] = Some(0x1000);
+        let value = evaluate_expression(
+            &expression,
+            None,
+            &registers,
+            |address| if address == 0x100b { Some(42) } else { None },
+            types::ElfEndianness::Lsb,
+            8,
+        )
+        .unwrap();
+        assert_eq!(value, 42);
+    }
+}
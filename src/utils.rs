@@ -31,6 +31,39 @@ macro_rules! read_u64 {
     }};
 }
 
+#[macro_export]
+macro_rules! write_u16 {
+    ($elf:ident, $io:ident, $val:expr) => {{
+        use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
+        match $elf.header.endianness {
+            types::ElfEndianness::Lsb => $io.write_u16::<LittleEndian>($val),
+            types::ElfEndianness::Msb => $io.write_u16::<BigEndian>($val),
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! write_u32 {
+    ($elf:ident, $io:ident, $val:expr) => {{
+        use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
+        match $elf.header.endianness {
+            types::ElfEndianness::Lsb => $io.write_u32::<LittleEndian>($val),
+            types::ElfEndianness::Msb => $io.write_u32::<BigEndian>($val),
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! write_u64 {
+    ($elf:ident, $io:ident, $val:expr) => {{
+        use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
+        match $elf.header.endianness {
+            types::ElfEndianness::Lsb => $io.write_u64::<LittleEndian>($val),
+            types::ElfEndianness::Msb => $io.write_u64::<BigEndian>($val),
+        }
+    }};
+}
+
 use std;
 pub fn get_string(data: &[u8], start: usize) -> Result<String, std::string::FromUtf8Error> {
     let mut end: usize = 0;
@@ -0,0 +1,276 @@
+use crate::types;
+use crate::{utils, File, ParseError, Section};
+use std::io;
+
+/// Version index of a local symbol. Named VER_NDX_LOCAL in C code.
+pub const VER_NDX_LOCAL: u16 = 0;
+/// Version index of an unversioned global symbol. Named VER_NDX_GLOBAL in C code.
+pub const VER_NDX_GLOBAL: u16 = 1;
+/// Bit in a version symbol entry that hides the symbol from unversioned references
+pub const VERSYM_HIDDEN: u16 = 0x8000;
+/// Version definition of the file itself. Named VER_FLG_BASE in C code.
+pub const VER_FLG_BASE: u16 = 0x1;
+/// Weak version identifier. Named VER_FLG_WEAK in C code.
+pub const VER_FLG_WEAK: u16 = 0x2;
+
+/// A version required from a needed file, from an Elfxx_Vernaux entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionNeedAux {
+    /// Version name, e.g. "GLIBC_2.17"
+    pub name: String,
+    /// ELF hash of the version name
+    pub hash: u32,
+    /// Version flags, see VER_FLG_WEAK
+    pub flags: u16,
+    /// Version index used in the version symbol table
+    pub index: u16,
+}
+
+/// The versions required from one needed file, from an Elfxx_Verneed entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionNeed {
+    /// Name of the needed file, as in DT_NEEDED
+    pub file: String,
+    /// Required versions
+    pub versions: Vec<VersionNeedAux>,
+}
+
+/// A version defined by this file, from an Elfxx_Verdef entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionDef {
+    /// Version flags, see VER_FLG_BASE and VER_FLG_WEAK
+    pub flags: u16,
+    /// Version index used in the version symbol table
+    pub index: u16,
+    /// ELF hash of the version name
+    pub hash: u32,
+    /// Version name, e.g. "LIBFOO_1.0"
+    pub name: String,
+    /// Names of the versions this one inherits from
+    pub parents: Vec<String>,
+}
+
+/// The version attached to a dynamic symbol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolVersion {
+    /// Version name
+    pub name: String,
+    /// For required versions, the file that provides it
+    pub file: Option<String>,
+    /// The symbol is hidden from unversioned references (the `@` rather than `@@` form)
+    pub hidden: bool,
+}
+
+impl File {
+    fn get_version_section(&self, shtype: types::SectionType) -> Option<&Section> {
+        self.sections
+            .iter()
+            .find(|section| section.shdr.shtype == shtype)
+    }
+
+    /// The string table linked from a version section
+    fn version_strtab(&self, section: &Section) -> Result<&[u8], ParseError> {
+        self.sections
+            .get(section.shdr.link as usize)
+            .map(|strtab| &strtab.data[..])
+            .ok_or(ParseError::InvalidFormat(Some(format!(
+                "Invalid sh_link in {}",
+                section.shdr.name
+            ))))
+    }
+
+    /// Parse the SHT_GNU_VERNEED (`.gnu.version_r`) section.
+    pub fn get_version_needs(&self) -> Result<Vec<VersionNeed>, ParseError> {
+        let mut needs = Vec::new();
+        let section = match self.get_version_section(types::SectionType::GnuVerneed) {
+            Some(section) => section,
+            None => return Ok(needs),
+        };
+        let strtab = self.version_strtab(section)?;
+        let data = &section.data;
+
+        let mut offset = 0u64;
+        for _ in 0..section.shdr.info {
+            let mut io_section = io::Cursor::new(data);
+            io_section.set_position(offset);
+            let _vn_version = read_u16!(self, io_section)?;
+            let vn_cnt = read_u16!(self, io_section)?;
+            let vn_file = read_u32!(self, io_section)?;
+            let vn_aux = read_u32!(self, io_section)?;
+            let vn_next = read_u32!(self, io_section)?;
+
+            let mut versions = Vec::new();
+            let mut aux_offset = offset + vn_aux as u64;
+            for _ in 0..vn_cnt {
+                io_section.set_position(aux_offset);
+                let hash = read_u32!(self, io_section)?;
+                let flags = read_u16!(self, io_section)?;
+                let index = read_u16!(self, io_section)?;
+                let vna_name = read_u32!(self, io_section)?;
+                let vna_next = read_u32!(self, io_section)?;
+                versions.push(VersionNeedAux {
+                    name: utils::get_string(strtab, vna_name as usize)?,
+                    hash,
+                    flags,
+                    index,
+                });
+                if vna_next == 0 {
+                    break;
+                }
+                aux_offset += vna_next as u64;
+            }
+
+            needs.push(VersionNeed {
+                file: utils::get_string(strtab, vn_file as usize)?,
+                versions,
+            });
+            if vn_next == 0 {
+                break;
+            }
+            offset += vn_next as u64;
+        }
+        Ok(needs)
+    }
+
+    /// Parse the SHT_GNU_VERDEF (`.gnu.version_d`) section.
+    pub fn get_version_definitions(&self) -> Result<Vec<VersionDef>, ParseError> {
+        let mut defs = Vec::new();
+        let section = match self.get_version_section(types::SectionType::GnuVerdef) {
+            Some(section) => section,
+            None => return Ok(defs),
+        };
+        let strtab = self.version_strtab(section)?;
+        let data = &section.data;
+
+        let mut offset = 0u64;
+        for _ in 0..section.shdr.info {
+            let mut io_section = io::Cursor::new(data);
+            io_section.set_position(offset);
+            let _vd_version = read_u16!(self, io_section)?;
+            let flags = read_u16!(self, io_section)?;
+            let index = read_u16!(self, io_section)?;
+            let vd_cnt = read_u16!(self, io_section)?;
+            let hash = read_u32!(self, io_section)?;
+            let vd_aux = read_u32!(self, io_section)?;
+            let vd_next = read_u32!(self, io_section)?;
+
+            let mut names = Vec::new();
+            let mut aux_offset = offset + vd_aux as u64;
+            for _ in 0..vd_cnt {
+                io_section.set_position(aux_offset);
+                let vda_name = read_u32!(self, io_section)?;
+                let vda_next = read_u32!(self, io_section)?;
+                names.push(utils::get_string(strtab, vda_name as usize)?);
+                if vda_next == 0 {
+                    break;
+                }
+                aux_offset += vda_next as u64;
+            }
+
+            let mut names = names.into_iter();
+            defs.push(VersionDef {
+                flags,
+                index,
+                hash,
+                name: names.next().unwrap_or_default(),
+                parents: names.collect(),
+            });
+            if vd_next == 0 {
+                break;
+            }
+            offset += vd_next as u64;
+        }
+        Ok(defs)
+    }
+
+    /// Parse the SHT_GNU_VERSYM (`.gnu.version`) section.
+    ///
+    /// There is one entry per symbol in the dynamic symbol table.
+    pub fn get_version_symbols(&self) -> Result<Vec<u16>, ParseError> {
+        let mut indexes = Vec::new();
+        if let Some(section) = self.get_version_section(types::SectionType::GnuVersym) {
+            let mut io_section = io::Cursor::new(&section.data);
+            while (io_section.position() as usize) + 2 <= section.data.len() {
+                indexes.push(read_u16!(self, io_section)?);
+            }
+        }
+        Ok(indexes)
+    }
+
+    /// Resolve the version of each symbol in the dynamic symbol table.
+    ///
+    /// The result is indexed like the symbols returned by [File::get_symbols]
+    /// for the SHT_DYNSYM section. Local and unversioned symbols map to None.
+    pub fn get_symbol_versions(&self) -> Result<Vec<Option<SymbolVersion>>, ParseError> {
+        let needs = self.get_version_needs()?;
+        let defs = self.get_version_definitions()?;
+        let lookup = |index: u16| -> Option<SymbolVersion> {
+            let hidden = index & VERSYM_HIDDEN != 0;
+            let index = index & !VERSYM_HIDDEN;
+            if index == VER_NDX_LOCAL || index == VER_NDX_GLOBAL {
+                return None;
+            }
+            if let Some(def) = defs.iter().find(|def| def.index == index) {
+                return Some(SymbolVersion {
+                    name: def.name.clone(),
+                    file: None,
+                    hidden,
+                });
+            }
+            needs.iter().find_map(|need| {
+                need.versions
+                    .iter()
+                    .find(|aux| aux.index == index)
+                    .map(|aux| SymbolVersion {
+                        name: aux.name.clone(),
+                        file: Some(need.file.clone()),
+                        hidden,
+                    })
+            })
+        };
+        Ok(self
+            .get_version_symbols()?
+            .into_iter()
+            .map(lookup)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::File;
+
+    #[test]
+    fn test_version_needs() {
+        let file = File::open_path("tests/samples/x86_64_hardened").unwrap();
+        let needs = file.get_version_needs().unwrap();
+        assert_eq!(needs.len(), 2);
+        assert_eq!(needs[0].file, "libm.so.6");
+        assert_eq!(needs[0].versions[0].name, "GLIBC_2.2.5");
+
+        let dynsym = file.get_section(".dynsym").unwrap();
+        let symbols = file.get_symbols(dynsym).unwrap();
+        let versions = file.get_symbol_versions().unwrap();
+        assert_eq!(symbols.len(), versions.len());
+        let (_, version) = symbols
+            .iter()
+            .zip(versions.iter())
+            .find(|(sym, _)| sym.name == "__stack_chk_fail")
+            .unwrap();
+        let version = version.as_ref().unwrap();
+        assert_eq!(version.name, "GLIBC_2.4");
+        assert_eq!(version.file.as_deref(), Some("libc.so.6"));
+    }
+
+    #[test]
+    fn test_invalid_link() {
+        let mut file = File::open_path("tests/samples/x86_64_hardened").unwrap();
+        let section = file
+            .sections
+            .iter_mut()
+            .find(|section| section.shdr.name == ".gnu.version_r")
+            .unwrap();
+        section.shdr.link = 0xffff;
+        assert!(file.get_version_needs().is_err());
+    }
+}
@@ -0,0 +1,371 @@
+use crate::types;
+use crate::{File, ParseError};
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Narrow a value to a 32-bit ELF field.
+fn field32(value: u64, name: &str) -> Result<u32, ParseError> {
+    u32::try_from(value).map_err(|_| {
+        ParseError::InvalidFormat(Some(format!(
+            "{} {:#x} does not fit in a 32-bit ELF file",
+            name, value
+        )))
+    })
+}
+
+fn align_up(value: u64, align: u64) -> u64 {
+    if align <= 1 {
+        value
+    } else {
+        value.div_ceil(align) * align
+    }
+}
+
+impl File {
+    /// Write this file to the given path.
+    pub fn write_path<T: AsRef<Path>>(&self, path: T) -> Result<(), ParseError> {
+        let mut io_file = fs::File::create(path)?;
+        self.write_stream(&mut io_file)
+    }
+
+    /// Serialize this file.
+    ///
+    /// The program headers are written right after the ELF header. The
+    /// contents of PT_LOAD segments of a parsed file are copied first, so
+    /// bytes that no section covers are kept. Section data is then written
+    /// at each section's `offset`; sections with an offset of
+    /// zero are laid out after all other contents, aligned to their
+    /// `addralign`. The `.shstrtab` section, if present, is regenerated from
+    /// the section names, and the section header table is written last.
+    /// Header `offset` and `size` fields are written as laid out, so the
+    /// `File` itself is not modified.
+    pub fn write_stream<T: io::Write + io::Seek>(&self, io_file: &mut T) -> Result<(), ParseError> {
+        let (ehsize, phentsize, shentsize): (u64, u64, u64) = match self.header.class {
+            types::ElfClass::Format32 => (52, 32, 40),
+            types::ElfClass::Format64 => (64, 56, 64),
+        };
+        let phoff = if self.phdrs.is_empty() { 0 } else { ehsize };
+
+        // Build the section header string table
+        let shstrndx = self
+            .sections
+            .iter()
+            .position(|section| section.shdr.name == ".shstrtab");
+        let mut shstrtab = vec![0u8];
+        let mut name_offsets = Vec::with_capacity(self.sections.len());
+        for section in self.sections.iter() {
+            if section.shdr.name.is_empty() {
+                name_offsets.push(0u32);
+                continue;
+            }
+            name_offsets.push(shstrtab.len() as u32);
+            shstrtab.extend_from_slice(section.shdr.name.as_bytes());
+            shstrtab.push(0);
+        }
+
+        // Lay out sections that have no file offset yet
+        let mut end = phoff + phentsize * self.phdrs.len() as u64;
+        end = end.max(ehsize);
+        let segments: Vec<(u64, &[u8])> = self
+            .phdrs
+            .iter()
+            .filter(|phdr| phdr.progtype == types::PT_LOAD && phdr.filesz != 0)
+            .filter_map(|phdr| Some((phdr.offset, self.get_segment_bytes(phdr)?)))
+            .collect();
+        for &(offset, data) in segments.iter() {
+            end = end.max(offset + data.len() as u64);
+        }
+        for (i, section) in self.sections.iter().enumerate() {
+            if section.shdr.offset != 0 && Some(i) != shstrndx {
+                let size = if section.shdr.shtype == types::SectionType::Nobits {
+                    0
+                } else {
+                    section.data.len() as u64
+                };
+                end = end.max(section.shdr.offset + size);
+            }
+        }
+        let mut layout: Vec<(u64, u64)> = Vec::with_capacity(self.sections.len());
+        for (i, section) in self.sections.iter().enumerate() {
+            let nobits = section.shdr.shtype == types::SectionType::Nobits;
+            let size = if Some(i) == shstrndx {
+                shstrtab.len() as u64
+            } else if nobits {
+                section.shdr.size
+            } else {
+                section.data.len() as u64
+            };
+            let offset = if section.shdr.shtype == types::SectionType::Null {
+                0
+            } else if section.shdr.offset != 0 && Some(i) != shstrndx {
+                section.shdr.offset
+            } else if nobits {
+                end
+            } else {
+                let offset = align_up(end, section.shdr.addralign);
+                end = offset + size;
+                offset
+            };
+            layout.push((offset, size));
+        }
+        let shoff = if self.sections.is_empty() {
+            0
+        } else {
+            align_up(end, 8)
+        };
+
+        // Counts beyond the 16-bit limits use the initial section header
+        let phnum = self.phdrs.len();
+        let e_phnum = if phnum >= types::PN_XNUM as usize {
+            if self.sections.is_empty() {
+                return Err(ParseError::InvalidFormat(Some(format!(
+                    "{} program headers need a section header table",
+                    phnum
+                ))));
+            }
+            types::PN_XNUM
+        } else {
+            phnum as u16
+        };
+        let phnum = u32::try_from(phnum)
+            .map_err(|_| ParseError::InvalidFormat(Some(format!("{} program headers", phnum))))?;
+        let shnum = self.sections.len() as u64;
+        let (e_shnum, e_shstrndx) = (
+            if shnum >= types::SHN_LORESERVE as u64 {
+                0
+            } else {
+                shnum as u16
+            },
+            match shstrndx {
+                Some(index) if index >= types::SHN_LORESERVE as usize => types::SHN_XINDEX,
+                Some(index) => index as u16,
+                None => types::SHN_UNDEF,
+            },
+        );
+
+        // Segment contents, overwritten by the headers and sections below
+        for &(offset, data) in segments.iter() {
+            io_file.seek(io::SeekFrom::Start(offset))?;
+            io_file.write_all(data)?;
+        }
+
+        // ELF header
+        let mut ident = [0u8; types::EI_NIDENT];
+        ident[0] = types::ELFMAG0;
+        ident[1] = types::ELFMAG1;
+        ident[2] = types::ELFMAG2;
+        ident[3] = types::ELFMAG3;
+        ident[types::EI_CLASS] = self.header.class as u8;
+        ident[types::EI_DATA] = self.header.endianness as u8;
+        ident[types::EI_VERSION] = 1;
+        ident[types::EI_OSABI] = self.header.osabi.0;
+        ident[types::EI_ABIVERSION] = self.header.abiversion;
+        io_file.seek(io::SeekFrom::Start(0))?;
+        io_file.write_all(&ident)?;
+        write_u16!(self, io_file, self.header.elftype as u16)?;
+        write_u16!(self, io_file, self.header.cpu_architecture as u16)?;
+        write_u32!(self, io_file, 1)?;
+        match self.header.class {
+            types::ElfClass::Format32 => {
+                write_u32!(self, io_file, field32(self.header.entry, "Entry point")?)?;
+                write_u32!(self, io_file, field32(phoff, "Program header offset")?)?;
+                write_u32!(self, io_file, field32(shoff, "Section header offset")?)?;
+            }
+            types::ElfClass::Format64 => {
+                write_u64!(self, io_file, self.header.entry)?;
+                write_u64!(self, io_file, phoff)?;
+                write_u64!(self, io_file, shoff)?;
+            }
+        }
+        write_u32!(self, io_file, self.header.flags)?;
+        write_u16!(self, io_file, ehsize as u16)?;
+        write_u16!(self, io_file, phentsize as u16)?;
+        write_u16!(self, io_file, e_phnum)?;
+        write_u16!(self, io_file, shentsize as u16)?;
+        write_u16!(self, io_file, e_shnum)?;
+        write_u16!(self, io_file, e_shstrndx)?;
+
+        // Program headers
+        io_file.seek(io::SeekFrom::Start(phoff))?;
+        for phdr in self.phdrs.iter() {
+            write_u32!(self, io_file, phdr.progtype.0)?;
+            match self.header.class {
+                types::ElfClass::Format32 => {
+                    write_u32!(self, io_file, field32(phdr.offset, "Segment offset")?)?;
+                    write_u32!(self, io_file, field32(phdr.vaddr, "Segment address")?)?;
+                    write_u32!(self, io_file, field32(phdr.paddr, "Segment address")?)?;
+                    write_u32!(self, io_file, field32(phdr.filesz, "Segment size")?)?;
+                    write_u32!(self, io_file, field32(phdr.memsz, "Segment size")?)?;
+                    write_u32!(self, io_file, phdr.flags.0)?;
+                    write_u32!(self, io_file, field32(phdr.align, "Segment alignment")?)?;
+                }
+                types::ElfClass::Format64 => {
+                    write_u32!(self, io_file, phdr.flags.0)?;
+                    write_u64!(self, io_file, phdr.offset)?;
+                    write_u64!(self, io_file, phdr.vaddr)?;
+                    write_u64!(self, io_file, phdr.paddr)?;
+                    write_u64!(self, io_file, phdr.filesz)?;
+                    write_u64!(self, io_file, phdr.memsz)?;
+                    write_u64!(self, io_file, phdr.align)?;
+                }
+            }
+        }
+
+        // Section data
+        for (i, section) in self.sections.iter().enumerate() {
+            if section.shdr.shtype == types::SectionType::Nobits
+                || section.shdr.shtype == types::SectionType::Null
+            {
+                continue;
+            }
+            io_file.seek(io::SeekFrom::Start(layout[i].0))?;
+            if Some(i) == shstrndx {
+                io_file.write_all(&shstrtab)?;
+            } else {
+                io_file.write_all(&section.data)?;
+            }
+        }
+
+        // Section headers
+        io_file.seek(io::SeekFrom::Start(shoff))?;
+        for (i, section) in self.sections.iter().enumerate() {
+            let shdr = &section.shdr;
+            let (offset, mut size) = layout[i];
+            let mut link = shdr.link;
+            let mut info = shdr.info;
+            if i == 0 {
+                if e_phnum == types::PN_XNUM {
+                    info = phnum;
+                }
+                if e_shnum == 0 && shnum > 0 {
+                    size = shnum;
+                }
+                if e_shstrndx == types::SHN_XINDEX {
+                    link = shstrndx.unwrap_or(0) as u32;
+                }
+            }
+            write_u32!(self, io_file, name_offsets[i])?;
+            write_u32!(self, io_file, shdr.shtype as u32)?;
+            match self.header.class {
+                types::ElfClass::Format32 => {
+                    write_u32!(self, io_file, field32(shdr.flags.0, "Section flags")?)?;
+                    write_u32!(self, io_file, field32(shdr.addr, "Section address")?)?;
+                    write_u32!(self, io_file, field32(offset, "Section offset")?)?;
+                    write_u32!(self, io_file, field32(size, "Section size")?)?;
+                    write_u32!(self, io_file, link)?;
+                    write_u32!(self, io_file, info)?;
+                    write_u32!(self, io_file, field32(shdr.addralign, "Section alignment")?)?;
+                    write_u32!(self, io_file, field32(shdr.entsize, "Section entry size")?)?;
+                }
+                types::ElfClass::Format64 => {
+                    write_u64!(self, io_file, shdr.flags.0)?;
+                    write_u64!(self, io_file, shdr.addr)?;
+                    write_u64!(self, io_file, offset)?;
+                    write_u64!(self, io_file, size)?;
+                    write_u32!(self, io_file, link)?;
+                    write_u32!(self, io_file, info)?;
+                    write_u64!(self, io_file, shdr.addralign)?;
+                    write_u64!(self, io_file, shdr.entsize)?;
+                }
+            }
+        }
+        io_file.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types;
+    use crate::{File, Section};
+    use std::io;
+
+    fn round_trip(file: &File) -> File {
+        let mut buffer = io::Cursor::new(Vec::new());
+        file.write_stream(&mut buffer).unwrap();
+        buffer.set_position(0);
+        File::open_stream(&mut buffer).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let file = File::open_path("tests/samples/test1").unwrap();
+        let mut buffer = io::Cursor::new(Vec::new());
+        file.write_stream(&mut buffer).unwrap();
+
+        buffer.set_position(0);
+        let copy = File::open_stream(&mut buffer).unwrap();
+        assert_eq!(copy.header, file.header);
+        assert_eq!(copy.phdrs, file.phdrs);
+        assert_eq!(copy.sections.len(), file.sections.len());
+        // The regenerated .shstrtab does not share string suffixes
+        for (a, b) in copy.sections.iter().zip(file.sections.iter()) {
+            assert_eq!(a.shdr.name, b.shdr.name);
+            if a.shdr.name != ".shstrtab" {
+                assert_eq!(a.shdr, b.shdr);
+                assert_eq!(a.data, b.data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_segments_without_sections() {
+        let mut file = File::open_path("tests/samples/test1").unwrap();
+        file.sections.clear();
+        let copy = round_trip(&file);
+        assert_eq!(copy.phdrs, file.phdrs);
+        for phdr in file.phdrs.iter() {
+            if phdr.progtype == types::PT_LOAD {
+                // The ELF header is rewritten, the rest of the segment is copied
+                let skip = if phdr.offset == 0 { 64 } else { 0 };
+                assert_eq!(
+                    copy.get_segment_data(phdr)[skip..],
+                    file.get_segment_data(phdr)[skip..]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_header_limits() {
+        let mut file = File::new();
+        file.header.entry = 0x1_0000_0000;
+        let mut buffer = io::Cursor::new(Vec::new());
+        assert!(file.write_stream(&mut buffer).is_err());
+
+        // PN_XNUM program headers need the initial section header
+        let mut file = File::new();
+        let phdr = types::ProgramHeader {
+            progtype: types::PT_NULL,
+            offset: 0,
+            vaddr: 0,
+            paddr: 0,
+            filesz: 0,
+            memsz: 0,
+            flags: types::PF_NONE,
+            align: 0,
+        };
+        file.phdrs = vec![phdr; types::PN_XNUM as usize + 1];
+        assert!(file.write_stream(&mut buffer).is_err());
+        file.sections.push(Section {
+            shdr: types::SectionHeader {
+                name: String::new(),
+                shtype: types::SectionType::Null,
+                flags: types::SHF_NONE,
+                addr: 0,
+                offset: 0,
+                size: 0,
+                link: 0,
+                info: 0,
+                addralign: 0,
+                entsize: 0,
+            },
+            data: Vec::new(),
+        });
+        let copy = round_trip(&file);
+        assert_eq!(copy.phdrs.len(), types::PN_XNUM as usize + 1);
+        assert_eq!(copy.sections[0].shdr.info, types::PN_XNUM as u32 + 1);
+    }
+}